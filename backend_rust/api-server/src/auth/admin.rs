@@ -0,0 +1,25 @@
+use axum::http::{HeaderMap, HeaderName};
+use shared::AppConfig;
+
+/// Header carrying the configured admin token
+pub static X_ADMIN_TOKEN: HeaderName = HeaderName::from_static("x-admin-token");
+
+/// Whether a request presents the admin token; always false while no token
+/// is configured
+pub fn is_admin(headers: &HeaderMap, config: &AppConfig) -> bool {
+    let expected = config.app.admin_token.as_bytes();
+    if expected.is_empty() {
+        return false;
+    }
+
+    headers
+        .get(&X_ADMIN_TOKEN)
+        .map(|value| constant_time_eq(value.as_bytes(), expected))
+        .unwrap_or(false)
+}
+
+/// Compare without short-circuiting, so response timing doesn't reveal how
+/// much of the token matched
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
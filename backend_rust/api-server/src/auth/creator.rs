@@ -0,0 +1,49 @@
+use axum::http::{HeaderMap, HeaderName};
+use shared::{AppError, AppResult, Session};
+use uuid::Uuid;
+
+use crate::{
+    auth::jwt::{bearer_token, verify_token},
+    models::ParticipantRepository,
+    AppState,
+};
+
+/// Header carrying the creator ID returned when a session was created
+pub static X_CREATOR_ID: HeaderName = HeaderName::from_static("x-creator-id");
+
+/// Read the creator ID presented with a request, if any
+pub fn creator_id(headers: &HeaderMap) -> Option<Uuid> {
+    headers
+        .get(&X_CREATOR_ID)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| Uuid::parse_str(value.trim()).ok())
+}
+
+/// Whether a request comes from the session's creator
+///
+/// The original creator presents their creator ID (`X-Creator-Id`). Once
+/// ownership has been transferred to a participant, the creator ID is that
+/// participant's user ID, which the whole session can see, so the new owner
+/// must present their participant token (`Authorization: Bearer`) instead.
+pub async fn is_session_creator(state: &AppState, session: &Session, headers: &HeaderMap) -> AppResult<bool> {
+    let owner = session.creator_id.to_string();
+
+    if let Some(token) = bearer_token(headers) {
+        if let Ok(claims) = verify_token(token, &state.config.jwt) {
+            if claims.session_id == session.id && claims.sub == owner {
+                return Ok(true);
+            }
+        }
+    }
+
+    if creator_id(headers) != Some(session.creator_id) {
+        return Ok(false);
+    }
+
+    let participant_repo = ParticipantRepository::new(state.db.clone());
+    match participant_repo.get_participant(session.id, &owner).await {
+        Ok(_) => Ok(false),
+        Err(AppError::ParticipantNotFound) => Ok(true),
+        Err(e) => Err(e),
+    }
+}
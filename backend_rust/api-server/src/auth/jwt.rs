@@ -0,0 +1,210 @@
+use axum::http::{header, HeaderMap};
+use chrono::{DateTime, Duration, Utc};
+use jsonwebtoken::errors::ErrorKind;
+use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header};
+use shared::{AppError, AppResult, Constants, JwtClaims, JwtConfig, LocationPrecision, ParticipantRole};
+use tracing::debug;
+use uuid::Uuid;
+
+/// Issue a WebSocket token for a participant, returning it with its expiry
+///
+/// The token carries the participant's location precision and role so the
+/// WebSocket server can apply them without a database lookup, and the configured issuer
+/// and audience so it is only accepted by this deployment.
+pub fn issue_websocket_token(
+    user_id: &str,
+    session_id: Uuid,
+    precision: LocationPrecision,
+    role: ParticipantRole,
+    config: &JwtConfig,
+) -> AppResult<(String, DateTime<Utc>)> {
+    let now = Utc::now();
+    let expires_at = now + Duration::hours(Constants::WS_TOKEN_DURATION_HOURS);
+
+    let claims = JwtClaims {
+        sub: user_id.to_string(),
+        session_id,
+        exp: expires_at.timestamp(),
+        iat: now.timestamp(),
+        precision,
+        role,
+        iss: Some(config.issuer.clone()),
+        aud: Some(config.audience.clone()),
+    };
+
+    let token = encode(
+        &Header::new(signing_algorithm(config)?),
+        &claims,
+        &EncodingKey::from_secret(config.secret.as_ref()),
+    )?;
+
+    debug!("Issued WebSocket token for user {} in session {}", user_id, session_id);
+    Ok((token, expires_at))
+}
+
+/// Verify a token presented for refresh
+///
+/// Accepts tokens that expired less than `grace` ago, so a participant whose
+/// token lapsed while disconnected can still renew it. The configured
+/// clock-skew leeway applies on top of `grace`.
+pub fn verify_refreshable_token(
+    token: &str,
+    config: &JwtConfig,
+    grace: Duration,
+) -> AppResult<JwtClaims> {
+    let mut validation = config
+        .validation()
+        .map_err(|e| AppError::Internal(anyhow::anyhow!(e)))?;
+    validation.leeway = config.leeway_seconds + grace.num_seconds().max(0) as u64;
+
+    let token_data = decode::<JwtClaims>(
+        token,
+        &DecodingKey::from_secret(config.secret.as_ref()),
+        &validation,
+    )
+    .map_err(|e| match e.kind() {
+        ErrorKind::ExpiredSignature => AppError::TokenExpired,
+        _ => AppError::InvalidToken,
+    })?;
+
+    // Reject tokens claiming to be issued in the future
+    let claims = token_data.claims;
+    if claims.iat > Utc::now().timestamp() + config.leeway_seconds as i64 {
+        return Err(AppError::InvalidToken);
+    }
+
+    Ok(claims)
+}
+
+/// Verify an unexpired WebSocket token
+pub fn verify_token(token: &str, config: &JwtConfig) -> AppResult<JwtClaims> {
+    verify_refreshable_token(token, config, Duration::zero())
+}
+
+/// Extract the token from an `Authorization: Bearer` header
+pub fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim)
+}
+
+/// Resolve the configured signing algorithm
+pub fn signing_algorithm(config: &JwtConfig) -> AppResult<Algorithm> {
+    config
+        .signing_algorithm()
+        .map_err(|e| AppError::Internal(anyhow::anyhow!(e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::AppConfig;
+
+    fn config() -> JwtConfig {
+        JwtConfig {
+            secret: "test_secret".to_string(),
+            ..AppConfig::default().jwt
+        }
+    }
+
+    fn token_expiring_at(exp: i64) -> String {
+        let claims = JwtClaims {
+            sub: "test_user".to_string(),
+            session_id: Uuid::new_v4(),
+            exp,
+            iat: exp - 3600,
+            precision: LocationPrecision::Exact,
+            role: ParticipantRole::Publisher,
+            iss: None,
+            aud: None,
+        };
+        encode(&Header::default(), &claims, &EncodingKey::from_secret(config().secret.as_ref())).unwrap()
+    }
+
+    #[test]
+    fn test_issue_and_verify_token() {
+        let session_id = Uuid::new_v4();
+        let (token, expires_at) = issue_websocket_token("test_user", session_id, LocationPrecision::Exact, ParticipantRole::Publisher, &config()).unwrap();
+
+        let claims = verify_refreshable_token(&token, &config(), Duration::zero()).unwrap();
+        assert_eq!(claims.sub, "test_user");
+        assert_eq!(claims.session_id, session_id);
+        assert_eq!(claims.exp, expires_at.timestamp());
+        assert_eq!(claims.iss.as_deref(), Some(config().issuer.as_str()));
+        assert_eq!(claims.aud.as_deref(), Some(config().audience.as_str()));
+    }
+
+    #[test]
+    fn test_refresh_grace_window() {
+        let token = token_expiring_at((Utc::now() - Duration::minutes(10)).timestamp());
+
+        assert!(verify_refreshable_token(&token, &config(), Duration::minutes(60)).is_ok());
+        assert!(matches!(
+            verify_refreshable_token(&token, &config(), Duration::minutes(5)),
+            Err(AppError::TokenExpired)
+        ));
+    }
+
+    #[test]
+    fn test_reject_tampered_token() {
+        let token = token_expiring_at((Utc::now() + Duration::hours(1)).timestamp());
+        let other = JwtConfig {
+            secret: "other_secret".to_string(),
+            ..config()
+        };
+
+        assert!(matches!(
+            verify_refreshable_token(&token, &other, Duration::zero()),
+            Err(AppError::InvalidToken)
+        ));
+    }
+
+    #[test]
+    fn test_configured_algorithm_round_trip() {
+        let session_id = Uuid::new_v4();
+        let hs512 = JwtConfig {
+            algorithm: "HS512".to_string(),
+            ..config()
+        };
+        let (token, _) = issue_websocket_token("test_user", session_id, LocationPrecision::Exact, ParticipantRole::Publisher, &hs512).unwrap();
+
+        let claims = verify_refreshable_token(&token, &hs512, Duration::zero()).unwrap();
+        assert_eq!(claims.session_id, session_id);
+
+        assert!(matches!(
+            verify_refreshable_token(&token, &config(), Duration::zero()),
+            Err(AppError::InvalidToken)
+        ));
+    }
+
+    #[test]
+    fn test_audience_mismatch_rejected() {
+        let (token, _) = issue_websocket_token("test_user", Uuid::new_v4(), LocationPrecision::Exact, ParticipantRole::Publisher, &config()).unwrap();
+        let other = JwtConfig {
+            audience: "some-other-service".to_string(),
+            ..config()
+        };
+
+        assert!(matches!(
+            verify_token(&token, &other),
+            Err(AppError::InvalidToken)
+        ));
+    }
+
+    #[test]
+    fn test_tokens_without_issuer_audience() {
+        let token = token_expiring_at((Utc::now() + Duration::hours(1)).timestamp());
+        let strict = JwtConfig {
+            require_issuer_audience: true,
+            ..config()
+        };
+
+        assert!(verify_token(&token, &config()).is_ok());
+        assert!(matches!(
+            verify_token(&token, &strict),
+            Err(AppError::InvalidToken)
+        ));
+    }
+}
@@ -0,0 +1,3 @@
+pub mod admin;
+pub mod creator;
+pub mod jwt;
@@ -1,15 +1,29 @@
-use shared::{AppConfig, AppError, AppResult};
+use shared::{connect_with_retry, AppConfig, AppError, AppResult};
 use sqlx::{PgPool, Row};
 use tracing::info;
 
 /// Create a PostgreSQL connection pool
+///
+/// Postgres may still be starting when the server comes up (e.g. under
+/// docker-compose), so failed connections are retried with backoff. With
+/// `connect_lazy` set, no connection is made until the pool is first used;
+/// `/health/ready` reports whether the database is reachable.
 pub async fn create_pool(config: &AppConfig) -> AppResult<PgPool> {
+    if config.database.connect_lazy {
+        info!("PostgreSQL connections will be opened on first use");
+        return Ok(config
+            .database_pool_options()
+            .connect_lazy_with(config.database_options()));
+    }
+
     info!("Connecting to PostgreSQL database...");
     
-    let pool = config
-        .database_pool_options()
-        .connect_with(config.database_options())
-        .await?;
+    let pool = connect_with_retry(&config.database, || {
+        config
+            .database_pool_options()
+            .connect_with(config.database_options())
+    })
+    .await?;
 
     // Test the connection
     let row: (i32,) = sqlx::query_as("SELECT 1")
@@ -85,4 +99,4 @@ pub struct DatabaseStats {
     pub total_sessions: i64,
     pub active_participants: i64,
     pub total_participants: i64,
-}
\ No newline at end of file
+}
@@ -1,5 +1,5 @@
 use axum::{
-    http::StatusCode,
+    http::{header, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
@@ -48,13 +48,52 @@ impl IntoResponse for ApiError {
             }
         }
 
-        let error_response = json!({
+        let mut error_response = json!({
             "error": {
                 "code": self.0.error_code(),
                 "message": self.0.to_string()
             }
         });
 
-        (status_code, Json(error_response)).into_response()
+        // List every invalid field, so clients can fix them all in one go
+        let field_errors = self.0.field_errors();
+        if !field_errors.is_empty() {
+            error_response["error"]["details"] = json!(field_errors);
+        }
+
+        let mut response = (status_code, Json(error_response)).into_response();
+
+        // Tell rate-limited clients how long to back off
+        if let Some(retry_after) = self.0.retry_after_seconds() {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+        }
+
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_limit_response_has_retry_after() {
+        let response = ApiError(AppError::rate_limit_exceeded(30)).into_response();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+
+        let retry_after = response
+            .headers()
+            .get(header::RETRY_AFTER)
+            .expect("Retry-After header missing");
+        assert_eq!(retry_after.to_str().unwrap().parse::<u64>().unwrap(), 30);
+    }
+
+    #[test]
+    fn test_other_errors_have_no_retry_after() {
+        let response = ApiError(AppError::SessionNotFound).into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert!(response.headers().get(header::RETRY_AFTER).is_none());
     }
 }
\ No newline at end of file
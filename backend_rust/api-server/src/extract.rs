@@ -0,0 +1,177 @@
+use axum::{
+    async_trait,
+    extract::{rejection::JsonRejection, ConnectInfo, FromRequest, FromRequestParts, Request},
+    http::{request::Parts, HeaderMap},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use shared::{camel_case_keys, AppConfig, AppError, IpRange};
+use std::convert::Infallible;
+use std::net::{IpAddr, SocketAddr};
+
+use crate::{error::ApiError, AppState};
+
+/// JSON request body that reports malformed input in the standard error
+/// envelope
+///
+/// Syntax errors and bodies that don't match the expected shape become
+/// `INVALID_REQUEST` errors whose message names the offending field when
+/// serde can tell; other rejections (missing content type, oversized body)
+/// keep axum's response.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ApiJson<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for ApiJson<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        match Json::<T>::from_request(req, state).await {
+            Ok(Json(value)) => Ok(Self(value)),
+            Err(rejection @ (JsonRejection::JsonDataError(_) | JsonRejection::JsonSyntaxError(_))) => {
+                Err(ApiError(AppError::invalid_request(&rejection.body_text())).into_response())
+            }
+            Err(rejection) => Err(rejection.into_response()),
+        }
+    }
+}
+
+/// JSON response body with field names in the configured case
+///
+/// DTOs serialize as snake_case; with `app.json_field_case = "camelCase"`
+/// their keys are renamed (`session_id` becomes `sessionId`) on the way out.
+pub struct CasedJson<T> {
+    value: T,
+    camel_case: bool,
+}
+
+impl<T> CasedJson<T> {
+    pub fn new(value: T, config: &AppConfig) -> Self {
+        Self {
+            value,
+            camel_case: config.is_camel_case_json(),
+        }
+    }
+}
+
+impl<T: Serialize> IntoResponse for CasedJson<T> {
+    fn into_response(self) -> Response {
+        if !self.camel_case {
+            return Json(self.value).into_response();
+        }
+
+        match serde_json::to_value(&self.value) {
+            Ok(mut value) => {
+                camel_case_keys(&mut value);
+                Json(value).into_response()
+            }
+            Err(e) => ApiError(AppError::from(e)).into_response(),
+        }
+    }
+}
+
+/// IP address of the client behind a request, or `None` if unknown
+///
+/// This is the socket peer, unless the peer is a trusted proxy
+/// (`server.trusted_proxies`) or there is no peer IP at all, as on a Unix
+/// socket that only a local proxy can reach. Then `X-Forwarded-For` is
+/// read right to left, skipping trusted proxies, falling back to
+/// `X-Real-IP` and then the peer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientIp(pub Option<IpAddr>);
+
+#[async_trait]
+impl FromRequestParts<AppState> for ClientIp {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let peer = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip().to_canonical());
+        let trusted = state.config.server.trusted_proxy_ranges();
+
+        Ok(Self(client_ip(&parts.headers, peer, &trusted)))
+    }
+}
+
+fn client_ip(headers: &HeaderMap, peer: Option<IpAddr>, trusted: &[IpRange]) -> Option<IpAddr> {
+    let is_trusted = |ip: IpAddr| trusted.iter().any(|range| range.contains(ip));
+    if peer.is_some_and(|peer| !is_trusted(peer)) {
+        return peer;
+    }
+
+    let header_ip = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+    let forwarded: Vec<IpAddr> = header_ip("x-forwarded-for")
+        .into_iter()
+        .flat_map(|value| value.split(','))
+        .filter_map(|ip| ip.trim().parse::<IpAddr>().ok())
+        .map(|ip| ip.to_canonical())
+        .collect();
+
+    // Each proxy appends the address it saw, so the nearest untrusted
+    // entry is the client; anything before it could be spoofed
+    forwarded
+        .iter()
+        .rev()
+        .find(|&&ip| !is_trusted(ip))
+        .or(forwarded.first())
+        .copied()
+        .or_else(|| header_ip("x-real-ip").and_then(|ip| ip.trim().parse().ok()))
+        .or(peer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.parse().unwrap(), value.parse().unwrap()))
+            .collect()
+    }
+
+    fn ip(ip: &str) -> Option<IpAddr> {
+        Some(ip.parse().unwrap())
+    }
+
+    #[test]
+    fn test_direct_connection_uses_socket_ip() {
+        let trusted = ["10.0.0.0/8".parse().unwrap()];
+        assert_eq!(client_ip(&HeaderMap::new(), ip("203.0.113.7"), &trusted), ip("203.0.113.7"));
+    }
+
+    #[test]
+    fn test_trusted_proxy_forwards_client_ip() {
+        let trusted = ["10.0.0.0/8".parse().unwrap()];
+        let peer = ip("10.0.0.2");
+
+        let forwarded = headers(&[("x-forwarded-for", "198.51.100.4")]);
+        assert_eq!(client_ip(&forwarded, peer, &trusted), ip("198.51.100.4"));
+
+        // A client-supplied entry in front of the real one is skipped, as is
+        // an inner proxy in the chain
+        let chained = headers(&[("x-forwarded-for", "1.2.3.4, 198.51.100.4, 10.0.0.9")]);
+        assert_eq!(client_ip(&chained, peer, &trusted), ip("198.51.100.4"));
+
+        let real_ip = headers(&[("x-real-ip", "198.51.100.5")]);
+        assert_eq!(client_ip(&real_ip, peer, &trusted), ip("198.51.100.5"));
+
+        assert_eq!(client_ip(&HeaderMap::new(), peer, &trusted), peer);
+    }
+
+    #[test]
+    fn test_untrusted_peer_ignores_forwarded_headers() {
+        let spoofed = headers(&[("x-forwarded-for", "198.51.100.4"), ("x-real-ip", "198.51.100.5")]);
+        assert_eq!(client_ip(&spoofed, ip("203.0.113.7"), &[]), ip("203.0.113.7"));
+
+        // Without a peer IP (a Unix socket) the local proxy's headers count
+        assert_eq!(client_ip(&spoofed, None, &[]), ip("198.51.100.4"));
+    }
+}
@@ -0,0 +1,66 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::HeaderMap,
+    Json,
+};
+use shared::{AdminSessionSummary, AdminSessionsQuery, AppError, AuditEvent, Paginated};
+use crate::error::ApiError;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+use crate::{
+    auth::admin::is_admin,
+    models::{AuditRepository, SessionRepository},
+    AppState,
+};
+
+/// List sessions with their participant counts and last activity
+///
+/// Requires the configured admin token in `X-Admin-Token`. With
+/// `?active_only=true` ended and expired sessions are left out; `?sort=`
+/// is `last_activity_desc` (the default) or `last_activity_asc`, and
+/// `?tag=` keeps only sessions carrying that tag.
+pub async fn list_sessions(
+    State(state): State<AppState>,
+    Query(query): Query<AdminSessionsQuery>,
+    headers: HeaderMap,
+) -> Result<Json<Paginated<AdminSessionSummary>>, ApiError> {
+    if !is_admin(&headers, &state.config) {
+        warn!("Rejected admin session list without a valid admin token");
+        return Err(ApiError(AppError::InvalidToken));
+    }
+
+    let tag = query.tag.map(|tag| tag.trim().to_lowercase());
+    let session_repo = SessionRepository::new(state.db.clone());
+    let sessions = session_repo
+        .list_session_summaries(query.active_only, query.sort, tag.as_deref())
+        .await
+        .map_err(ApiError)?;
+
+    debug!("Listing {} sessions for admin", sessions.len());
+
+    Ok(Json(Paginated::all(sessions)))
+}
+
+/// List a session's audit events, oldest first
+///
+/// Requires the configured admin token in `X-Admin-Token`. Events are kept
+/// after the session ends or expires, so this works for any session ID that
+/// was ever used.
+pub async fn list_audit_events(
+    State(state): State<AppState>,
+    Path(session_id): Path<Uuid>,
+    headers: HeaderMap,
+) -> Result<Json<Paginated<AuditEvent>>, ApiError> {
+    if !is_admin(&headers, &state.config) {
+        warn!("Rejected admin audit log request without a valid admin token");
+        return Err(ApiError(AppError::InvalidToken));
+    }
+
+    let audit_repo = AuditRepository::new(state.db.clone());
+    let events = audit_repo.list_events(session_id).await.map_err(ApiError)?;
+
+    debug!("Listing {} audit events for session {}", events.len(), session_id);
+
+    Ok(Json(Paginated::all(events)))
+}
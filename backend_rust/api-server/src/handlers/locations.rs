@@ -0,0 +1,438 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, HeaderMap},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{Duration, Utc};
+use shared::{
+    average_speed, calculate_distance, centroid, estimate_eta_seconds, is_duplicate_location, is_too_soon,
+    max_speed, path_distance,
+    AppError, AppResult, Constants, IngestLocationEntry, IngestLocationsRequest,
+    IngestLocationsResponse, IngestedLocationResult, Location, LocationBroadcastData, Participant,
+    ParticipantResponse, ParticipantRole, ParticipantStatsQuery, ParticipantStatsResponse,
+    SessionCentroidResponse, SessionLocationResponse, SessionLocationsResponse, WebSocketMessage,
+};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use crate::error::ApiError;
+use crate::extract::ApiJson;
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+use crate::{
+    auth::{
+        creator::is_session_creator,
+        jwt::{bearer_token, verify_token},
+    },
+    models::{LocationHistoryRepository, ParticipantRepository, SessionRepository},
+    AppState,
+};
+
+/// Get a snapshot of every current location in a session
+///
+/// Authorized by an active participant's token (`Authorization: Bearer`) or
+/// the session creator's ID (`X-Creator-Id`). Stale locations and locations
+/// of participants who have left are omitted.
+pub async fn get_session_locations(
+    State(state): State<AppState>,
+    Path(session_id): Path<Uuid>,
+    headers: HeaderMap,
+) -> Result<Json<SessionLocationsResponse>, ApiError> {
+    debug!("Fetching location snapshot for session {}", session_id);
+
+    authorize_location_read(&state, session_id, &headers).await?;
+
+    let mut locations: Vec<SessionLocationResponse> = current_locations(&state, session_id)
+        .await?
+        .into_iter()
+        .map(|(profile, location)| SessionLocationResponse {
+            location: LocationBroadcastData {
+                user_id: profile.user_id,
+                lat: location.lat,
+                lng: location.lng,
+                accuracy: location.accuracy,
+                timestamp: location.timestamp,
+            },
+            display_name: profile.display_name,
+            avatar_color: profile.avatar_color,
+        })
+        .collect();
+    locations.sort_by(|a, b| a.location.user_id.cmp(&b.location.user_id));
+
+    debug!("Returning {} locations for session {}", locations.len(), session_id);
+
+    Ok(Json(SessionLocationsResponse { locations }))
+}
+
+/// Push locations for many participants at once, for server-side publishers
+/// such as fleet backends that don't hold a WebSocket per device
+///
+/// Only the session creator may do this. Each entry is checked like a
+/// WebSocket `location_update` from that participant, who must be an active
+/// publisher, then stored and broadcast to connected peers through pub/sub.
+/// That includes the minimum update interval and, for an entry repeating the
+/// stored location, refreshing it without a broadcast. Entries are processed
+/// in order and failures reported per entry rather than failing the whole
+/// request. In durable mode accepted locations are
+/// also written to the `location_history` table in one insert.
+pub async fn ingest_locations(
+    State(state): State<AppState>,
+    Path(session_id): Path<Uuid>,
+    headers: HeaderMap,
+    ApiJson(request): ApiJson<IngestLocationsRequest>,
+) -> Result<Json<IngestLocationsResponse>, ApiError> {
+    debug!("Ingesting {} locations for session {}", request.locations.len(), session_id);
+
+    request.validate().map_err(|msg| ApiError(AppError::validation("locations", &msg)))?;
+
+    let session_repo = SessionRepository::new(state.db.clone());
+    let session = session_repo.get_session(session_id).await.map_err(ApiError)?;
+
+    if !is_session_creator(&state, &session, &headers).await.map_err(ApiError)? {
+        warn!("Non-creator attempted to ingest locations for session {}", session_id);
+        return Err(ApiError(AppError::UnauthorizedSessionOperation));
+    }
+
+    let participant_repo = ParticipantRepository::new(state.db.clone());
+    let participants: HashMap<String, Participant> = participant_repo
+        .get_all_participants_for_session(session_id)
+        .await
+        .map_err(ApiError)?
+        .into_iter()
+        .filter(|participant| participant.is_active)
+        .map(|participant| (participant.user_id.clone(), participant))
+        .collect();
+
+    let mut results = Vec::with_capacity(request.locations.len());
+    let mut accepted = Vec::new();
+    for entry in request.locations {
+        let error = match ingest_location(&state, session_id, participants.get(&entry.user_id), &entry).await {
+            Ok(location) => {
+                accepted.extend(location.map(|location| (entry.user_id.clone(), location)));
+                None
+            }
+            Err(e) => {
+                debug!("Rejected location for user {} in session {}: {}", entry.user_id, session_id, e);
+                Some(e.error_code().to_string())
+            }
+        };
+        results.push(IngestedLocationResult {
+            user_id: entry.user_id,
+            accepted: error.is_none(),
+            error,
+        });
+    }
+
+    // Redis already holds the accepted locations, so a failed write doesn't
+    // fail the request
+    if state.config.app.persist_locations && !accepted.is_empty() {
+        let history_repo = LocationHistoryRepository::new(state.db.clone());
+        if let Err(e) = history_repo.record(session_id, &accepted).await {
+            error!("Failed to write {} ingested locations to the durable history: {}", accepted.len(), e);
+        }
+    }
+
+    info!(
+        "Ingested {} of {} locations for session {}",
+        accepted.len(),
+        results.len(),
+        session_id
+    );
+
+    Ok(Json(IngestLocationsResponse { results }))
+}
+
+/// Check, store and broadcast one ingested location, returning it as stored
+/// unless it only repeated the stored location
+async fn ingest_location(
+    state: &AppState,
+    session_id: Uuid,
+    participant: Option<&Participant>,
+    entry: &IngestLocationEntry,
+) -> AppResult<Option<Location>> {
+    let participant = participant.ok_or(AppError::ParticipantNotFound)?;
+    if participant.role == ParticipantRole::Viewer {
+        return Err(AppError::InsufficientPermissions);
+    }
+
+    entry.validate().map_err(|message| AppError::InvalidLocation { message })?;
+    if !entry.as_update().is_accurate_enough(state.config.app.max_accuracy_meters) {
+        return Err(AppError::InvalidLocation {
+            message: "Location accuracy is too low".to_string(),
+        });
+    }
+
+    // Like a delayed WebSocket update, an older fix must not overwrite a
+    // newer one, and fixes closer together than the deployment keeps are dropped
+    let previous = state.redis.get_location(&session_id, &entry.user_id).await?;
+    if let Some(stored) = &previous {
+        if entry.timestamp < stored.timestamp {
+            return Err(AppError::InvalidLocation {
+                message: "Location is older than the latest one received".to_string(),
+            });
+        }
+        if is_too_soon(stored, entry.timestamp, state.config.app.min_update_interval_ms) {
+            return Err(AppError::InvalidLocation {
+                message: "Location is within the minimum update interval".to_string(),
+            });
+        }
+    }
+
+    let location = participant.location_precision.apply(&Location {
+        lat: entry.lat,
+        lng: entry.lng,
+        accuracy: entry.accuracy,
+        timestamp: entry.timestamp,
+    });
+
+    // A resent point only needs its TTL refreshed, as on the WebSocket path
+    if state.config.app.dedup_identical_locations
+        && previous.as_ref().is_some_and(|previous| is_duplicate_location(previous, &location))
+    {
+        state.redis.refresh_location(&session_id, &entry.user_id, &location).await?;
+        state.redis.touch_participant(&session_id, &entry.user_id).await?;
+        return Ok(None);
+    }
+
+    state.redis.record_location(&session_id, &entry.user_id, &location).await?;
+    state.redis.touch_participant(&session_id, &entry.user_id).await?;
+
+    let message = WebSocketMessage::LocationBroadcast(LocationBroadcastData {
+        user_id: entry.user_id.clone(),
+        lat: location.lat,
+        lng: location.lng,
+        accuracy: location.accuracy,
+        timestamp: location.timestamp,
+    });
+    state.redis.publish_to_session(&session_id, &serde_json::to_string(&message)?).await?;
+
+    Ok(Some(location))
+}
+
+/// Get the center of a session's current locations and how spread out
+/// they are around it
+///
+/// Authorized like [`get_session_locations`] and computed from the same
+/// locations. The body is `null` while nobody is sharing a location, and
+/// for locations balanced on opposite sides of the globe.
+pub async fn get_session_centroid(
+    State(state): State<AppState>,
+    Path(session_id): Path<Uuid>,
+    headers: HeaderMap,
+) -> Result<Json<Option<SessionCentroidResponse>>, ApiError> {
+    debug!("Computing centroid for session {}", session_id);
+
+    authorize_location_read(&state, session_id, &headers).await?;
+
+    let points: Vec<Location> = current_locations(&state, session_id)
+        .await?
+        .into_iter()
+        .map(|(_, location)| location)
+        .collect();
+    let Some((lat, lng)) = centroid(&points) else {
+        return Ok(Json(None));
+    };
+
+    let distances: Vec<f64> = points
+        .iter()
+        .map(|point| calculate_distance(lat, lng, point.lat, point.lng))
+        .collect();
+
+    Ok(Json(Some(SessionCentroidResponse {
+        lat,
+        lng,
+        location_count: points.len(),
+        average_distance_meters: distances.iter().sum::<f64>() / distances.len() as f64,
+        max_distance_meters: distances.iter().copied().fold(0.0, f64::max),
+    })))
+}
+
+/// Unexpired locations of a session's active participants, with their profiles
+async fn current_locations(
+    state: &AppState,
+    session_id: Uuid,
+) -> Result<Vec<(ParticipantResponse, Location)>, ApiError> {
+    let participant_repo = ParticipantRepository::new(state.db.clone());
+    let mut profiles: HashMap<String, _> = participant_repo
+        .list_participants(session_id)
+        .await
+        .map_err(ApiError)?
+        .into_iter()
+        .map(|participant| (participant.user_id.clone(), participant))
+        .collect();
+
+    let cutoff = Utc::now() - Duration::seconds(Constants::LOCATION_TTL_SECONDS as i64);
+    let locations = state
+        .redis
+        .get_session_locations(&session_id)
+        .await
+        .map_err(ApiError)?
+        .into_iter()
+        .filter(|(_, location)| location.timestamp >= cutoff)
+        .filter_map(|(user_id, location)| Some((profiles.remove(&user_id)?, location)))
+        .collect();
+
+    Ok(locations)
+}
+
+/// Get distance traveled, speeds and an optional ETA for a participant
+///
+/// Computed from the participant's location history and authorized like
+/// [`get_session_locations`]. With `?dest_lat=&dest_lng=`, the ETA is the
+/// straight-line distance from their latest location at their average speed
+/// over the last few minutes; it is omitted while they aren't moving.
+pub async fn get_participant_stats(
+    State(state): State<AppState>,
+    Path((session_id, user_id)): Path<(Uuid, String)>,
+    Query(query): Query<ParticipantStatsQuery>,
+    headers: HeaderMap,
+) -> Result<Json<ParticipantStatsResponse>, ApiError> {
+    debug!("Computing stats for participant {} in session {}", user_id, session_id);
+
+    let destination = query
+        .destination()
+        .map_err(|msg| ApiError(AppError::validation("destination", &msg)))?;
+
+    authorize_location_read(&state, session_id, &headers).await?;
+
+    let participant_repo = ParticipantRepository::new(state.db.clone());
+    participant_repo.get_participant(session_id, &user_id).await.map_err(ApiError)?;
+
+    let history = location_history(&state, session_id, &user_id).await.map_err(ApiError)?;
+
+    let eta_seconds = destination.zip(history.last()).and_then(|((lat, lng), last)| {
+        let window_start = last.timestamp - Duration::seconds(Constants::ETA_SPEED_WINDOW_SECONDS);
+        let recent_start = history.partition_point(|point| point.timestamp < window_start);
+        let remaining = calculate_distance(last.lat, last.lng, lat, lng);
+        estimate_eta_seconds(remaining, average_speed(&history[recent_start..]))
+    });
+
+    Ok(Json(ParticipantStatsResponse {
+        point_count: history.len(),
+        distance_meters: path_distance(&history),
+        average_speed_mps: average_speed(&history),
+        max_speed_mps: max_speed(&history),
+        eta_seconds,
+        user_id,
+    }))
+}
+
+/// Export every participant's recorded path as a GeoJSON `FeatureCollection`
+///
+/// Creator only, and still available once the session has ended, for
+/// looking back at an event. Each participant with history becomes a
+/// `LineString` (a `Point` if only one location was recorded).
+pub async fn get_session_track(
+    State(state): State<AppState>,
+    Path(session_id): Path<Uuid>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    debug!("Exporting track for session {}", session_id);
+
+    let session_repo = SessionRepository::new(state.db.clone());
+    let session = session_repo.find_session(session_id).await.map_err(ApiError)?;
+
+    if !is_session_creator(&state, &session, &headers).await.map_err(ApiError)? {
+        warn!("Non-creator attempted to export the track of session {}", session_id);
+        return Err(ApiError(AppError::UnauthorizedSessionOperation));
+    }
+
+    let participant_repo = ParticipantRepository::new(state.db.clone());
+    let mut tracks = Vec::new();
+    for participant in participant_repo
+        .get_all_participants_for_session(session_id)
+        .await
+        .map_err(ApiError)?
+    {
+        let history = location_history(&state, session_id, &participant.user_id)
+            .await
+            .map_err(ApiError)?;
+        tracks.push((participant, history));
+    }
+
+    let geojson = track_feature_collection(&tracks);
+    Ok(([(header::CONTENT_TYPE, "application/geo+json")], Json(geojson)).into_response())
+}
+
+/// A participant's location history, oldest first
+///
+/// Read from the `location_history` table in durable mode, which keeps every
+/// accepted location, and from the capped history in Redis otherwise.
+async fn location_history(state: &AppState, session_id: Uuid, user_id: &str) -> AppResult<Vec<Location>> {
+    if state.config.app.persist_locations {
+        LocationHistoryRepository::new(state.db.clone())
+            .get_history(session_id, user_id)
+            .await
+    } else {
+        state.redis.get_location_history(&session_id, user_id).await
+    }
+}
+
+/// Build a FeatureCollection from each participant's history, oldest first
+fn track_feature_collection(tracks: &[(Participant, Vec<Location>)]) -> Value {
+    let features: Vec<Value> = tracks
+        .iter()
+        .filter(|(_, history)| !history.is_empty())
+        .map(|(participant, history)| {
+            // GeoJSON positions are [longitude, latitude]
+            let mut positions: Vec<Value> = history
+                .iter()
+                .map(|point| json!([point.lng, point.lat]))
+                .collect();
+            let geometry = if positions.len() == 1 {
+                json!({ "type": "Point", "coordinates": positions.remove(0) })
+            } else {
+                json!({ "type": "LineString", "coordinates": positions })
+            };
+
+            json!({
+                "type": "Feature",
+                "geometry": geometry,
+                "properties": {
+                    "user_id": participant.user_id,
+                    "display_name": participant.display_name,
+                    "color": participant.avatar_color,
+                },
+            })
+        })
+        .collect();
+
+    json!({ "type": "FeatureCollection", "features": features })
+}
+
+/// Allow the session creator (`X-Creator-Id`) or an active participant
+/// (`Authorization: Bearer`) to read a session's locations
+async fn authorize_location_read(
+    state: &AppState,
+    session_id: Uuid,
+    headers: &HeaderMap,
+) -> Result<(), ApiError> {
+    let session_repo = SessionRepository::new(state.db.clone());
+    let session = session_repo.get_session(session_id).await.map_err(ApiError)?;
+
+    if is_session_creator(state, &session, headers).await.map_err(ApiError)? {
+        return Ok(());
+    }
+
+    let token = bearer_token(headers).ok_or(ApiError(AppError::InvalidToken))?;
+    let jwt = &state.config.jwt;
+    let claims = verify_token(token, jwt).map_err(ApiError)?;
+
+    let participant_repo = ParticipantRepository::new(state.db.clone());
+    let is_member = claims.session_id == session_id
+        && participant_repo
+            .get_participant(session_id, &claims.sub)
+            .await
+            .map(|participant| participant.is_active)
+            .unwrap_or(false);
+    if !is_member {
+        warn!(
+            "User {} attempted to read locations of session {}",
+            claims.sub, session_id
+        );
+        return Err(ApiError(AppError::UnauthorizedSessionOperation));
+    }
+
+    Ok(())
+}
@@ -1,2 +1,5 @@
 pub mod sessions;
-pub mod participants;
\ No newline at end of file
+pub mod participants;
+pub mod locations;
+pub mod openapi;
+pub mod admin;
\ No newline at end of file
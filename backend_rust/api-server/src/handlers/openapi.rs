@@ -0,0 +1,830 @@
+use axum::{extract::State, Json};
+use serde_json::{json, Value};
+
+use crate::AppState;
+
+/// Serve an OpenAPI 3 description of the REST API
+///
+/// Hand-maintained alongside the handlers and the DTOs in `shared::types`;
+/// update it when a route, request or response shape changes.
+pub async fn openapi_spec(State(state): State<AppState>) -> Json<Value> {
+    let app = &state.config.app;
+    let mut document = openapi_document(&format!("{}{}", app.base_url, app.base_path));
+
+    // The longest session duration is set per deployment
+    document["components"]["schemas"]["CreateSessionRequest"]["properties"]["expires_in_minutes"]["maximum"] =
+        json!(app.max_session_duration_minutes);
+
+    Json(document)
+}
+
+/// Build the OpenAPI document for an API served under `server_url`
+pub fn openapi_document(server_url: &str) -> Value {
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "Location Sharing API",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "servers": [{ "url": server_url }],
+        "paths": paths(),
+        "components": {
+            "schemas": schemas(),
+            "securitySchemes": {
+                "participantToken": {
+                    "type": "http",
+                    "scheme": "bearer",
+                    "bearerFormat": "JWT",
+                    "description": "WebSocket token returned when joining a session",
+                },
+                "creatorId": {
+                    "type": "apiKey",
+                    "in": "header",
+                    "name": "X-Creator-Id",
+                    "description": "Creator ID returned when the session was created; after an ownership transfer the new owner sends their participant token instead",
+                },
+                "adminToken": {
+                    "type": "apiKey",
+                    "in": "header",
+                    "name": "X-Admin-Token",
+                    "description": "Admin token from the server configuration",
+                },
+            },
+        },
+    })
+}
+
+fn paths() -> Value {
+    json!({
+        "/api/sessions": {
+            "post": {
+                "summary": "Create a session",
+                "parameters": [{
+                    "name": "Idempotency-Key",
+                    "in": "header",
+                    "description": "Return the session created by an earlier request with this key and body (kept for 24 hours); the key can't be reused with another body",
+                    "schema": { "type": "string", "minLength": 1, "maxLength": 255 },
+                }],
+                "requestBody": json_body("CreateSessionRequest"),
+                "responses": responses(
+                    ("201", "Session created", Some("CreateSessionResponse")),
+                    &["400", "409", "422", "429"],
+                ),
+            },
+        },
+        "/api/sessions/public": {
+            "get": {
+                "summary": "List open public sessions with their join links",
+                "parameters": [tag_param()],
+                "responses": responses(("200", "Public sessions", Some("PublicSessionPage")), &[]),
+            },
+        },
+        "/api/sessions/{session_id}": {
+            "parameters": [session_id_param()],
+            "get": {
+                "summary": "Get session details",
+                "description": "Carries a weak ETag; a matching If-None-Match returns 304.",
+                "responses": with_not_modified(responses(
+                    ("200", "Session details", Some("SessionDetailsResponse")),
+                    &["404", "410"],
+                )),
+            },
+            "patch": {
+                "summary": "Change a session's tags",
+                "security": [{ "creatorId": [] }],
+                "requestBody": json_body("UpdateSessionRequest"),
+                "responses": responses(
+                    ("200", "Updated session details", Some("SessionDetailsResponse")),
+                    &["400", "403", "404", "410"],
+                ),
+            },
+            "delete": {
+                "summary": "End a session",
+                "security": [{ "creatorId": [] }],
+                "responses": responses(
+                    ("200", "Session ended", Some("SuccessResponse")),
+                    &["403", "404", "410"],
+                ),
+            },
+        },
+        "/api/sessions/{join_token}/join": {
+            "parameters": [uuid_param("join_token", "Token from the session's join link")],
+            "post": {
+                "summary": "Join a session through its join link",
+                "requestBody": json_body("JoinSessionRequest"),
+                "responses": with_queued(responses(
+                    ("201", "Joined", Some("JoinSessionResponse")),
+                    &["400", "403", "404", "409", "410", "429"],
+                )),
+            },
+        },
+        "/api/sessions/{join_token}/waitlist/{ticket}": {
+            "parameters": [
+                uuid_param("join_token", "Token from the session's join link"),
+                uuid_param("ticket", "Ticket from a queued join"),
+            ],
+            "get": {
+                "summary": "Check a waitlist ticket, collecting the join once promoted",
+                "description": "Queued joiners aren't notified of their promotion; they poll this. 409 means the joiner was turned away when their turn came.",
+                "responses": with_queued(responses(
+                    ("200", "Promoted", Some("JoinSessionResponse")),
+                    &["404", "409", "410"],
+                )),
+            },
+        },
+        "/api/sessions/{session_id}/rotate-link": {
+            "parameters": [session_id_param()],
+            "post": {
+                "summary": "Replace the session's join link",
+                "security": [{ "creatorId": [] }],
+                "responses": responses(
+                    ("200", "New join link", Some("RotateJoinLinkResponse")),
+                    &["403", "404", "410"],
+                ),
+            },
+        },
+        "/api/sessions/{session_id}/qr": {
+            "parameters": [session_id_param()],
+            "get": {
+                "summary": "Get the session's join link as a QR code",
+                "security": [{ "creatorId": [] }],
+                "parameters": [{
+                    "name": "size",
+                    "in": "query",
+                    "description": "Smallest width and height of the PNG, in pixels",
+                    "schema": { "type": "integer", "minimum": 64, "maximum": 2048, "default": 256 },
+                }],
+                "responses": with_png_body(responses(
+                    ("200", "QR code contents, or the QR code as a PNG when `Accept: image/png`", Some("SessionQrCodeResponse")),
+                    &["400", "403", "404", "410"],
+                )),
+            },
+        },
+        "/api/sessions/{session_id}/transfer": {
+            "parameters": [session_id_param()],
+            "post": {
+                "summary": "Hand the session over to an active participant",
+                "security": [{ "creatorId": [] }],
+                "requestBody": json_body("TransferOwnershipRequest"),
+                "responses": responses(
+                    ("200", "Ownership transferred", Some("SuccessResponse")),
+                    &["400", "403", "404", "410"],
+                ),
+            },
+        },
+        "/api/sessions/{session_id}/locations": {
+            "parameters": [session_id_param()],
+            "get": {
+                "summary": "Get every participant's current location",
+                "security": [{ "participantToken": [] }, { "creatorId": [] }],
+                "responses": responses(
+                    ("200", "Current locations", Some("SessionLocationsResponse")),
+                    &["401", "403", "404", "410"],
+                ),
+            },
+            "post": {
+                "summary": "Ingest locations for many participants",
+                "security": [{ "creatorId": [] }],
+                "requestBody": json_body("IngestLocationsRequest"),
+                "responses": responses(
+                    ("200", "Per-location results", Some("IngestLocationsResponse")),
+                    &["400", "403", "404", "410"],
+                ),
+            },
+        },
+        "/api/sessions/{session_id}/centroid": {
+            "parameters": [session_id_param()],
+            "get": {
+                "summary": "Get the center and spread of current locations",
+                "security": [{ "participantToken": [] }, { "creatorId": [] }],
+                "responses": with_nullable_body(responses(
+                    ("200", "Centroid, or null while nobody is sharing a location", Some("SessionCentroidResponse")),
+                    &["401", "403", "404", "410"],
+                )),
+            },
+        },
+        "/api/sessions/{session_id}/participants": {
+            "parameters": [session_id_param()],
+            "get": {
+                "summary": "List participants",
+                "parameters": [{
+                    "name": "include_inactive",
+                    "in": "query",
+                    "description": "Also list participants who have left",
+                    "schema": { "type": "boolean", "default": false },
+                }],
+                "responses": responses(
+                    ("200", "Participants", Some("ParticipantPage")),
+                    &["404", "410"],
+                ),
+            },
+        },
+        "/api/sessions/{session_id}/participants/remove": {
+            "parameters": [session_id_param()],
+            "post": {
+                "summary": "Remove participants",
+                "security": [{ "creatorId": [] }],
+                "requestBody": json_body("RemoveParticipantsRequest"),
+                "responses": responses(
+                    ("200", "Per-participant results", Some("RemoveParticipantsResponse")),
+                    &["400", "403", "404", "410"],
+                ),
+            },
+        },
+        "/api/sessions/{session_id}/participants/{user_id}": {
+            "parameters": [session_id_param(), user_id_param()],
+            "delete": {
+                "summary": "Leave a session",
+                "responses": responses(
+                    ("200", "Left the session", Some("SuccessResponse")),
+                    &["404", "410"],
+                ),
+            },
+        },
+        "/api/sessions/{session_id}/participants/{user_id}/heartbeat": {
+            "parameters": [session_id_param(), user_id_param()],
+            "post": {
+                "summary": "Keep a participant marked active",
+                "security": [{ "participantToken": [] }],
+                "responses": responses(
+                    ("200", "Heartbeat recorded", Some("SuccessResponse")),
+                    &["401", "403", "404", "410"],
+                ),
+            },
+        },
+        "/api/sessions/{session_id}/participants/{user_id}/refresh-token": {
+            "parameters": [session_id_param(), user_id_param()],
+            "post": {
+                "summary": "Renew a participant's WebSocket token",
+                "requestBody": json_body("RefreshTokenRequest"),
+                "responses": responses(
+                    ("200", "New token", Some("RefreshTokenResponse")),
+                    &["401", "403", "404", "410"],
+                ),
+            },
+        },
+        "/api/sessions/{session_id}/participants/{user_id}/location": {
+            "parameters": [session_id_param(), user_id_param()],
+            "delete": {
+                "summary": "Delete a participant's stored location and history",
+                "security": [{ "participantToken": [] }],
+                "responses": responses(
+                    ("200", "Location deleted", Some("SuccessResponse")),
+                    &["401", "403", "404", "410"],
+                ),
+            },
+        },
+        "/api/sessions/{session_id}/participants/{user_id}/stats": {
+            "parameters": [session_id_param(), user_id_param()],
+            "get": {
+                "summary": "Get a participant's distance, speeds and ETA",
+                "security": [{ "participantToken": [] }, { "creatorId": [] }],
+                "parameters": [
+                    { "name": "dest_lat", "in": "query", "schema": { "type": "number" } },
+                    { "name": "dest_lng", "in": "query", "schema": { "type": "number" } },
+                ],
+                "responses": responses(
+                    ("200", "Movement summary", Some("ParticipantStatsResponse")),
+                    &["400", "401", "403", "404", "410"],
+                ),
+            },
+        },
+        "/api/sessions/{session_id}/track.geojson": {
+            "parameters": [session_id_param()],
+            "get": {
+                "summary": "Export every participant's path as GeoJSON",
+                "security": [{ "creatorId": [] }],
+                "responses": with_geojson_body(responses(
+                    ("200", "FeatureCollection with one LineString or Point per participant", None),
+                    &["403", "404"],
+                )),
+            },
+        },
+        "/api/admin/sessions": {
+            "get": {
+                "summary": "List sessions with participant counts and last activity",
+                "security": [{ "adminToken": [] }],
+                "parameters": [
+                    { "name": "active_only", "in": "query", "schema": { "type": "boolean", "default": false } },
+                    {
+                        "name": "sort",
+                        "in": "query",
+                        "schema": {
+                            "type": "string",
+                            "enum": ["last_activity_desc", "last_activity_asc"],
+                            "default": "last_activity_desc",
+                        },
+                    },
+                    tag_param(),
+                ],
+                "responses": responses(
+                    ("200", "Session summaries", Some("AdminSessionPage")),
+                    &["400", "401"],
+                ),
+            },
+        },
+        "/api/admin/sessions/{session_id}/audit": {
+            "parameters": [session_id_param()],
+            "get": {
+                "summary": "List a session's lifecycle events, oldest first",
+                "security": [{ "adminToken": [] }],
+                "responses": responses(
+                    ("200", "Audit events", Some("AuditEventPage")),
+                    &["400", "401"],
+                ),
+            },
+        },
+        "/api/openapi.json": {
+            "get": {
+                "summary": "This document",
+                "responses": {
+                    "200": {
+                        "description": "OpenAPI document",
+                        "content": { "application/json": { "schema": { "type": "object" } } },
+                    },
+                },
+            },
+        },
+    })
+}
+
+fn schemas() -> Value {
+    json!({
+        "CreateSessionRequest": object(
+            json!({
+                "name": nullable(json!({ "type": "string", "maxLength": 255 })),
+                "creator_id": nullable(uuid()),
+                "expires_in_minutes": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "maximum": 10080,
+                    "default": 1440,
+                },
+                "avatar_palette": nullable(json!({
+                    "type": "array",
+                    "items": hex_color(),
+                    "maxItems": 64,
+                })),
+                "is_public": { "type": "boolean", "default": false },
+                "tags": tags(),
+                "welcome_message": nullable(json!({ "type": "string", "maxLength": 500 })),
+                "view_only": { "type": "boolean", "default": false },
+                "waitlist_enabled": { "type": "boolean", "default": false },
+                "interpolate_locations": { "type": "boolean", "default": false },
+                "join_window_minutes": nullable(json!({ "type": "integer", "minimum": 1 })),
+                "metadata": nullable(json!({ "type": "object" })),
+            }),
+            &[],
+        ),
+        "UpdateSessionRequest": object(
+            json!({
+                "tags": tags(),
+                "metadata": nullable(json!({ "type": "object" })),
+            }),
+            &[],
+        ),
+        "CreateSessionResponse": object(
+            json!({
+                "session_id": uuid(),
+                "creator_id": uuid(),
+                "join_link": { "type": "string", "format": "uri" },
+                "expires_at": date_time(),
+                "name": nullable(json!({ "type": "string" })),
+            }),
+            &["session_id", "creator_id", "join_link", "expires_at", "name"],
+        ),
+        "SessionDetailsResponse": object(
+            json!({
+                "id": uuid(),
+                "name": nullable(json!({ "type": "string" })),
+                "created_at": date_time(),
+                "expires_at": date_time(),
+                "participant_count": { "type": "integer" },
+                "is_active": { "type": "boolean" },
+                "last_activity": date_time(),
+                "tags": tags(),
+                "welcome_message": nullable(json!({ "type": "string" })),
+                "view_only": { "type": "boolean" },
+                "waitlist_enabled": { "type": "boolean" },
+                "interpolate_locations": { "type": "boolean" },
+                "join_window_minutes": nullable(json!({ "type": "integer" })),
+                "metadata": { "type": "object" },
+            }),
+            &["id", "name", "created_at", "expires_at", "participant_count", "is_active", "last_activity", "tags", "welcome_message", "view_only", "waitlist_enabled", "interpolate_locations", "join_window_minutes", "metadata"],
+        ),
+        "AdminSessionSummary": object(
+            json!({
+                "id": uuid(),
+                "name": nullable(json!({ "type": "string" })),
+                "participant_count": { "type": "integer" },
+                "last_activity": date_time(),
+                "expires_at": date_time(),
+                "is_active": { "type": "boolean" },
+                "tags": tags(),
+            }),
+            &["id", "name", "participant_count", "last_activity", "expires_at", "is_active", "tags"],
+        ),
+        "AdminSessionPage": page_of("AdminSessionSummary"),
+        "AuditEvent": object(
+            json!({
+                "id": uuid(),
+                "event_type": {
+                    "type": "string",
+                    "enum": ["session_created", "participant_joined", "participant_left", "session_ended"],
+                },
+                "session_id": uuid(),
+                "actor": nullable(json!({ "type": "string" })),
+                "metadata": { "type": "object" },
+                "created_at": date_time(),
+            }),
+            &["id", "event_type", "session_id", "actor", "metadata", "created_at"],
+        ),
+        "AuditEventPage": page_of("AuditEvent"),
+        "PublicSessionSummary": object(
+            json!({
+                "id": uuid(),
+                "name": nullable(json!({ "type": "string" })),
+                "participant_count": { "type": "integer" },
+                "expires_at": date_time(),
+                "join_link": { "type": "string", "format": "uri" },
+                "tags": tags(),
+            }),
+            &["id", "name", "participant_count", "expires_at", "join_link", "tags"],
+        ),
+        "PublicSessionPage": page_of("PublicSessionSummary"),
+        "TransferOwnershipRequest": object(json!({ "new_creator_id": uuid() }), &["new_creator_id"]),
+        "RotateJoinLinkResponse": object(
+            json!({ "join_link": { "type": "string", "format": "uri" } }),
+            &["join_link"],
+        ),
+        "SessionQrCodeResponse": object(
+            json!({
+                "payload": { "type": "string" },
+                "url": { "type": "string", "format": "uri" },
+            }),
+            &["payload", "url"],
+        ),
+        "LocationPrecision": {
+            "type": "string",
+            "enum": ["exact", "coarse", "city"],
+            "default": "exact",
+        },
+        "ParticipantRole": {
+            "type": "string",
+            "enum": ["publisher", "viewer"],
+        },
+        "JoinSessionRequest": object(
+            json!({
+                "display_name": { "type": "string", "minLength": 1, "maxLength": 100 },
+                "avatar_color": nullable(hex_color()),
+                "precision": schema_ref("LocationPrecision"),
+                "device_id": nullable(json!({
+                    "type": "string",
+                    "maxLength": 128,
+                    "pattern": "^[A-Za-z0-9._-]+$",
+                })),
+                "role": nullable(schema_ref("ParticipantRole")),
+            }),
+            &["display_name"],
+        ),
+        "JoinSessionResponse": object(
+            json!({
+                "session_id": uuid(),
+                "user_id": uuid(),
+                "role": schema_ref("ParticipantRole"),
+                "websocket_token": { "type": "string" },
+                "websocket_url": { "type": "string", "format": "uri" },
+            }),
+            &["session_id", "user_id", "role", "websocket_token", "websocket_url"],
+        ),
+        "JoinQueuedResponse": object(
+            json!({
+                "session_id": uuid(),
+                "ticket": uuid(),
+                "position": { "type": "integer", "minimum": 1 },
+            }),
+            &["session_id", "ticket", "position"],
+        ),
+        "RefreshTokenRequest": object(json!({ "token": { "type": "string" } }), &["token"]),
+        "RefreshTokenResponse": object(
+            json!({
+                "websocket_token": { "type": "string" },
+                "expires_at": date_time(),
+            }),
+            &["websocket_token", "expires_at"],
+        ),
+        "ParticipantResponse": object(
+            json!({
+                "user_id": { "type": "string" },
+                "display_name": { "type": "string" },
+                "avatar_color": hex_color(),
+                "last_seen": date_time(),
+                "is_active": { "type": "boolean" },
+                "role": schema_ref("ParticipantRole"),
+            }),
+            &["user_id", "display_name", "avatar_color", "last_seen", "is_active", "role"],
+        ),
+        "ParticipantPage": page_of("ParticipantResponse"),
+        "RemoveParticipantsRequest": object(
+            json!({ "user_ids": { "type": "array", "items": { "type": "string" } } }),
+            &["user_ids"],
+        ),
+        "RemovedParticipantResult": object(
+            json!({
+                "user_id": { "type": "string" },
+                "removed": { "type": "boolean" },
+                "error": { "type": "string", "description": "Error code when not removed" },
+            }),
+            &["user_id", "removed"],
+        ),
+        "RemoveParticipantsResponse": object(
+            json!({ "results": array_of("RemovedParticipantResult") }),
+            &["results"],
+        ),
+        "IngestLocationEntry": object(
+            json!({
+                "user_id": { "type": "string" },
+                "lat": { "type": "number" },
+                "lng": { "type": "number" },
+                "accuracy": { "type": "number" },
+                "timestamp": date_time(),
+            }),
+            &["user_id", "lat", "lng", "accuracy", "timestamp"],
+        ),
+        "IngestLocationsRequest": array_of("IngestLocationEntry"),
+        "IngestedLocationResult": object(
+            json!({
+                "user_id": { "type": "string" },
+                "accepted": { "type": "boolean" },
+                "error": { "type": "string", "description": "Error code when not accepted" },
+            }),
+            &["user_id", "accepted"],
+        ),
+        "IngestLocationsResponse": object(
+            json!({ "results": array_of("IngestedLocationResult") }),
+            &["results"],
+        ),
+        "SessionLocationResponse": object(
+            json!({
+                "user_id": { "type": "string" },
+                "lat": { "type": "number" },
+                "lng": { "type": "number" },
+                "accuracy": { "type": "number" },
+                "timestamp": date_time(),
+                "display_name": { "type": "string" },
+                "avatar_color": hex_color(),
+            }),
+            &["user_id", "lat", "lng", "accuracy", "timestamp", "display_name", "avatar_color"],
+        ),
+        "SessionLocationsResponse": object(
+            json!({ "locations": array_of("SessionLocationResponse") }),
+            &["locations"],
+        ),
+        "SessionCentroidResponse": object(
+            json!({
+                "lat": { "type": "number" },
+                "lng": { "type": "number" },
+                "location_count": { "type": "integer" },
+                "average_distance_meters": { "type": "number" },
+                "max_distance_meters": { "type": "number" },
+            }),
+            &["lat", "lng", "location_count", "average_distance_meters", "max_distance_meters"],
+        ),
+        "ParticipantStatsResponse": object(
+            json!({
+                "user_id": { "type": "string" },
+                "point_count": { "type": "integer" },
+                "distance_meters": { "type": "number" },
+                "average_speed_mps": { "type": "number" },
+                "max_speed_mps": { "type": "number" },
+                "eta_seconds": nullable(json!({ "type": "number" })),
+            }),
+            &["user_id", "point_count", "distance_meters", "average_speed_mps", "max_speed_mps", "eta_seconds"],
+        ),
+        "SuccessResponse": object(json!({ "success": { "type": "boolean" } }), &["success"]),
+        "FieldError": object(
+            json!({
+                "field": { "type": "string" },
+                "message": { "type": "string" },
+            }),
+            &["field", "message"],
+        ),
+        "ErrorResponse": object(
+            json!({
+                "error": object(
+                    json!({
+                        "code": { "type": "string", "example": "SESSION_NOT_FOUND" },
+                        "message": { "type": "string" },
+                        "details": array_of("FieldError"),
+                    }),
+                    &["code", "message"],
+                ),
+            }),
+            &["error"],
+        ),
+    })
+}
+
+/// Responses for an operation: its success response plus the error envelope
+/// for each listed error status
+fn responses(success: (&str, &str, Option<&str>), errors: &[&str]) -> Value {
+    let (status, description, schema) = success;
+    let mut responses = serde_json::Map::new();
+
+    let mut ok = json!({ "description": description });
+    if let Some(schema) = schema {
+        ok["content"] = json!({ "application/json": { "schema": schema_ref(schema) } });
+    }
+    responses.insert(status.to_string(), ok);
+
+    for status in errors {
+        responses.insert(
+            status.to_string(),
+            json!({
+                "description": error_description(status),
+                "content": { "application/json": { "schema": schema_ref("ErrorResponse") } },
+            }),
+        );
+    }
+    responses.insert(
+        "500".to_string(),
+        json!({
+            "description": "Internal error",
+            "content": { "application/json": { "schema": schema_ref("ErrorResponse") } },
+        }),
+    );
+
+    Value::Object(responses)
+}
+
+fn with_geojson_body(mut responses: Value) -> Value {
+    responses["200"]["content"] = json!({ "application/geo+json": { "schema": { "type": "object" } } });
+    responses
+}
+
+fn with_png_body(mut responses: Value) -> Value {
+    responses["200"]["content"]["image/png"] = json!({ "schema": { "type": "string", "format": "binary" } });
+    responses
+}
+
+fn with_nullable_body(mut responses: Value) -> Value {
+    let schema = responses["200"]["content"]["application/json"]["schema"].take();
+    responses["200"]["content"]["application/json"]["schema"] = json!({ "allOf": [schema], "nullable": true });
+    responses
+}
+
+fn with_not_modified(mut responses: Value) -> Value {
+    responses["304"] = json!({ "description": "Not modified since the ETag in If-None-Match" });
+    responses
+}
+
+fn with_queued(mut responses: Value) -> Value {
+    responses["202"] = json!({
+        "description": "Session full; queued on its waitlist",
+        "content": { "application/json": { "schema": schema_ref("JoinQueuedResponse") } },
+    });
+    responses
+}
+
+fn error_description(status: &str) -> &'static str {
+    match status {
+        "400" => "Invalid request",
+        "401" => "Missing, invalid or expired token",
+        "403" => "Not allowed for this caller",
+        "404" => "Session or participant not found",
+        "409" => "Session full, limit reached, already joined or request in progress",
+        "422" => "Idempotency-Key already used for a different request",
+        "410" => "Session expired or ended",
+        "429" => "Rate limited; see Retry-After",
+        _ => "Error",
+    }
+}
+
+fn json_body(schema: &str) -> Value {
+    json!({
+        "required": true,
+        "content": { "application/json": { "schema": schema_ref(schema) } },
+    })
+}
+
+fn object(properties: Value, required: &[&str]) -> Value {
+    let mut schema = json!({ "type": "object", "properties": properties });
+    if !required.is_empty() {
+        schema["required"] = json!(required);
+    }
+    schema
+}
+
+fn schema_ref(name: &str) -> Value {
+    json!({ "$ref": format!("#/components/schemas/{}", name) })
+}
+
+fn array_of(name: &str) -> Value {
+    json!({ "type": "array", "items": schema_ref(name) })
+}
+
+/// The `Paginated` envelope around a list of `name`
+fn page_of(name: &str) -> Value {
+    object(
+        json!({
+            "items": array_of(name),
+            "total": nullable(json!({ "type": "integer" })),
+            "limit": nullable(json!({ "type": "integer" })),
+            "offset": nullable(json!({ "type": "integer" })),
+        }),
+        &["items", "total", "limit", "offset"],
+    )
+}
+
+fn nullable(mut schema: Value) -> Value {
+    schema["nullable"] = json!(true);
+    schema
+}
+
+fn uuid() -> Value {
+    json!({ "type": "string", "format": "uuid" })
+}
+
+fn date_time() -> Value {
+    json!({ "type": "string", "format": "date-time" })
+}
+
+fn hex_color() -> Value {
+    json!({ "type": "string", "pattern": "^#([0-9A-Fa-f]{3}|[0-9A-Fa-f]{6}|[0-9A-Fa-f]{8})$" })
+}
+
+fn tags() -> Value {
+    json!({
+        "type": "array",
+        "items": { "type": "string", "minLength": 1, "maxLength": 30 },
+        "maxItems": 10,
+    })
+}
+
+fn tag_param() -> Value {
+    json!({
+        "name": "tag",
+        "in": "query",
+        "description": "Only list sessions carrying this tag",
+        "schema": { "type": "string" },
+    })
+}
+
+fn uuid_param(name: &str, description: &str) -> Value {
+    json!({
+        "name": name,
+        "in": "path",
+        "required": true,
+        "description": description,
+        "schema": uuid(),
+    })
+}
+
+fn session_id_param() -> Value {
+    uuid_param("session_id", "Session ID")
+}
+
+fn user_id_param() -> Value {
+    json!({
+        "name": "user_id",
+        "in": "path",
+        "required": true,
+        "description": "Participant's user ID",
+        "schema": { "type": "string" },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collect_refs<'a>(value: &'a Value, refs: &mut Vec<&'a str>) {
+        match value {
+            Value::Object(map) => {
+                if let Some(Value::String(reference)) = map.get("$ref") {
+                    refs.push(reference);
+                }
+                map.values().for_each(|value| collect_refs(value, refs));
+            }
+            Value::Array(values) => values.iter().for_each(|value| collect_refs(value, refs)),
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn test_schema_references_resolve() {
+        let document = openapi_document("https://app.com");
+        let mut refs = Vec::new();
+        collect_refs(&document, &mut refs);
+        assert!(!refs.is_empty());
+
+        for reference in refs {
+            let name = reference.strip_prefix("#/components/schemas/").unwrap();
+            assert!(
+                document["components"]["schemas"].get(name).is_some(),
+                "{} does not resolve",
+                reference
+            );
+        }
+    }
+}
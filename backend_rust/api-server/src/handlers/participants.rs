@@ -1,31 +1,71 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
+    http::HeaderMap,
     Json,
 };
-use shared::{ParticipantsListResponse, SuccessResponse};
+use chrono::Duration;
+use shared::{
+    AppError, AppResult, AuditEventType, Constants, ListParticipantsQuery,
+    Paginated, ParticipantResponse,
+    RefreshTokenRequest, RefreshTokenResponse, RemoveParticipantsRequest,
+    RemoveParticipantsResponse, RemovedParticipantResult, SuccessResponse,
+};
+use std::collections::HashSet;
 use crate::error::ApiError;
-use tracing::{debug, info};
+use crate::extract::{ApiJson, CasedJson};
+use serde_json::json;
+use tracing::{debug, info, warn};
 use uuid::Uuid;
 
-use crate::{models::ParticipantRepository, AppState};
+use super::sessions::promote_from_waitlist;
+use crate::{
+    auth::{
+        creator::is_session_creator,
+        jwt::{bearer_token, issue_websocket_token, verify_refreshable_token, verify_token},
+    },
+    models::{AuditRepository, LocationHistoryRepository, ParticipantRepository, SessionRepository},
+    AppState,
+};
 
-/// List all participants in a session
+/// List the active participants in a session
+///
+/// With `?include_inactive=true`, participants who have left are listed too.
 pub async fn list_participants(
     State(state): State<AppState>,
     Path(session_id): Path<Uuid>,
-) -> Result<Json<ParticipantsListResponse>, ApiError> {
+    Query(query): Query<ListParticipantsQuery>,
+) -> Result<CasedJson<Paginated<ParticipantResponse>>, ApiError> {
     debug!("Listing participants for session: {}", session_id);
 
     let participant_repo = ParticipantRepository::new(state.db.clone());
-    let participants = participant_repo.list_participants(session_id).await.map_err(ApiError)?;
+    let participants = if query.include_inactive {
+        participant_repo
+            .get_all_participants_for_session(session_id)
+            .await
+            .map_err(ApiError)?
+            .into_iter()
+            .map(|participant| ParticipantResponse {
+                user_id: participant.user_id,
+                display_name: participant.display_name,
+                avatar_color: participant.avatar_color,
+                last_seen: participant.last_seen,
+                is_active: participant.is_active,
+                role: participant.role,
+            })
+            .collect()
+    } else {
+        participant_repo.list_participants(session_id).await.map_err(ApiError)?
+    };
 
     debug!("Found {} participants in session {}", participants.len(), session_id);
 
-    let response = ParticipantsListResponse { participants };
-    Ok(Json(response))
+    Ok(CasedJson::new(Paginated::all(participants), &state.config))
 }
 
 /// Remove a participant from a session
+///
+/// Peers are told the participant left, once even if their WebSocket
+/// disconnect reports the departure too.
 pub async fn leave_session(
     State(state): State<AppState>,
     Path((session_id, user_id)): Path<(Uuid, String)>,
@@ -37,5 +77,221 @@ pub async fn leave_session(
 
     info!("Participant {} left session {}", user_id, session_id);
 
+    // The participant is gone either way, so a failed announcement only warns
+    if let Err(e) = state.redis.announce_participant_left(&session_id, &user_id).await {
+        warn!("Failed to announce participant {} leaving session {}: {}", user_id, session_id, e);
+    }
+
+    AuditRepository::new(state.db.clone())
+        .record(AuditEventType::ParticipantLeft, session_id, Some(&user_id), json!({}))
+        .await;
+
+    // The participant is gone either way, so a failed promotion only warns
+    if let Err(e) = promote_from_waitlist(&state, session_id).await {
+        warn!("Failed to promote from the waitlist of session {}: {}", session_id, e);
+    }
+
+    Ok(Json(SuccessResponse { success: true }))
+}
+
+/// Remove several participants from a session at once
+///
+/// Only the session creator (`X-Creator-Id`) may do this. Each participant is
+/// marked inactive, their presence and location are cleared, and peers are
+/// told they left. Failures are reported per user ID rather than failing the
+/// whole request.
+pub async fn remove_participants(
+    State(state): State<AppState>,
+    Path(session_id): Path<Uuid>,
+    headers: HeaderMap,
+    ApiJson(request): ApiJson<RemoveParticipantsRequest>,
+) -> Result<Json<RemoveParticipantsResponse>, ApiError> {
+    debug!("Removing {} participants from session {}", request.user_ids.len(), session_id);
+
+    request.validate().map_err(|msg| ApiError(AppError::validation("user_ids", &msg)))?;
+
+    let session_repo = SessionRepository::new(state.db.clone());
+    let session = session_repo.get_session(session_id).await.map_err(ApiError)?;
+
+    if !is_session_creator(&state, &session, &headers).await.map_err(ApiError)? {
+        warn!("Non-creator attempted to remove participants from session {}", session_id);
+        return Err(ApiError(AppError::UnauthorizedSessionOperation));
+    }
+
+    let participant_repo = ParticipantRepository::new(state.db.clone());
+    let mut seen = HashSet::new();
+    let mut results = Vec::with_capacity(request.user_ids.len());
+
+    for user_id in request.user_ids {
+        if !seen.insert(user_id.clone()) {
+            continue;
+        }
+
+        let error = match remove_participant(&state, &participant_repo, session_id, &user_id).await {
+            Ok(()) => None,
+            Err(e) => {
+                warn!("Failed to remove participant {} from session {}: {}", user_id, session_id, e);
+                Some(e.error_code().to_string())
+            }
+        };
+        results.push(RemovedParticipantResult {
+            user_id,
+            removed: error.is_none(),
+            error,
+        });
+    }
+
+    info!(
+        "Removed {} of {} participants from session {}",
+        results.iter().filter(|result| result.removed).count(),
+        results.len(),
+        session_id
+    );
+
+    if let Err(e) = promote_from_waitlist(&state, session_id).await {
+        warn!("Failed to promote from the waitlist of session {}: {}", session_id, e);
+    }
+
+    Ok(Json(RemoveParticipantsResponse { results }))
+}
+
+/// Deactivate one participant, clear their Redis state and announce it
+async fn remove_participant(
+    state: &AppState,
+    participant_repo: &ParticipantRepository,
+    session_id: Uuid,
+    user_id: &str,
+) -> AppResult<()> {
+    participant_repo.remove_participant(session_id, user_id).await?;
+    state.redis.clear_participant(&session_id, user_id).await?;
+    LocationHistoryRepository::new(state.db.clone())
+        .delete_history(session_id, user_id)
+        .await?;
+
+    state.redis.announce_participant_left(&session_id, user_id).await?;
+    Ok(())
+}
+
+/// Keep a participant marked active without a WebSocket connection
+///
+/// Authorized by the participant's own token (`Authorization: Bearer`).
+pub async fn heartbeat(
+    State(state): State<AppState>,
+    Path((session_id, user_id)): Path<(Uuid, String)>,
+    headers: HeaderMap,
+) -> Result<Json<SuccessResponse>, ApiError> {
+    debug!("Heartbeat from participant {} in session {}", user_id, session_id);
+
+    let token = bearer_token(&headers).ok_or(ApiError(AppError::InvalidToken))?;
+    let claims = verify_token(token, &state.config.jwt).map_err(ApiError)?;
+    if claims.session_id != session_id || claims.sub != user_id {
+        warn!(
+            "User {} attempted to heartbeat for user {} in session {}",
+            claims.sub, user_id, session_id
+        );
+        return Err(ApiError(AppError::UnauthorizedSessionOperation));
+    }
+
+    // Verify session exists and is active
+    let session_repo = SessionRepository::new(state.db.clone());
+    session_repo.get_session(session_id).await.map_err(ApiError)?;
+
+    let participant_repo = ParticipantRepository::new(state.db.clone());
+    participant_repo.update_last_seen(session_id, &user_id).await.map_err(ApiError)?;
+
+    state.redis.touch_participant(&session_id, &user_id).await.map_err(ApiError)?;
+
+    Ok(Json(SuccessResponse { success: true }))
+}
+
+/// Exchange a still-valid (or recently expired) WebSocket token for a fresh one
+pub async fn refresh_token(
+    State(state): State<AppState>,
+    Path((session_id, user_id)): Path<(Uuid, String)>,
+    ApiJson(request): ApiJson<RefreshTokenRequest>,
+) -> Result<Json<RefreshTokenResponse>, ApiError> {
+    debug!("Refreshing token for participant {} in session {}", user_id, session_id);
+
+    let jwt = &state.config.jwt;
+    let claims = verify_refreshable_token(
+        &request.token,
+        jwt,
+        Duration::minutes(Constants::WS_TOKEN_REFRESH_GRACE_MINUTES),
+    )
+    .map_err(ApiError)?;
+
+    if claims.session_id != session_id || claims.sub != user_id {
+        warn!(
+            "Token for user {} in session {} presented to refresh user {} in session {}",
+            claims.sub, claims.session_id, user_id, session_id
+        );
+        return Err(ApiError(AppError::UnauthorizedSessionOperation));
+    }
+
+    // Verify session exists and is active
+    let session_repo = SessionRepository::new(state.db.clone());
+    session_repo.get_session(session_id).await.map_err(ApiError)?;
+
+    let participant_repo = ParticipantRepository::new(state.db.clone());
+    let participant = participant_repo.get_participant(session_id, &user_id).await.map_err(ApiError)?;
+    if !participant.is_active {
+        return Err(ApiError(AppError::ParticipantNotFound));
+    }
+
+    let (websocket_token, expires_at) =
+        issue_websocket_token(&user_id, session_id, participant.location_precision, participant.role, jwt)
+            .map_err(ApiError)?;
+
+    info!("Refreshed token for participant {} in session {}", user_id, session_id);
+
+    Ok(Json(RefreshTokenResponse {
+        websocket_token,
+        expires_at,
+    }))
+}
+
+/// Immediately delete a participant's shared location
+///
+/// Authorized by the participant's own token (`Authorization: Bearer`) or the
+/// session creator's ID (`X-Creator-Id`). Peers are told the participant left
+/// so their marker disappears.
+pub async fn delete_location(
+    State(state): State<AppState>,
+    Path((session_id, user_id)): Path<(Uuid, String)>,
+    headers: HeaderMap,
+) -> Result<Json<SuccessResponse>, ApiError> {
+    debug!("Deleting location of participant {} in session {}", user_id, session_id);
+
+    let session_repo = SessionRepository::new(state.db.clone());
+    let session = session_repo.get_session(session_id).await.map_err(ApiError)?;
+
+    let is_creator = is_session_creator(&state, &session, &headers).await.map_err(ApiError)?;
+    if !is_creator {
+        let token = bearer_token(&headers).ok_or(ApiError(AppError::InvalidToken))?;
+        let jwt = &state.config.jwt;
+        let claims = verify_token(token, jwt).map_err(ApiError)?;
+
+        if claims.session_id != session_id || claims.sub != user_id {
+            warn!(
+                "User {} attempted to delete location of user {} in session {}",
+                claims.sub, user_id, session_id
+            );
+            return Err(ApiError(AppError::UnauthorizedSessionOperation));
+        }
+    }
+
+    let participant_repo = ParticipantRepository::new(state.db.clone());
+    participant_repo.get_participant(session_id, &user_id).await.map_err(ApiError)?;
+
+    state.redis.delete_location(&session_id, &user_id).await.map_err(ApiError)?;
+    LocationHistoryRepository::new(state.db.clone())
+        .delete_history(session_id, &user_id)
+        .await
+        .map_err(ApiError)?;
+
+    state.redis.announce_participant_left(&session_id, &user_id).await.map_err(ApiError)?;
+
+    info!("Deleted location of participant {} in session {}", user_id, session_id);
+
     Ok(Json(SuccessResponse { success: true }))
 }
\ No newline at end of file
@@ -1,147 +1,799 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
-use chrono::{Duration, Utc};
-use jsonwebtoken::{encode, EncodingKey, Header};
+use chrono::Utc;
 use shared::{
-    AppError, Constants, CreateSessionRequest, CreateSessionResponse,
-    JoinSessionRequest, JoinSessionResponse, JwtClaims, SessionDetailsResponse, SuccessResponse,
-    generate_join_link, generate_user_id, generate_websocket_url, sanitize_session_name,
-    generate_session_name,
+    AppError, AppResult, AuditEventType, Constants, CreateSessionRequest, CreateSessionResponse,
+    JoinQueuedResponse, JoinSessionRequest, JoinSessionResponse, OwnershipTransferredData, Paginated,
+    Participant, ParticipantRole, PublicSessionSummary, PublicSessionsQuery, RotateJoinLinkResponse,
+    Session, SessionDetailsResponse, SessionInfoData, SessionQrCodeQuery, SessionQrCodeResponse, SuccessResponse, TransferOwnershipRequest, UpdateSessionRequest,
+    WaitlistEntry, WebSocketMessage, generate_join_link, generate_user_id, generate_websocket_url,
+    sanitize_session_name, generate_session_name, normalize_hex_color, derive_user_id,
+    normalize_session_tags, is_valid_idempotency_key, idempotency_fingerprint, sanitize_welcome_message,
+    is_reserved_avatar_color, validate_display_name,
 };
 use crate::error::ApiError;
-use tracing::{debug, info};
+use crate::extract::{ApiJson, CasedJson};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::{debug, info, warn};
 use uuid::Uuid;
 
-use crate::{models::SessionRepository, AppState};
+use crate::{
+    auth::{creator::is_session_creator, jwt::issue_websocket_token},
+    models::{AuditRepository, SessionRepository},
+    AppState,
+};
 
 /// Create a new session
+///
+/// A request carrying an `Idempotency-Key` header that was already used
+/// with the same body returns the originally created session instead of
+/// creating another. Reusing a key with a different body is rejected, so the
+/// original creator ID only goes to a caller repeating the original request.
 pub async fn create_session(
     State(state): State<AppState>,
-    Json(request): Json<CreateSessionRequest>,
-) -> Result<Json<CreateSessionResponse>, ApiError> {
+    headers: HeaderMap,
+    ApiJson(request): ApiJson<CreateSessionRequest>,
+) -> Result<(StatusCode, CasedJson<CreateSessionResponse>), ApiError> {
     debug!("Creating session with request: {:?}", request);
 
+    let idempotency_key = idempotency_key(&headers)?;
+
     // Validate request
-    request.validate().map_err(|msg| ApiError(AppError::validation("request", &msg)))?;
+    let max_duration_minutes = state.config.app.max_session_duration_minutes;
+    request
+        .validate(max_duration_minutes)
+        .map_err(|errors| ApiError(AppError::validation_errors(errors)))?;
+
+    let Some(key) = idempotency_key else {
+        let response = create_new_session(&state, request).await?;
+        return Ok((StatusCode::CREATED, CasedJson::new(response, &state.config)));
+    };
+
+    // Reserve the key before creating anything, so concurrent retries can't
+    // each create a session
+    let body = serde_json::to_string(&request).map_err(|e| ApiError(AppError::from(e)))?;
+    let fingerprint = idempotency_fingerprint(&key, &body);
+    let reservation = IdempotencyRecord { fingerprint: fingerprint.clone(), response: None };
+    let stored = state
+        .redis
+        .reserve_idempotent_session(
+            &key,
+            &serde_json::to_string(&reservation).map_err(|e| ApiError(AppError::from(e)))?,
+            Constants::IDEMPOTENCY_RESERVATION_SECONDS,
+        )
+        .await
+        .map_err(ApiError)?;
+
+    if let Some(stored) = stored {
+        let record: IdempotencyRecord = serde_json::from_str(&stored)
+            .map_err(|e| ApiError(AppError::from(e)))?;
+        if record.fingerprint != fingerprint {
+            return Err(ApiError(AppError::IdempotencyKeyReused));
+        }
+        // Replay the original response for a retried request
+        let Some(response) = record.response else {
+            return Err(ApiError(AppError::IdempotencyKeyInProgress));
+        };
+        debug!("Replaying session {} for idempotency key {}", response.session_id, key);
+        return Ok((StatusCode::CREATED, CasedJson::new(response, &state.config)));
+    }
+
+    let response = match create_new_session(&state, request).await {
+        Ok(response) => response,
+        Err(e) => {
+            if let Err(release_error) = state.redis.release_idempotent_session(&key).await {
+                warn!("Failed to release idempotency key {}: {}", key, release_error);
+            }
+            return Err(e);
+        }
+    };
+
+    // The session exists now, so a failure here must not fail the request
+    let record = IdempotencyRecord { fingerprint, response: Some(response.clone()) };
+    let stored = serde_json::to_string(&record).map_err(|e| ApiError(AppError::from(e)))?;
+    if let Err(e) = state
+        .redis
+        .complete_idempotent_session(&key, &stored, Constants::IDEMPOTENCY_KEY_TTL_SECONDS)
+        .await
+    {
+        warn!("Failed to store idempotency key for session {}: {}", response.session_id, e);
+    }
+
+    Ok((StatusCode::CREATED, CasedJson::new(response, &state.config)))
+}
+
+/// What an `Idempotency-Key` is stored with: the fingerprint of the request
+/// that reserved it, and the session it created once finished
+#[derive(Serialize, Deserialize)]
+struct IdempotencyRecord {
+    fingerprint: String,
+    response: Option<CreateSessionResponse>,
+}
+
+/// Create a session from a validated request
+async fn create_new_session(
+    state: &AppState,
+    request: CreateSessionRequest,
+) -> Result<CreateSessionResponse, ApiError> {
+    let max_duration_minutes = state.config.app.max_session_duration_minutes;
+    let duration_minutes = request.duration_minutes(max_duration_minutes);
 
     let session_repo = SessionRepository::new(state.db.clone());
     
-    // Generate creator ID for anonymous session
-    let creator_id = Uuid::new_v4();
+    // Reuse the caller's creator ID or generate one for a new anonymous creator
+    let creator_id = request.creator_id.unwrap_or_else(Uuid::new_v4);
+
+    // Best-effort for anonymous creators, who can always omit their ID
+    let max_sessions = state.config.app.max_active_sessions_per_creator;
+    let active_sessions = session_repo
+        .count_active_sessions_for_creator(creator_id)
+        .await.map_err(ApiError)?;
+    if active_sessions >= max_sessions as i64 {
+        return Err(ApiError(AppError::SessionLimitExceeded { max: max_sessions }));
+    }
     
-    // Sanitize session name or generate one if not provided
+    // Sanitize session name, or generate one if not provided and enabled
     let session_name = match request.name {
         Some(name) if !name.trim().is_empty() => Some(sanitize_session_name(&name)),
-        _ => Some(generate_session_name()),
+        _ if state.config.app.auto_generate_session_names => Some(generate_session_name()),
+        _ => None,
     };
 
     // Create the session
     let session = session_repo
-        .create_session(session_name.clone(), request.expires_in_minutes, creator_id)
+        .create_session(
+            session_name.clone(),
+            duration_minutes,
+            creator_id,
+            request.avatar_palette.filter(|palette| !palette.is_empty()),
+            request.is_public,
+            normalize_session_tags(&request.tags),
+            request.welcome_message.as_deref().and_then(sanitize_welcome_message),
+            request.view_only,
+            request.waitlist_enabled,
+            request.interpolate_locations,
+            request.join_window_minutes,
+            request.metadata.unwrap_or_else(|| json!({})),
+        )
         .await.map_err(ApiError)?;
 
     // Generate join link
-    let join_link = generate_join_link(session.id, &state.config.app.base_url);
+    let join_link = generate_join_link(session.join_token, &state.config.app.base_url, &state.config.app.base_path);
 
     info!("Created session {} with name: {:?}", session.id, session_name);
 
-    let response = CreateSessionResponse {
+    cache_session_info(state, &session).await;
+
+    AuditRepository::new(state.db.clone())
+        .record(
+            AuditEventType::SessionCreated,
+            session.id,
+            Some(&creator_id.to_string()),
+            json!({
+                "name": session_name,
+                "expires_at": session.expires_at,
+                "is_public": session.is_public,
+                "tags": session.tags,
+            }),
+        )
+        .await;
+
+    Ok(CreateSessionResponse {
         session_id: session.id,
+        creator_id,
         join_link,
         expires_at: session.expires_at,
         name: session_name,
+    })
+}
+
+/// Cache the info WebSocket clients get from `get_session_info`, so asking
+/// doesn't cost them a database query
+///
+/// Logged rather than failed: the WebSocket server falls back to the database.
+async fn cache_session_info(state: &AppState, session: &Session) {
+    let info = SessionInfoData {
+        name: session.name.clone(),
+        welcome_message: session.welcome_message.clone(),
+        expires_at: session.expires_at,
+        is_public: session.is_public,
+        participant_count: None,
     };
+    let ttl_seconds = (session.expires_at - Utc::now()).num_seconds().max(1) as u64;
+
+    if let Err(e) = state.redis.cache_session_info(&session.id, &info, ttl_seconds).await {
+        warn!("Failed to cache info for session {}: {}", session.id, e);
+    }
+}
+
+/// Read and validate the optional `Idempotency-Key` header
+fn idempotency_key(headers: &HeaderMap) -> Result<Option<String>, ApiError> {
+    let Some(value) = headers.get("idempotency-key") else {
+        return Ok(None);
+    };
+
+    match value.to_str() {
+        Ok(key) if is_valid_idempotency_key(key) => Ok(Some(key.to_string())),
+        _ => Err(ApiError(AppError::validation(
+            "Idempotency-Key",
+            &format!(
+                "Idempotency-Key must be 1-{} visible ASCII characters",
+                Constants::MAX_IDEMPOTENCY_KEY_LENGTH
+            ),
+        ))),
+    }
+}
+
+/// List open public sessions with their join links
+///
+/// Only sessions created with `is_public` appear; private sessions are
+/// never listed, whatever their state. `?tag=` keeps only sessions carrying
+/// that tag.
+pub async fn list_public_sessions(
+    State(state): State<AppState>,
+    Query(query): Query<PublicSessionsQuery>,
+) -> Result<Json<Paginated<PublicSessionSummary>>, ApiError> {
+    let tag = query.tag.map(|tag| tag.trim().to_lowercase());
+    let session_repo = SessionRepository::new(state.db.clone());
+    let sessions = session_repo.list_public_sessions(tag.as_deref()).await.map_err(ApiError)?;
+
+    let app = &state.config.app;
+    let sessions: Vec<PublicSessionSummary> = sessions
+        .into_iter()
+        .map(|session| PublicSessionSummary {
+            id: session.id,
+            name: session.name,
+            participant_count: session.participant_count,
+            expires_at: session.expires_at,
+            join_link: generate_join_link(session.join_token, &app.base_url, &app.base_path),
+            tags: session.tags,
+        })
+        .collect();
 
-    Ok(Json(response))
+    debug!("Listing {} public sessions", sessions.len());
+    Ok(Json(Paginated::all(sessions)))
 }
 
 /// Get session details
+///
+/// Responses carry a weak `ETag`; a matching `If-None-Match` gets
+/// `304 Not Modified` so polling clients skip unchanged bodies.
 pub async fn get_session(
     State(state): State<AppState>,
     Path(session_id): Path<Uuid>,
-) -> Result<Json<SessionDetailsResponse>, ApiError> {
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
     debug!("Getting session details for: {}", session_id);
 
     let session_repo = SessionRepository::new(state.db.clone());
     let session_details = session_repo.get_session_details(session_id).await.map_err(ApiError)?;
 
+    let etag = session_etag(&session_details);
+    let etag_header = [(header::ETAG, etag.clone())];
+    if if_none_match(&headers, &etag) {
+        debug!("Session {} unchanged, returning 304", session_id);
+        return Ok((StatusCode::NOT_MODIFIED, etag_header).into_response());
+    }
+
     debug!("Retrieved session details: {:?}", session_details);
-    Ok(Json(session_details))
+    Ok((etag_header, CasedJson::new(session_details, &state.config)).into_response())
+}
+
+/// Change a running session's settings (creator only)
+///
+/// Tags replace the session's current ones; metadata is merged into the
+/// current object. Returns the updated session details.
+pub async fn update_session(
+    State(state): State<AppState>,
+    Path(session_id): Path<Uuid>,
+    headers: HeaderMap,
+    ApiJson(request): ApiJson<UpdateSessionRequest>,
+) -> Result<CasedJson<SessionDetailsResponse>, ApiError> {
+    debug!("Updating session {} with request: {:?}", session_id, request);
+
+    request.validate().map_err(|errors| ApiError(AppError::validation_errors(errors)))?;
+
+    let session_repo = SessionRepository::new(state.db.clone());
+    let session = session_repo.get_session(session_id).await.map_err(ApiError)?;
+
+    if !is_session_creator(&state, &session, &headers).await.map_err(ApiError)? {
+        warn!("Non-creator attempted to update session {}", session_id);
+        return Err(ApiError(AppError::UnauthorizedSessionOperation));
+    }
+
+    if let Some(tags) = &request.tags {
+        session_repo
+            .update_tags(session_id, normalize_session_tags(tags))
+            .await
+            .map_err(ApiError)?;
+    }
+
+    if let Some(metadata) = &request.metadata {
+        session_repo.merge_metadata(session_id, metadata).await.map_err(ApiError)?;
+    }
+
+    info!("Updated session {}", session_id);
+
+    cache_session_info(&state, &session).await;
+
+    let session_details = session_repo.get_session_details(session_id).await.map_err(ApiError)?;
+    Ok(CasedJson::new(session_details, &state.config))
+}
+
+/// Weak ETag covering the parts of the session that change while it runs
+fn session_etag(details: &SessionDetailsResponse) -> HeaderValue {
+    let etag = format!(
+        "W/\"{:x}-{}\"",
+        details.last_activity.timestamp_micros(),
+        details.participant_count
+    );
+    HeaderValue::from_str(&etag).expect("ETag is always a valid header value")
+}
+
+/// Whether the request's `If-None-Match` matches the current ETag
+///
+/// Uses weak comparison, so the `W/` prefix is ignored on either side.
+fn if_none_match(headers: &HeaderMap, etag: &HeaderValue) -> bool {
+    let Some(value) = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+    else {
+        return false;
+    };
+    let current = etag.to_str().unwrap_or_default().trim_start_matches("W/");
+
+    value
+        .split(',')
+        .map(str::trim)
+        .any(|candidate| candidate == "*" || candidate.trim_start_matches("W/") == current)
 }
 
 /// Join a session
+///
+/// The path carries the join token from the session's join link rather than
+/// the session ID, so rotating the link locks out anyone holding the old one.
 pub async fn join_session(
     State(state): State<AppState>,
-    Path(session_id): Path<Uuid>,
-    Json(request): Json<JoinSessionRequest>,
-) -> Result<Json<JoinSessionResponse>, ApiError> {
-    debug!("Joining session {} with request: {:?}", session_id, request);
+    Path(join_token): Path<Uuid>,
+    headers: HeaderMap,
+    ApiJson(request): ApiJson<JoinSessionRequest>,
+) -> Result<Response, ApiError> {
+    debug!("Joining session by join token with request: {:?}", request);
 
     // Validate request
-    request.validate().map_err(|msg| ApiError(AppError::validation("request", &msg)))?;
+    request.validate().map_err(|errors| ApiError(AppError::validation_errors(errors)))?;
 
     let session_repo = SessionRepository::new(state.db.clone());
     
     // Verify session exists and is active
-    let _session = session_repo.get_session(session_id).await.map_err(ApiError)?;
+    let session = session_repo.get_session_by_join_token(join_token).await.map_err(ApiError)?;
+    let session_id = session.id;
 
-    // Check if session can accept more participants
-    if !session_repo.can_accept_participants(session_id).await.map_err(ApiError)? {
+    // Joiners of a view-only session watch unless the creator lets them publish
+    let role = match request.role {
+        Some(ParticipantRole::Publisher) if session.view_only => {
+            if !is_session_creator(&state, &session, &headers).await.map_err(ApiError)? {
+                warn!("Non-creator attempted to join view-only session {} as a publisher", session_id);
+                return Err(ApiError(AppError::InsufficientPermissions));
+            }
+            ParticipantRole::Publisher
+        }
+        Some(role) => role,
+        None if session.view_only => ParticipantRole::Viewer,
+        None => ParticipantRole::Publisher,
+    };
+
+    let participant_repo = crate::models::ParticipantRepository::new(state.db.clone());
+
+    // A known device gets its previous user ID back; otherwise a fresh one
+    let (user_id, existing) = match &request.device_id {
+        Some(device_id) => {
+            let user_id = derive_user_id(&session_id, device_id);
+            let existing = match participant_repo.get_participant(session_id, &user_id).await {
+                Ok(participant) => Some(participant),
+                Err(AppError::ParticipantNotFound) => None,
+                Err(e) => return Err(ApiError(e)),
+            };
+            (user_id, existing)
+        }
+        None => (generate_user_id(), None),
+    };
+
+    // Once the join window closes only participants who joined before can come back
+    if existing.is_none() && session.join_window_closed(Utc::now()) {
+        warn!("Rejected join of session {} after its join window closed", session_id);
+        return Err(ApiError(AppError::JoinWindowClosed));
+    }
+
+    // Check if session can accept more participants; an active participant
+    // rejoining from the same device doesn't take another slot
+    let already_active = existing.as_ref().is_some_and(|participant| participant.is_active);
+    if !already_active && session.waitlist_enabled {
+        // Queued joiners get free slots first; anyone still queued after that
+        // is ahead of this joiner
+        if let Err(e) = promote_from_waitlist(&state, session_id).await {
+            warn!("Failed to promote from the waitlist of session {}: {}", session_id, e);
+        }
+        if state.redis.waitlist_length(&session_id).await.map_err(ApiError)? > 0 {
+            return join_waitlist(&state, &session, user_id, role, request).await;
+        }
+    }
+    if !already_active && !session_repo.can_accept_participants(session_id).await.map_err(ApiError)? {
+        if session.waitlist_enabled {
+            return join_waitlist(&state, &session, user_id, role, request).await;
+        }
         return Err(ApiError(AppError::SessionCapacityExceeded {
             max: Constants::MAX_PARTICIPANTS_PER_SESSION,
         }));
     }
 
-    // Generate user ID
-    let user_id = generate_user_id();
+    let participant = admit_participant(&state, &session, &user_id, existing.is_some(), request, role)
+        .await
+        .map_err(ApiError)?;
+    let response = join_response(&state, &participant)?;
+
+    Ok((StatusCode::CREATED, CasedJson::new(response, &state.config)).into_response())
+}
 
-    // Create participant
+/// Add a joiner to a session, reactivating them if their device has joined before
+async fn admit_participant(
+    state: &AppState,
+    session: &Session,
+    user_id: &str,
+    rejoining: bool,
+    request: JoinSessionRequest,
+    role: ParticipantRole,
+) -> AppResult<Participant> {
+    let session_id = session.id;
     let participant_repo = crate::models::ParticipantRepository::new(state.db.clone());
-    let _participant = participant_repo
-        .create_participant(
+
+    let participant = if rejoining {
+        // Rejoining keeps the participant's profile, color and trail
+        participant_repo.reactivate_participant(session_id, user_id).await?
+    } else {
+        // Use the requested color, or pick an unreserved one from the session's palette
+        let reserved_colors = &state.config.app.reserved_avatar_colors;
+        let avatar_color = match request.avatar_color.as_deref().and_then(normalize_hex_color) {
+            Some(color) => color,
+            None => {
+                let palette = session.avatar_palette.as_deref().unwrap_or(&state.config.app.avatar_palette);
+                participant_repo
+                    .pick_avatar_color(
+                        session_id,
+                        user_id,
+                        palette,
+                        reserved_colors,
+                        state.config.app.distinct_avatar_colors,
+                    )
+                    .await?
+            }
+        };
+
+        participant_repo
+            .create_participant(
+                session_id,
+                user_id.to_string(),
+                request.display_name,
+                avatar_color,
+                reserved_colors,
+                &state.config.app.display_name_denylist,
+                request.precision,
+                role,
+            )
+            .await?
+    };
+
+    info!("User {} joined session {}", user_id, session_id);
+
+    // A rejoin starts a new stay, whose departure must be announced again
+    if let Err(e) = state.redis.clear_participant_left(&session_id, user_id).await {
+        warn!("Failed to clear departure tombstone for {}: {}", user_id, e);
+    }
+
+    AuditRepository::new(state.db.clone())
+        .record(
+            AuditEventType::ParticipantJoined,
             session_id,
-            user_id.clone(),
-            request.display_name,
-            request.avatar_color,
+            Some(user_id),
+            json!({
+                "display_name": participant.display_name,
+                "rejoined": rejoining,
+                "role": participant.role,
+            }),
         )
-        .await.map_err(ApiError)?;
+        .await;
 
-    // Generate JWT token for WebSocket authentication
-    let claims = JwtClaims {
-        sub: user_id.clone(),
-        session_id,
-        exp: (Utc::now() + Duration::hours(Constants::WS_TOKEN_DURATION_HOURS)).timestamp(),
-        iat: Utc::now().timestamp(),
-    };
+    Ok(participant)
+}
 
-    let token = encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(state.config.jwt.secret.as_ref()),
-    ).map_err(|e| ApiError(AppError::from(e)))?;
+/// Build a participant's join response with a fresh WebSocket token
+fn join_response(state: &AppState, participant: &Participant) -> Result<JoinSessionResponse, ApiError> {
+    // Generate JWT token for WebSocket authentication
+    let (token, _) = issue_websocket_token(
+        &participant.user_id,
+        participant.session_id,
+        participant.location_precision,
+        participant.role,
+        &state.config.jwt,
+    )
+    .map_err(ApiError)?;
 
     // Generate WebSocket URL
-    let websocket_url = generate_websocket_url(&state.config.app.base_ws_url);
-
-    info!("User {} joined session {}", user_id, session_id);
+    let app = &state.config.app;
+    let websocket_url = generate_websocket_url(&app.base_ws_url, &app.base_path, &app.ws_path);
 
-    let response = JoinSessionResponse {
-        user_id: Uuid::parse_str(&user_id).map_err(|e| ApiError(AppError::from(e)))?,
+    Ok(JoinSessionResponse {
+        session_id: participant.session_id,
+        user_id: Uuid::parse_str(&participant.user_id).map_err(|e| ApiError(AppError::from(e)))?,
+        role: participant.role,
         websocket_token: token,
         websocket_url,
+    })
+}
+
+/// Queue a joiner of a full session for the next free slot
+///
+/// The display name and color are checked now, so a queued joiner isn't
+/// turned away only once a slot opens.
+async fn join_waitlist(
+    state: &AppState,
+    session: &Session,
+    user_id: String,
+    role: ParticipantRole,
+    request: JoinSessionRequest,
+) -> Result<Response, ApiError> {
+    let app = &state.config.app;
+    validate_display_name(&request.display_name, &app.display_name_denylist)
+        .map_err(|msg| ApiError(AppError::invalid_participant_data(&msg)))?;
+    if request
+        .avatar_color
+        .as_deref()
+        .is_some_and(|color| is_reserved_avatar_color(color, &app.reserved_avatar_colors))
+    {
+        return Err(ApiError(AppError::invalid_participant_data("Avatar color is reserved")));
+    }
+
+    let ticket = Uuid::new_v4();
+    let entry = serde_json::to_string(&WaitlistEntry { user_id, role, request, failure: None })
+        .map_err(|e| ApiError(AppError::from(e)))?;
+    let ttl_seconds = (session.expires_at - Utc::now()).num_seconds().max(1) as u64;
+    let position = state
+        .redis
+        .enqueue_waitlist(&session.id, &ticket, &entry, ttl_seconds)
+        .await
+        .map_err(ApiError)?;
+
+    info!("Session {} is full, queued ticket {} at position {}", session.id, ticket, position);
+
+    let response = JoinQueuedResponse {
+        session_id: session.id,
+        ticket,
+        position,
     };
+    Ok((StatusCode::ACCEPTED, CasedJson::new(response, &state.config)).into_response())
+}
+
+/// Check a waitlist ticket
+///
+/// Like joins, this is keyed by the join link's token. A queued ticket gets
+/// `202` with its position; once promoted, the join response with a fresh
+/// WebSocket token. Queued joiners aren't notified, so they poll this.
+///
+/// Slots also free up where promotion doesn't run, e.g. when the WebSocket
+/// server deactivates idle participants, so a poll of a queued ticket first
+/// admits whoever fits.
+pub async fn get_waitlist_ticket(
+    State(state): State<AppState>,
+    Path((join_token, ticket)): Path<(Uuid, Uuid)>,
+) -> Result<Response, ApiError> {
+    let session_repo = SessionRepository::new(state.db.clone());
+    let session = session_repo.get_session_by_join_token(join_token).await.map_err(ApiError)?;
+
+    let mut position = state.redis.waitlist_position(&session.id, &ticket).await.map_err(ApiError)?;
+    if position.is_some() {
+        if let Err(e) = promote_from_waitlist(&state, session.id).await {
+            warn!("Failed to promote from the waitlist of session {}: {}", session.id, e);
+        }
+        position = state.redis.waitlist_position(&session.id, &ticket).await.map_err(ApiError)?;
+    }
+
+    if let Some(position) = position {
+        let response = JoinQueuedResponse {
+            session_id: session.id,
+            ticket,
+            position,
+        };
+        return Ok((StatusCode::ACCEPTED, CasedJson::new(response, &state.config)).into_response());
+    }
+
+    let Some(entry) = state.redis.get_waitlist_entry(&session.id, &ticket).await.map_err(ApiError)? else {
+        return Err(ApiError(AppError::ParticipantNotFound));
+    };
+    let entry: WaitlistEntry = serde_json::from_str(&entry).map_err(|e| ApiError(AppError::from(e)))?;
+    if let Some(reason) = entry.failure {
+        return Err(ApiError(AppError::WaitlistAdmissionFailed { reason }));
+    }
+
+    // Promoted participants who have since left don't get back in this way
+    let participant_repo = crate::models::ParticipantRepository::new(state.db.clone());
+    let participant = participant_repo
+        .get_participant(session.id, &entry.user_id)
+        .await
+        .map_err(ApiError)?;
+    if !participant.is_active {
+        return Err(ApiError(AppError::ParticipantNotFound));
+    }
+
+    debug!("Ticket {} was promoted to participant {}", ticket, participant.user_id);
+    let response = join_response(&state, &participant)?;
+    Ok(CasedJson::new(response, &state.config).into_response())
+}
+
+/// Admit queued joiners while a session with a waitlist has free slots
+///
+/// Called once a participant leaves, when a queued ticket is checked, and
+/// before a direct join takes a slot. Tickets whose joiner is already back
+/// in, e.g. by rejoining from the same device, are passed over; promoted
+/// joiners collect their token through their ticket. A joiner turned away on
+/// admission keeps their ticket, which reports why; one that failed on a
+/// server error, or found the session full after all, goes back to the head
+/// of the queue.
+pub(crate) async fn promote_from_waitlist(state: &AppState, session_id: Uuid) -> AppResult<()> {
+    let session_repo = SessionRepository::new(state.db.clone());
+    let session = session_repo.get_session(session_id).await?;
+    if !session.waitlist_enabled {
+        return Ok(());
+    }
+
+    let participant_repo = crate::models::ParticipantRepository::new(state.db.clone());
+    while session_repo.can_accept_participants(session_id).await? {
+        let Some((ticket, stored)) = state.redis.pop_waitlist(&session_id).await? else {
+            break;
+        };
+        let entry: WaitlistEntry = serde_json::from_str(&stored)?;
+
+        let existing = match participant_repo.get_participant(session_id, &entry.user_id).await {
+            Ok(participant) => Some(participant),
+            Err(AppError::ParticipantNotFound) => None,
+            Err(e) => return Err(e),
+        };
+        if existing.as_ref().is_some_and(|participant| participant.is_active) {
+            continue;
+        }
+
+        match admit_participant(state, &session, &entry.user_id, existing.is_some(), entry.request, entry.role).await {
+            Ok(_) => info!("Promoted ticket {} off the waitlist of session {}", ticket, session_id),
+            Err(e) if e.is_client_error() && !matches!(e, AppError::SessionCapacityExceeded { .. }) => {
+                warn!("Ticket {} in session {} was turned away: {}", ticket, session_id, e);
+                let mut failed: WaitlistEntry = serde_json::from_str(&stored)?;
+                failed.failure = Some(e.to_string());
+                state
+                    .redis
+                    .update_waitlist_entry(&session_id, &ticket, &serde_json::to_string(&failed)?)
+                    .await?;
+            }
+            Err(e) => {
+                state.redis.requeue_waitlist(&session_id, &ticket).await?;
+                return match e {
+                    AppError::SessionCapacityExceeded { .. } => Ok(()),
+                    e => Err(e),
+                };
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Issue a new join link for a session (creator only)
+///
+/// Links built from the previous join token stop working; the session ID and
+/// existing participants are unaffected.
+pub async fn rotate_join_link(
+    State(state): State<AppState>,
+    Path(session_id): Path<Uuid>,
+    headers: HeaderMap,
+) -> Result<Json<RotateJoinLinkResponse>, ApiError> {
+    debug!("Rotating join link for session: {}", session_id);
+
+    let session_repo = SessionRepository::new(state.db.clone());
+    let session = session_repo.get_session(session_id).await.map_err(ApiError)?;
+
+    if !is_session_creator(&state, &session, &headers).await.map_err(ApiError)? {
+        warn!("Non-creator attempted to rotate the join link of session {}", session_id);
+        return Err(ApiError(AppError::UnauthorizedSessionOperation));
+    }
+
+    let join_token = session_repo.rotate_join_token(session_id).await.map_err(ApiError)?;
+    let join_link = generate_join_link(join_token, &state.config.app.base_url, &state.config.app.base_path);
+
+    info!("Rotated join link for session {}", session_id);
+
+    Ok(Json(RotateJoinLinkResponse { join_link }))
+}
+
+/// Get a session's join link as a QR code (creator only)
+///
+/// Responds with a PNG when the `Accept` header asks for `image/png`, and
+/// with the code's contents as JSON otherwise.
+pub async fn get_session_qr_code(
+    State(state): State<AppState>,
+    Path(session_id): Path<Uuid>,
+    Query(query): Query<SessionQrCodeQuery>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    debug!("Getting QR code for session: {}", session_id);
+
+    let session_repo = SessionRepository::new(state.db.clone());
+    let session = session_repo.get_session(session_id).await.map_err(ApiError)?;
+
+    if !is_session_creator(&state, &session, &headers).await.map_err(ApiError)? {
+        warn!("Non-creator attempted to get the QR code of session {}", session_id);
+        return Err(ApiError(AppError::UnauthorizedSessionOperation));
+    }
+
+    let join_link = generate_join_link(session.join_token, &state.config.app.base_url, &state.config.app.base_path);
+    if !accepts_png(&headers) {
+        let response = SessionQrCodeResponse {
+            payload: session.join_token.to_string(),
+            url: join_link,
+        };
+        return Ok(CasedJson::new(response, &state.config).into_response());
+    }
+
+    let size = query.size.unwrap_or(Constants::QR_CODE_DEFAULT_SIZE);
+    if !(Constants::QR_CODE_MIN_SIZE..=Constants::QR_CODE_MAX_SIZE).contains(&size) {
+        return Err(ApiError(AppError::validation(
+            "size",
+            &format!(
+                "QR code size must be between {} and {} pixels",
+                Constants::QR_CODE_MIN_SIZE,
+                Constants::QR_CODE_MAX_SIZE
+            ),
+        )));
+    }
+
+    let png = render_qr_code_png(&join_link, size).map_err(ApiError)?;
+    Ok(([(header::CONTENT_TYPE, "image/png")], png).into_response())
+}
+
+/// Whether the `Accept` header lists `image/png`
+fn accepts_png(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|accept| {
+            accept
+                .split(',')
+                .any(|media_type| media_type.split(';').next().map(str::trim) == Some("image/png"))
+        })
+}
+
+/// Render `text` as a QR code PNG at least `size` pixels across
+fn render_qr_code_png(text: &str, size: u32) -> AppResult<Vec<u8>> {
+    let code = qrcode::QrCode::new(text.as_bytes()).map_err(|e| AppError::Internal(anyhow::anyhow!(e)))?;
+    let image = code
+        .render::<image::Luma<u8>>()
+        .min_dimensions(size, size)
+        .build();
 
-    Ok(Json(response))
+    let mut png = Vec::new();
+    image
+        .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+        .map_err(|e| AppError::Internal(anyhow::anyhow!(e)))?;
+    Ok(png)
 }
 
 /// End a session (creator only)
 pub async fn end_session(
     State(state): State<AppState>,
     Path(session_id): Path<Uuid>,
-    // TODO: Add authentication to get the requester ID
-    // For now, we'll use a placeholder approach
+    headers: HeaderMap,
 ) -> Result<Json<SuccessResponse>, ApiError> {
     debug!("Ending session: {}", session_id);
 
@@ -150,11 +802,64 @@ pub async fn end_session(
     // Get session to verify it exists
     let session = session_repo.get_session(session_id).await.map_err(ApiError)?;
     
-    // For MVP without authentication, allow ending by creator_id
-    // In production, this would need proper authentication
+    if !is_session_creator(&state, &session, &headers).await.map_err(ApiError)? {
+        warn!("Non-creator attempted to end session {}", session_id);
+        return Err(ApiError(AppError::UnauthorizedSessionOperation));
+    }
+
     session_repo.end_session(session_id, session.creator_id).await.map_err(ApiError)?;
 
     info!("Ended session: {}", session_id);
 
+    AuditRepository::new(state.db.clone())
+        .record(
+            AuditEventType::SessionEnded,
+            session_id,
+            Some(&session.creator_id.to_string()),
+            json!({}),
+        )
+        .await;
+
+    Ok(Json(SuccessResponse { success: true }))
+}
+
+/// Hand a session over to one of its active participants (creator only)
+///
+/// The participant's user ID becomes the creator ID, and from then on they
+/// authorize creator-only operations with their participant token. Everyone
+/// in the session is told who the new owner is.
+pub async fn transfer_ownership(
+    State(state): State<AppState>,
+    Path(session_id): Path<Uuid>,
+    headers: HeaderMap,
+    ApiJson(request): ApiJson<TransferOwnershipRequest>,
+) -> Result<Json<SuccessResponse>, ApiError> {
+    debug!("Transferring session {} to {}", session_id, request.new_creator_id);
+
+    let session_repo = SessionRepository::new(state.db.clone());
+    let session = session_repo.get_session(session_id).await.map_err(ApiError)?;
+
+    if !is_session_creator(&state, &session, &headers).await.map_err(ApiError)? {
+        warn!("Non-creator attempted to transfer session {}", session_id);
+        return Err(ApiError(AppError::UnauthorizedSessionOperation));
+    }
+
+    session_repo
+        .transfer_ownership(session_id, request.new_creator_id)
+        .await
+        .map_err(ApiError)?;
+
+    let message = WebSocketMessage::OwnershipTransferred(OwnershipTransferredData {
+        new_creator_id: request.new_creator_id,
+    });
+    let message_json = serde_json::to_string(&message).map_err(|e| ApiError(AppError::from(e)))?;
+
+    info!("Transferred session {} to {}", session_id, request.new_creator_id);
+
+    // The transfer is committed either way, so a failed announcement only warns
+    if let Err(e) = state.redis.publish_to_session(&session_id, &message_json).await {
+        warn!("Failed to announce ownership transfer of session {}: {}", session_id, e);
+    }
+
     Ok(Json(SuccessResponse { success: true }))
 }
\ No newline at end of file
@@ -0,0 +1,207 @@
+// The OpenAPI document's `json!` literals nest deeper than the default allows
+#![recursion_limit = "256"]
+
+use axum::{
+    extract::{DefaultBodyLimit, State},
+    http::StatusCode,
+    middleware::{from_fn, from_fn_with_state},
+    routing::{delete, get, patch, post},
+    Json, Router,
+};
+use shared::{AppConfig, AppResult};
+use sqlx::PgPool;
+use std::sync::Arc;
+use tower::ServiceBuilder;
+use tower_http::trace::TraceLayer;
+use tracing::warn;
+
+pub mod auth;
+pub mod config;
+pub mod database;
+pub mod error;
+pub mod extract;
+pub mod handlers;
+pub mod middleware;
+pub mod models;
+pub mod redis;
+#[cfg(unix)]
+pub mod unix_socket;
+
+use error::handle_error;
+use handlers::{admin, locations, openapi, participants, sessions};
+use serde_json::json;
+use middleware::compression::response_compression;
+use middleware::cors::cors_layer;
+use middleware::rate_limit::rate_limit;
+use middleware::request_id::request_id;
+use middleware::timeout::request_timeout;
+use redis::client::RedisClient;
+
+/// Application state shared across all handlers
+#[derive(Clone)]
+pub struct AppState {
+    pub db: PgPool,
+    pub redis: RedisClient,
+    pub config: Arc<AppConfig>,
+}
+
+/// Readiness endpoint, also served as `/health`
+///
+/// Reports each dependency separately and responds with 503 if any is down,
+/// so load balancers stop routing to an instance that can't serve requests.
+async fn readiness_check(State(state): State<AppState>) -> (StatusCode, Json<serde_json::Value>) {
+    let (db, redis) = tokio::join!(
+        database::postgres::health_check(&state.db),
+        state.redis.health_check(),
+    );
+
+    let component_status = |name: &str, result: &AppResult<()>| match result {
+        Ok(()) => "ok",
+        Err(e) => {
+            warn!("Health check failed for {}: {}", name, e);
+            "down"
+        }
+    };
+    let components = json!({
+        "db": component_status("database", &db),
+        "redis": component_status("redis", &redis),
+    });
+
+    let healthy = db.is_ok() && redis.is_ok();
+    let status = if healthy { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+
+    let response = json!({
+        "status": if healthy { "healthy" } else { "unhealthy" },
+        "components": components,
+        "timestamp": chrono::Utc::now(),
+        "service": "api-server",
+        "version": env!("CARGO_PKG_VERSION")
+    });
+
+    (status, Json(response))
+}
+
+/// Liveness endpoint, reporting only that the process is serving requests
+async fn liveness_check() -> Json<serde_json::Value> {
+    Json(json!({ "status": "alive" }))
+}
+
+/// Create the main application router with all routes and middleware
+pub async fn create_router(state: AppState) -> AppResult<Router> {
+    let api_routes = Router::new()
+        // Session management routes (writes are rate limited per client IP)
+        .route(
+            "/sessions",
+            post(sessions::create_session)
+                .layer(from_fn_with_state(state.clone(), rate_limit)),
+        )
+        .route("/sessions/public", get(sessions::list_public_sessions))
+        .route("/sessions/:session_id", get(sessions::get_session))
+        .route("/sessions/:session_id", delete(sessions::end_session))
+        .route("/sessions/:session_id", patch(sessions::update_session))
+        // Joins are keyed by the join link's token, not the session ID; the
+        // segment keeps the `:session_id` name shared with its sibling routes
+        .route(
+            "/sessions/:session_id/join",
+            post(sessions::join_session)
+                .layer(from_fn_with_state(state.clone(), rate_limit)),
+        )
+        .route(
+            "/sessions/:session_id/waitlist/:ticket",
+            get(sessions::get_waitlist_ticket),
+        )
+        .route(
+            "/sessions/:session_id/rotate-link",
+            post(sessions::rotate_join_link),
+        )
+        .route(
+            "/sessions/:session_id/qr",
+            get(sessions::get_session_qr_code),
+        )
+        .route(
+            "/sessions/:session_id/transfer",
+            post(sessions::transfer_ownership),
+        )
+        .route(
+            "/sessions/:session_id/locations",
+            get(locations::get_session_locations).post(locations::ingest_locations),
+        )
+        .route(
+            "/sessions/:session_id/centroid",
+            get(locations::get_session_centroid),
+        )
+        .route(
+            "/sessions/:session_id/track.geojson",
+            get(locations::get_session_track),
+        )
+        // Participant management routes
+        .route(
+            "/sessions/:session_id/participants",
+            get(participants::list_participants),
+        )
+        .route(
+            "/sessions/:session_id/participants/remove",
+            post(participants::remove_participants),
+        )
+        .route(
+            "/sessions/:session_id/participants/:user_id",
+            delete(participants::leave_session),
+        )
+        .route(
+            "/sessions/:session_id/participants/:user_id/heartbeat",
+            post(participants::heartbeat),
+        )
+        .route(
+            "/sessions/:session_id/participants/:user_id/refresh-token",
+            post(participants::refresh_token),
+        )
+        .route(
+            "/sessions/:session_id/participants/:user_id/location",
+            delete(participants::delete_location),
+        )
+        .route(
+            "/sessions/:session_id/participants/:user_id/stats",
+            get(locations::get_participant_stats),
+        )
+        // Monitoring routes, authorized by the admin token
+        .route("/admin/sessions", get(admin::list_sessions))
+        .route("/admin/sessions/:session_id/audit", get(admin::list_audit_events))
+        .route("/openapi.json", get(openapi::openapi_spec))
+        .layer(request_timeout(&state.config))
+        // Health check routes, added after the timeout so probes report slow
+        // dependencies themselves rather than being cut off
+        .route("/health", get(readiness_check))
+        .route("/health/ready", get(readiness_check))
+        .route("/health/live", get(liveness_check))
+        .with_state(state.clone());
+
+    // Add root health check as well, reachable with and without the base path
+    let base_path = state.config.app.base_path.as_str();
+    let mut root_routes = Router::new()
+        .route("/health", get(readiness_check))
+        .route("/health/ready", get(readiness_check))
+        .route("/health/live", get(liveness_check));
+    if !base_path.is_empty() {
+        root_routes = root_routes
+            .route(&format!("{}/health", base_path), get(readiness_check))
+            .route(&format!("{}/health/ready", base_path), get(readiness_check))
+            .route(&format!("{}/health/live", base_path), get(liveness_check));
+    }
+    let root_routes = root_routes.with_state(state.clone());
+
+    let app = Router::new()
+        .merge(root_routes)
+        .nest(&format!("{}/api", base_path), api_routes)
+        .layer(
+            ServiceBuilder::new()
+                .layer(from_fn(request_id))
+                .layer(TraceLayer::new_for_http())
+                .layer(response_compression(&state.config))
+                .layer(cors_layer(&state.config))
+                .layer(DefaultBodyLimit::max(state.config.server.max_request_size as usize))
+                .into_inner(),
+        )
+        .fallback(handle_error);
+
+    Ok(app)
+}
@@ -1,36 +1,11 @@
-use axum::{
-    extract::State,
-    routing::{delete, get, post},
-    Json, Router,
+use api_server::{
+    create_router, database::postgres::create_pool, redis::client::RedisClient, AppState,
 };
-use shared::{AppConfig, AppResult};
-use sqlx::PgPool;
-use std::sync::Arc;
-use tower::ServiceBuilder;
-use tower_http::trace::TraceLayer;
+use shared::{bind_tcp, fmt_layer, AppConfig, AppError, AppResult, BindAddress};
+use std::{net::SocketAddr, sync::Arc};
 use tracing::{info, warn};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-mod config;
-mod database;
-mod error;
-mod handlers;
-mod middleware;
-mod models;
-
-use database::postgres::create_pool;
-use error::handle_error;
-use handlers::{participants, sessions};
-use serde_json::json;
-use middleware::cors::cors_layer;
-
-/// Application state shared across all handlers
-#[derive(Clone)]
-pub struct AppState {
-    pub db: PgPool,
-    pub config: Arc<AppConfig>,
-}
-
 #[tokio::main]
 async fn main() -> AppResult<()> {
     // Load environment variables from .env file if present
@@ -70,82 +45,68 @@ async fn main() -> AppResult<()> {
         }
     }
 
+    // Create Redis client
+    let redis = RedisClient::new(&config.redis.url)
+        .await?
+        .with_key_prefix(&config.redis.key_prefix)
+        .with_max_publish_bytes(config.redis.max_publish_bytes)
+        .with_location_history_limits(
+            config.app.location_history_max_points,
+            config.app.location_history_ttl_seconds,
+        );
+
     // Create application state
     let state = AppState {
         db,
+        redis,
         config: Arc::clone(&config),
     };
 
     // Build the application router
     let app = create_router(state).await?;
 
-    // Create server address
-    let addr = config.api_address();
-    info!("API server listening on {}", addr);
-
-    // Start the server
-    let listener = tokio::net::TcpListener::bind(&addr).await?;
-    axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_signal())
-        .await?;
+    // Start the server on the configured TCP address or Unix socket
+    let bind_address = config
+        .api_bind_address()
+        .map_err(|e| AppError::Internal(anyhow::anyhow!(e)))?;
+    match bind_address {
+        BindAddress::Tcp(addr) => {
+            info!("API server listening on {}", addr);
+
+            let listener = tokio::net::TcpListener::from_std(bind_tcp(addr)?)?;
+            axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+                .with_graceful_shutdown(shutdown_signal())
+                .await?;
+        }
+        BindAddress::Unix(path) => serve_unix_socket(&path, app).await?,
+    }
 
     info!("API server shutdown complete");
     Ok(())
 }
 
-/// Health check endpoint
-async fn health_check(State(state): State<AppState>) -> Result<Json<serde_json::Value>, error::ApiError> {
-    // Check database connection
-    database::postgres::health_check(&state.db).await.map_err(error::ApiError)?;
-    
-    let response = json!({
-        "status": "healthy",
-        "timestamp": chrono::Utc::now(),
-        "service": "api-server",
-        "version": env!("CARGO_PKG_VERSION")
-    });
-    
-    Ok(Json(response))
-}
+/// Serve over a Unix domain socket, removing the socket file on shutdown
+#[cfg(unix)]
+async fn serve_unix_socket(path: &std::path::Path, app: axum::Router) -> AppResult<()> {
+    use api_server::unix_socket;
 
-/// Create the main application router with all routes and middleware
-async fn create_router(state: AppState) -> AppResult<Router> {
-    let api_routes = Router::new()
-        // Health check route
-        .route("/health", get(health_check))
-        // Session management routes
-        .route("/sessions", post(sessions::create_session))
-        .route("/sessions/:session_id", get(sessions::get_session))
-        .route("/sessions/:session_id", delete(sessions::end_session))
-        .route("/sessions/:session_id/join", post(sessions::join_session))
-        // Participant management routes
-        .route(
-            "/sessions/:session_id/participants",
-            get(participants::list_participants),
-        )
-        .route(
-            "/sessions/:session_id/participants/:user_id",
-            delete(participants::leave_session),
-        )
-        .with_state(state.clone());
-
-    // Add root health check as well
-    let root_routes = Router::new()
-        .route("/health", get(health_check))
-        .with_state(state.clone());
-
-    let app = Router::new()
-        .merge(root_routes)
-        .nest("/api", api_routes)
-        .layer(
-            ServiceBuilder::new()
-                .layer(TraceLayer::new_for_http())
-                .layer(cors_layer(&state.config))
-                .into_inner(),
-        )
-        .fallback(handle_error);
+    let listener = unix_socket::bind(path)?;
+    info!("API server listening on unix:{}", path.display());
+
+    unix_socket::serve(listener, app, shutdown_signal()).await?;
+
+    if let Err(e) = std::fs::remove_file(path) {
+        warn!("Failed to remove socket {}: {}", path.display(), e);
+    }
+    Ok(())
+}
 
-    Ok(app)
+#[cfg(not(unix))]
+async fn serve_unix_socket(path: &std::path::Path, _app: axum::Router) -> AppResult<()> {
+    Err(AppError::Internal(anyhow::anyhow!(
+        "Unix domain sockets are not supported on this platform ({})",
+        path.display()
+    )))
 }
 
 /// Initialize structured logging
@@ -157,7 +118,7 @@ fn init_logging(config: &AppConfig) -> AppResult<()> {
             tracing_subscriber::EnvFilter::try_from_default_env()
                 .unwrap_or_else(|_| format!("api_server={},tower_http=debug", log_level).into()),
         )
-        .with(tracing_subscriber::fmt::layer().with_target(false))
+        .with(fmt_layer(config, std::io::stdout))
         .init();
 
     Ok(())
@@ -190,4 +151,4 @@ async fn shutdown_signal() {
             info!("Received SIGTERM, initiating graceful shutdown");
         },
     }
-}
\ No newline at end of file
+}
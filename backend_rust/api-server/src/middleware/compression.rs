@@ -0,0 +1,13 @@
+use shared::AppConfig;
+use tower_http::compression::CompressionLayer;
+
+/// Compress responses with gzip or brotli, as the client's `Accept-Encoding`
+/// allows
+///
+/// tower-http's default predicate leaves small bodies, images and event
+/// streams uncompressed. When disabled (e.g. a proxy already compresses),
+/// no encoding is offered and responses pass through untouched.
+pub fn response_compression(config: &AppConfig) -> CompressionLayer {
+    let enabled = config.server.compression_enabled;
+    CompressionLayer::new().gzip(enabled).br(enabled)
+}
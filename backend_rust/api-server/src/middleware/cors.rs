@@ -1,26 +1,31 @@
-use axum::http::{HeaderValue, Method, header};
+use axum::http::{HeaderName, HeaderValue, Method};
 use shared::AppConfig;
-use tower_http::cors::{Any, CorsLayer};
+use tower_http::cors::{AllowHeaders, AllowMethods, Any, CorsLayer};
+use tracing::warn;
+
+use super::request_id::X_REQUEST_ID;
 
 /// Create CORS layer with configuration-based allowed origins
+///
+/// Development allows any origin unless credentials are enabled, which
+/// browsers only honor for explicitly listed origins.
 pub fn cors_layer(config: &AppConfig) -> CorsLayer {
+    let server = &config.server;
     let mut cors = CorsLayer::new()
-        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE, Method::OPTIONS])
-        .allow_headers([
-            header::CONTENT_TYPE,
-            header::AUTHORIZATION,
-            header::ACCEPT,
-            header::ORIGIN,
-        ]);
+        .allow_methods(allowed_methods(&server.cors_allowed_methods))
+        .allow_headers(allowed_headers(&server.cors_allowed_headers))
+        .allow_credentials(server.cors_allow_credentials)
+        .expose_headers([X_REQUEST_ID.clone()]);
 
     // Configure allowed origins based on environment
-    if config.is_development() {
+    if config.is_development() && !server.cors_allow_credentials {
         // In development, allow any origin (no credentials for Any origin)
         cors = cors.allow_origin(Any);
+    } else if is_wildcard(&server.cors_allowed_origins) {
+        cors = cors.allow_origin(Any);
     } else {
-        // In production, only allow configured origins
-        let origins: Result<Vec<HeaderValue>, _> = config
-            .server
+        // Otherwise only allow configured origins
+        let origins: Result<Vec<HeaderValue>, _> = server
             .cors_allowed_origins
             .iter()
             .map(|origin| origin.parse::<HeaderValue>())
@@ -28,17 +33,50 @@ pub fn cors_layer(config: &AppConfig) -> CorsLayer {
             
         match origins {
             Ok(origins) => {
-                for origin in origins {
-                    cors = cors.allow_origin(origin);
-                }
+                cors = cors.allow_origin(origins);
             }
             Err(_) => {
                 // Fallback to no CORS if origins are invalid
-                tracing::warn!("Invalid CORS origins configured, disabling CORS");
+                warn!("Invalid CORS origins configured, disabling CORS");
                 return CorsLayer::new().allow_origin(Any);
             }
         }
     }
 
     cors
-}
\ No newline at end of file
+}
+
+/// Whether a configured list is the `*` wildcard
+fn is_wildcard(values: &[String]) -> bool {
+    values.iter().any(|value| value.trim() == "*")
+}
+
+/// Parse the configured methods, skipping any that aren't valid
+fn allowed_methods(methods: &[String]) -> AllowMethods {
+    if is_wildcard(methods) {
+        return Any.into();
+    }
+
+    AllowMethods::list(methods.iter().filter_map(|method| {
+        let parsed = Method::from_bytes(method.trim().to_uppercase().as_bytes()).ok();
+        if parsed.is_none() {
+            warn!("Ignoring invalid CORS method {:?}", method);
+        }
+        parsed
+    }))
+}
+
+/// Parse the configured headers, skipping any that aren't valid
+fn allowed_headers(headers: &[String]) -> AllowHeaders {
+    if is_wildcard(headers) {
+        return Any.into();
+    }
+
+    AllowHeaders::list(headers.iter().filter_map(|header| {
+        let parsed = HeaderName::from_bytes(header.trim().to_lowercase().as_bytes()).ok();
+        if parsed.is_none() {
+            warn!("Ignoring invalid CORS header {:?}", header);
+        }
+        parsed
+    }))
+}
@@ -1 +1,5 @@
-pub mod cors;
\ No newline at end of file
+pub mod compression;
+pub mod cors;
+pub mod rate_limit;
+pub mod request_id;
+pub mod timeout;
\ No newline at end of file
@@ -0,0 +1,39 @@
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+use shared::AppError;
+use tracing::{error, warn};
+
+use crate::{error::ApiError, extract::ClientIp, AppState};
+
+/// Limit anonymous write requests per client IP using a Redis-backed
+/// sliding window
+///
+/// Fails open when Redis is unavailable so a cache outage doesn't block
+/// session creation entirely.
+pub async fn rate_limit(
+    State(state): State<AppState>,
+    ClientIp(client_ip): ClientIp,
+    request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let limits = &state.config.rate_limit;
+    if !limits.enabled {
+        return Ok(next.run(request).await);
+    }
+
+    let client_ip = client_ip.map_or_else(|| "unknown".to_string(), |ip| ip.to_string());
+
+    match state.redis.increment_rate_limit(&client_ip, limits.max_requests, limits.window_seconds).await {
+        Ok((count, retry_after)) if count > u64::from(limits.max_requests) => {
+            warn!("Rate limit exceeded for client {}", client_ip);
+            return Err(ApiError(AppError::rate_limit_exceeded(retry_after)));
+        }
+        Ok(_) => {}
+        Err(e) => error!("Rate limit check failed, allowing request: {}", e),
+    }
+
+    Ok(next.run(request).await)
+}
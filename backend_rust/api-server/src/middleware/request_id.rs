@@ -0,0 +1,51 @@
+use axum::{
+    extract::Request,
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use tracing::{info_span, Instrument};
+use uuid::Uuid;
+
+/// Header carrying the correlation ID between clients and servers
+pub static X_REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Maximum accepted length for a client-supplied request ID
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+/// Correlation ID for the current request, available as a request extension
+#[derive(Debug, Clone)]
+pub struct RequestId(pub String);
+
+/// Tag each request with a correlation ID
+///
+/// Reuses a well-formed incoming `X-Request-Id` or generates a UUID, records
+/// it on a tracing span wrapping the rest of the request (so handler and
+/// error logs carry it), and echoes it back on the response.
+pub async fn request_id(mut request: Request, next: Next) -> Response {
+    let request_id = request
+        .headers()
+        .get(&X_REQUEST_ID)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| is_valid_request_id(value))
+        .map(str::to_string)
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+
+    request.extensions_mut().insert(RequestId(request_id.clone()));
+
+    let span = info_span!("request", request_id = %request_id);
+    let mut response = next.run(request).instrument(span).await;
+
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(X_REQUEST_ID.clone(), value);
+    }
+
+    response
+}
+
+/// Accept only short, printable ASCII IDs so they are safe to log and echo
+fn is_valid_request_id(value: &str) -> bool {
+    !value.is_empty()
+        && value.len() <= MAX_REQUEST_ID_LENGTH
+        && value.chars().all(|c| c.is_ascii_graphic())
+}
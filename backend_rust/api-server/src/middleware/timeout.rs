@@ -0,0 +1,47 @@
+use shared::AppConfig;
+use std::time::Duration;
+use tower_http::timeout::TimeoutLayer;
+
+/// Fail requests that take longer than the configured request timeout
+///
+/// Responds with `408 Request Timeout` so a slow handler or a client
+/// trickling its body can't hold a connection indefinitely.
+pub fn request_timeout(config: &AppConfig) -> TimeoutLayer {
+    TimeoutLayer::new(Duration::from_secs(config.server.request_timeout))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::{Request, StatusCode}, routing::get, Router};
+    use tower::ServiceExt;
+
+    fn app_with_timeout(seconds: u64) -> Router {
+        let mut config = AppConfig::default();
+        config.server.request_timeout = seconds;
+
+        Router::new()
+            .route(
+                "/slow",
+                get(|| async {
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                    "done"
+                }),
+            )
+            .route("/fast", get(|| async { "done" }))
+            .layer(request_timeout(&config))
+    }
+
+    #[tokio::test]
+    async fn test_slow_request_times_out() {
+        let app = app_with_timeout(1);
+
+        let request = Request::builder().uri("/slow").body(Body::empty()).unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::REQUEST_TIMEOUT);
+
+        let request = Request::builder().uri("/fast").body(Body::empty()).unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
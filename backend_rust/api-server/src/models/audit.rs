@@ -0,0 +1,66 @@
+use serde_json::Value;
+use shared::{AppResult, AuditEvent, AuditEventType};
+use sqlx::PgPool;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+/// Repository for the session audit log
+pub struct AuditRepository {
+    pool: PgPool,
+}
+
+impl AuditRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Append an event to the audit log
+    ///
+    /// Never fails: the operation being audited has already happened, so a
+    /// failed insert is logged rather than turned into an error response.
+    pub async fn record(
+        &self,
+        event_type: AuditEventType,
+        session_id: Uuid,
+        actor: Option<&str>,
+        metadata: Value,
+    ) {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO audit_events (event_type, session_id, actor, metadata)
+            VALUES ($1, $2, $3, $4)
+            "#,
+        )
+        .bind(event_type)
+        .bind(session_id)
+        .bind(actor)
+        .bind(metadata)
+        .execute(&self.pool)
+        .await;
+
+        match result {
+            Ok(_) => debug!("Audited {:?} for session {}", event_type, session_id),
+            Err(e) => warn!(
+                "Failed to audit {:?} for session {}: {}",
+                event_type, session_id, e
+            ),
+        }
+    }
+
+    /// Get a session's audit events, oldest first
+    pub async fn list_events(&self, session_id: Uuid) -> AppResult<Vec<AuditEvent>> {
+        let events = sqlx::query_as::<_, AuditEvent>(
+            r#"
+            SELECT id, event_type, session_id, actor, metadata, created_at
+            FROM audit_events
+            WHERE session_id = $1
+            ORDER BY created_at, id
+            "#,
+        )
+        .bind(session_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(events)
+    }
+}
@@ -0,0 +1,76 @@
+use chrono::{DateTime, Utc};
+use shared::{AppResult, Location};
+use sqlx::PgPool;
+use tracing::debug;
+use uuid::Uuid;
+
+/// Repository for the durable location history, kept when location
+/// persistence is enabled
+pub struct LocationHistoryRepository {
+    pool: PgPool,
+}
+
+impl LocationHistoryRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Record several participants' locations in a single statement
+    pub async fn record(&self, session_id: Uuid, locations: &[(String, Location)]) -> AppResult<()> {
+        let user_ids: Vec<&str> = locations.iter().map(|(user_id, _)| user_id.as_str()).collect();
+        let lats: Vec<f64> = locations.iter().map(|(_, location)| location.lat).collect();
+        let lngs: Vec<f64> = locations.iter().map(|(_, location)| location.lng).collect();
+        let accuracies: Vec<f64> = locations.iter().map(|(_, location)| location.accuracy).collect();
+        let recorded_at: Vec<DateTime<Utc>> = locations.iter().map(|(_, location)| location.timestamp).collect();
+
+        sqlx::query(
+            r#"
+            INSERT INTO location_history (session_id, user_id, lat, lng, accuracy, recorded_at)
+            SELECT $1, * FROM UNNEST($2::varchar[], $3::float8[], $4::float8[], $5::float8[], $6::timestamptz[])
+            "#,
+        )
+        .bind(session_id)
+        .bind(&user_ids)
+        .bind(&lats)
+        .bind(&lngs)
+        .bind(&accuracies)
+        .bind(&recorded_at)
+        .execute(&self.pool)
+        .await?;
+
+        debug!("Recorded {} locations for session {}", locations.len(), session_id);
+        Ok(())
+    }
+
+    /// Get a participant's recorded locations, oldest first
+    pub async fn get_history(&self, session_id: Uuid, user_id: &str) -> AppResult<Vec<Location>> {
+        let rows = sqlx::query_as::<_, (f64, f64, f64, DateTime<Utc>)>(
+            r#"
+            SELECT lat, lng, accuracy, recorded_at
+            FROM location_history
+            WHERE session_id = $1 AND user_id = $2
+            ORDER BY recorded_at, id
+            "#,
+        )
+        .bind(session_id)
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(lat, lng, accuracy, timestamp)| Location { lat, lng, accuracy, timestamp })
+            .collect())
+    }
+
+    /// Delete a participant's recorded locations
+    pub async fn delete_history(&self, session_id: Uuid, user_id: &str) -> AppResult<()> {
+        sqlx::query("DELETE FROM location_history WHERE session_id = $1 AND user_id = $2")
+            .bind(session_id)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+}
@@ -1,5 +1,9 @@
 pub mod session;
 pub mod participant;
+pub mod audit;
+pub mod location_history;
 
 pub use session::*;
-pub use participant::*;
\ No newline at end of file
+pub use participant::*;
+pub use audit::*;
+pub use location_history::*;
\ No newline at end of file
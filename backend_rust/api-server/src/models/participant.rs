@@ -1,6 +1,7 @@
 use shared::{
-    AppError, AppResult, Constants, Participant, ParticipantResponse, 
-    generate_avatar_color, sanitize_display_name
+    AppError, AppResult, Constants, LocationPrecision, Participant, ParticipantResponse, ParticipantRole,
+    generate_avatar_color, generate_distinct_avatar_color, is_reserved_avatar_color,
+    validate_display_name
 };
 use sqlx::PgPool;
 use tracing::debug;
@@ -16,22 +17,49 @@ impl ParticipantRepository {
         Self { pool }
     }
 
+    /// Pick an avatar color for a new participant from `palette` (the
+    /// built-in palette if empty), never one of the `reserved` colors
+    ///
+    /// With `distinct` set, picks a color not used by the session's active
+    /// participants.
+    pub async fn pick_avatar_color(
+        &self,
+        session_id: Uuid,
+        user_id: &str,
+        palette: &[String],
+        reserved: &[String],
+        distinct: bool,
+    ) -> AppResult<String> {
+        if !distinct {
+            return Ok(generate_avatar_color(palette, reserved));
+        }
+
+        let used_colors = self.get_active_avatar_colors(session_id).await?;
+        Ok(generate_distinct_avatar_color(palette, reserved, &used_colors, user_id))
+    }
+
     /// Add a participant to a session
+    ///
+    /// Rejects an avatar color on the `reserved_colors` list, and a display
+    /// name containing a word on `denied_words`.
+    #[allow(clippy::too_many_arguments)]
     pub async fn create_participant(
         &self,
         session_id: Uuid,
         user_id: String,
         display_name: String,
-        avatar_color: Option<String>,
+        avatar_color: String,
+        reserved_colors: &[String],
+        denied_words: &[String],
+        location_precision: LocationPrecision,
+        role: ParticipantRole,
     ) -> AppResult<Participant> {
-        // Sanitize display name
-        let display_name = sanitize_display_name(&display_name);
-        if display_name.is_empty() {
-            return Err(AppError::invalid_participant_data("Display name cannot be empty"));
-        }
+        let display_name = validate_display_name(&display_name, denied_words)
+            .map_err(|msg| AppError::invalid_participant_data(&msg))?;
 
-        // Use provided avatar color or generate one
-        let avatar_color = avatar_color.unwrap_or_else(generate_avatar_color);
+        if is_reserved_avatar_color(&avatar_color, reserved_colors) {
+            return Err(AppError::invalid_participant_data("Avatar color is reserved"));
+        }
 
         // Check if participant already exists in this session
         let existing = sqlx::query_scalar::<_, bool>(
@@ -63,15 +91,17 @@ impl ParticipantRepository {
         // Create the participant
         let participant = sqlx::query_as::<_, Participant>(
             r#"
-            INSERT INTO participants (session_id, user_id, display_name, avatar_color)
-            VALUES ($1, $2, $3, $4)
-            RETURNING id, session_id, user_id, display_name, avatar_color, joined_at, last_seen, is_active
+            INSERT INTO participants (session_id, user_id, display_name, avatar_color, location_precision, role)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING id, session_id, user_id, display_name, avatar_color, joined_at, last_seen, is_active, location_precision, role
             "#,
         )
         .bind(session_id)
         .bind(&user_id)
         .bind(&display_name)
         .bind(&avatar_color)
+        .bind(location_precision)
+        .bind(role)
         .fetch_one(&self.pool)
         .await?;
 
@@ -79,11 +109,23 @@ impl ParticipantRepository {
         Ok(participant)
     }
 
+    /// Get the avatar colors of a session's active participants
+    pub async fn get_active_avatar_colors(&self, session_id: Uuid) -> AppResult<Vec<String>> {
+        let colors = sqlx::query_scalar::<_, String>(
+            "SELECT avatar_color FROM participants WHERE session_id = $1 AND is_active = true",
+        )
+        .bind(session_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(colors)
+    }
+
     /// Get participant by session and user ID
     pub async fn get_participant(&self, session_id: Uuid, user_id: &str) -> AppResult<Participant> {
         let participant = sqlx::query_as::<_, Participant>(
             r#"
-            SELECT id, session_id, user_id, display_name, avatar_color, joined_at, last_seen, is_active
+            SELECT id, session_id, user_id, display_name, avatar_color, joined_at, last_seen, is_active, location_precision, role
             FROM participants 
             WHERE session_id = $1 AND user_id = $2
             "#,
@@ -101,7 +143,7 @@ impl ParticipantRepository {
     pub async fn list_participants(&self, session_id: Uuid) -> AppResult<Vec<ParticipantResponse>> {
         let participants = sqlx::query_as::<_, ParticipantResponse>(
             r#"
-            SELECT user_id, display_name, avatar_color, last_seen, is_active
+            SELECT user_id, display_name, avatar_color, last_seen, is_active, role
             FROM participants 
             WHERE session_id = $1 AND is_active = true
             ORDER BY joined_at ASC
@@ -133,15 +175,20 @@ impl ParticipantRepository {
         Ok(())
     }
 
-    /// Update participant's last seen timestamp
+    /// Update an active participant's last seen timestamp
     pub async fn update_last_seen(&self, session_id: Uuid, user_id: &str) -> AppResult<()> {
-        sqlx::query(
-            "UPDATE participants SET last_seen = NOW() WHERE session_id = $1 AND user_id = $2",
+        let rows_affected = sqlx::query(
+            "UPDATE participants SET last_seen = NOW() WHERE session_id = $1 AND user_id = $2 AND is_active = true",
         )
         .bind(session_id)
         .bind(user_id)
         .execute(&self.pool)
-        .await?;
+        .await?
+        .rows_affected();
+
+        if rows_affected == 0 {
+            return Err(AppError::ParticipantNotFound);
+        }
 
         Ok(())
     }
@@ -175,7 +222,7 @@ impl ParticipantRepository {
     pub async fn get_all_participants_for_session(&self, session_id: Uuid) -> AppResult<Vec<Participant>> {
         let participants = sqlx::query_as::<_, Participant>(
             r#"
-            SELECT id, session_id, user_id, display_name, avatar_color, joined_at, last_seen, is_active
+            SELECT id, session_id, user_id, display_name, avatar_color, joined_at, last_seen, is_active, location_precision, role
             FROM participants 
             WHERE session_id = $1
             ORDER BY joined_at ASC
@@ -195,7 +242,7 @@ impl ParticipantRepository {
             UPDATE participants 
             SET is_active = true, last_seen = NOW()
             WHERE session_id = $1 AND user_id = $2
-            RETURNING id, session_id, user_id, display_name, avatar_color, joined_at, last_seen, is_active
+            RETURNING id, session_id, user_id, display_name, avatar_color, joined_at, last_seen, is_active, location_precision, role
             "#,
         )
         .bind(session_id)
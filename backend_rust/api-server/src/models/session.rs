@@ -1,12 +1,24 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
 use shared::{
-    AppError, AppResult, Constants, Session, SessionDetailsResponse, 
-    calculate_expiration_time, is_session_expired
+    AdminSessionSummary, AppError, AppResult, Constants, Session, SessionDetailsResponse, 
+    SessionSort, calculate_expiration_time, is_session_expired, merge_session_metadata,
+    validate_session_metadata
 };
 use sqlx::{PgPool, Row};
 use tracing::debug;
 use uuid::Uuid;
 
+/// A public session listing, before its join link is built
+#[derive(Debug, sqlx::FromRow)]
+pub struct PublicSessionRow {
+    pub id: Uuid,
+    pub name: Option<String>,
+    pub participant_count: i64,
+    pub expires_at: DateTime<Utc>,
+    pub join_token: Uuid,
+    pub tags: Vec<String>,
+}
+
 /// Repository for session database operations
 pub struct SessionRepository {
     pool: PgPool,
@@ -18,24 +30,43 @@ impl SessionRepository {
     }
 
     /// Create a new session
+    #[allow(clippy::too_many_arguments)]
     pub async fn create_session(
         &self,
         name: Option<String>,
         expires_in_minutes: i64,
         creator_id: Uuid,
+        avatar_palette: Option<Vec<String>>,
+        is_public: bool,
+        tags: Vec<String>,
+        welcome_message: Option<String>,
+        view_only: bool,
+        waitlist_enabled: bool,
+        interpolate_locations: bool,
+        join_window_minutes: Option<i64>,
+        metadata: serde_json::Value,
     ) -> AppResult<Session> {
         let expires_at = calculate_expiration_time(expires_in_minutes);
         
         let session = sqlx::query_as::<_, Session>(
             r#"
-            INSERT INTO sessions (name, expires_at, creator_id)
-            VALUES ($1, $2, $3)
-            RETURNING id, name, created_at, expires_at, creator_id, is_active, last_activity
+            INSERT INTO sessions (name, expires_at, creator_id, avatar_palette, is_public, tags, welcome_message, view_only, waitlist_enabled, interpolate_locations, join_window_minutes, metadata)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+            RETURNING id, name, created_at, expires_at, creator_id, is_active, last_activity, join_token, avatar_palette, is_public, tags, welcome_message, view_only, waitlist_enabled, interpolate_locations, join_window_minutes, metadata
             "#,
         )
         .bind(name)
         .bind(expires_at)
         .bind(creator_id)
+        .bind(avatar_palette)
+        .bind(is_public)
+        .bind(tags)
+        .bind(welcome_message)
+        .bind(view_only)
+        .bind(waitlist_enabled)
+        .bind(interpolate_locations)
+        .bind(join_window_minutes)
+        .bind(metadata)
         .fetch_one(&self.pool)
         .await?;
 
@@ -45,13 +76,7 @@ impl SessionRepository {
 
     /// Get session by ID
     pub async fn get_session(&self, session_id: Uuid) -> AppResult<Session> {
-        let session = sqlx::query_as::<_, Session>(
-            "SELECT id, name, created_at, expires_at, creator_id, is_active, last_activity FROM sessions WHERE id = $1",
-        )
-        .bind(session_id)
-        .fetch_optional(&self.pool)
-        .await?
-        .ok_or(AppError::SessionNotFound)?;
+        let session = self.find_session(session_id).await?;
 
         // Check if session is expired
         if is_session_expired(session.expires_at) {
@@ -66,12 +91,50 @@ impl SessionRepository {
         Ok(session)
     }
 
+    /// Get session by ID, whether or not it has ended or expired
+    pub async fn find_session(&self, session_id: Uuid) -> AppResult<Session> {
+        let session = sqlx::query_as::<_, Session>(
+            "SELECT id, name, created_at, expires_at, creator_id, is_active, last_activity, join_token, avatar_palette, is_public, tags, welcome_message, view_only, waitlist_enabled, interpolate_locations, join_window_minutes, metadata FROM sessions WHERE id = $1",
+        )
+        .bind(session_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or(AppError::SessionNotFound)?;
+
+        Ok(session)
+    }
+
+    /// Resolve a join link's token to the session it belongs to
+    pub async fn get_session_by_join_token(&self, join_token: Uuid) -> AppResult<Session> {
+        let session_id: Uuid = sqlx::query_scalar("SELECT id FROM sessions WHERE join_token = $1")
+            .bind(join_token)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or(AppError::SessionNotFound)?;
+
+        self.get_session(session_id).await
+    }
+
+    /// Replace a session's join token, invalidating links built from the old one
+    pub async fn rotate_join_token(&self, session_id: Uuid) -> AppResult<Uuid> {
+        let join_token: Uuid = sqlx::query_scalar(
+            "UPDATE sessions SET join_token = uuid_generate_v4() WHERE id = $1 AND is_active = true RETURNING join_token",
+        )
+        .bind(session_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or(AppError::SessionNotFound)?;
+
+        debug!("Rotated join token for session: {}", session_id);
+        Ok(join_token)
+    }
+
     /// Get session details with participant count
     pub async fn get_session_details(&self, session_id: Uuid) -> AppResult<SessionDetailsResponse> {
         let row = sqlx::query(
             r#"
             SELECT 
-                s.id, s.name, s.created_at, s.expires_at, s.is_active,
+                s.id, s.name, s.created_at, s.expires_at, s.is_active, s.last_activity, s.tags, s.welcome_message, s.view_only, s.waitlist_enabled, s.interpolate_locations, s.join_window_minutes, s.metadata,
                 get_active_participant_count(s.id)::bigint as participant_count
             FROM sessions s 
             WHERE s.id = $1
@@ -102,6 +165,14 @@ impl SessionRepository {
             expires_at,
             participant_count: row.get("participant_count"),
             is_active,
+            last_activity: row.get("last_activity"),
+            tags: row.get("tags"),
+            welcome_message: row.get("welcome_message"),
+            view_only: row.get("view_only"),
+            waitlist_enabled: row.get("waitlist_enabled"),
+            interpolate_locations: row.get("interpolate_locations"),
+            join_window_minutes: row.get("join_window_minutes"),
+            metadata: row.get("metadata"),
         })
     }
 
@@ -138,6 +209,98 @@ impl SessionRepository {
         Ok(())
     }
 
+    /// Make an active participant the session's creator
+    pub async fn transfer_ownership(&self, session_id: Uuid, new_creator_id: Uuid) -> AppResult<()> {
+        let rows_affected = sqlx::query(
+            r#"
+            UPDATE sessions SET creator_id = $2
+            WHERE id = $1 AND is_active = true
+            AND EXISTS (
+                SELECT 1 FROM participants 
+                WHERE session_id = $1 AND user_id = $2::text AND is_active = true
+            )
+            "#,
+        )
+        .bind(session_id)
+        .bind(new_creator_id)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        if rows_affected == 0 {
+            return Err(AppError::validation("new_creator_id", "New creator must be an active participant"));
+        }
+
+        debug!("Transferred session {} to {}", session_id, new_creator_id);
+        Ok(())
+    }
+
+    /// Get a session's metadata object
+    pub async fn get_metadata(&self, session_id: Uuid) -> AppResult<serde_json::Value> {
+        let metadata = sqlx::query_scalar("SELECT metadata FROM sessions WHERE id = $1")
+            .bind(session_id)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or(AppError::SessionNotFound)?;
+
+        Ok(metadata)
+    }
+
+    /// Merge a patch into an active session's metadata, returning the result
+    ///
+    /// The merged object is held to the same size limit as on create. Counts
+    /// as activity, so the session's ETag changes with its metadata.
+    pub async fn merge_metadata(
+        &self,
+        session_id: Uuid,
+        patch: &serde_json::Value,
+    ) -> AppResult<serde_json::Value> {
+        let mut tx = self.pool.begin().await?;
+
+        let mut metadata: serde_json::Value = sqlx::query_scalar(
+            "SELECT metadata FROM sessions WHERE id = $1 AND is_active = true FOR UPDATE",
+        )
+        .bind(session_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or(AppError::SessionNotFound)?;
+
+        merge_session_metadata(&mut metadata, patch);
+        validate_session_metadata(&metadata).map_err(|msg| AppError::validation("metadata", &msg))?;
+
+        sqlx::query("UPDATE sessions SET metadata = $2, last_activity = NOW() WHERE id = $1")
+            .bind(session_id)
+            .bind(&metadata)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        debug!("Merged metadata of session: {}", session_id);
+        Ok(metadata)
+    }
+
+    /// Replace an active session's tags
+    ///
+    /// Counts as activity, so the session's ETag changes with its tags.
+    pub async fn update_tags(&self, session_id: Uuid, tags: Vec<String>) -> AppResult<()> {
+        let rows_affected = sqlx::query(
+            "UPDATE sessions SET tags = $2, last_activity = NOW() WHERE id = $1 AND is_active = true",
+        )
+        .bind(session_id)
+        .bind(tags)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        if rows_affected == 0 {
+            return Err(AppError::SessionNotFound);
+        }
+
+        debug!("Updated tags of session: {}", session_id);
+        Ok(())
+    }
+
     /// Update session activity timestamp
     pub async fn update_activity(&self, session_id: Uuid) -> AppResult<()> {
         sqlx::query(
@@ -150,6 +313,18 @@ impl SessionRepository {
         Ok(())
     }
 
+    /// Count a creator's sessions that are still active and unexpired
+    pub async fn count_active_sessions_for_creator(&self, creator_id: Uuid) -> AppResult<i64> {
+        let count: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM sessions WHERE creator_id = $1 AND is_active = true AND expires_at > NOW()",
+        )
+        .bind(creator_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count)
+    }
+
     /// Check if session can accept more participants
     pub async fn can_accept_participants(&self, session_id: Uuid) -> AppResult<bool> {
         let count: i64 = sqlx::query_scalar(
@@ -166,7 +341,7 @@ impl SessionRepository {
     pub async fn get_active_sessions(&self) -> AppResult<Vec<Session>> {
         let sessions = sqlx::query_as::<_, Session>(
             r#"
-            SELECT id, name, created_at, expires_at, creator_id, is_active, last_activity 
+            SELECT id, name, created_at, expires_at, creator_id, is_active, last_activity, join_token, avatar_palette, is_public, tags, welcome_message, view_only, waitlist_enabled, interpolate_locations, join_window_minutes, metadata 
             FROM sessions 
             WHERE is_active = true AND expires_at > NOW()
             ORDER BY created_at DESC
@@ -178,6 +353,59 @@ impl SessionRepository {
         Ok(sessions)
     }
 
+    /// Summarize every session's participant count and activity in one query,
+    /// optionally only those carrying `tag`
+    pub async fn list_session_summaries(
+        &self,
+        active_only: bool,
+        sort: SessionSort,
+        tag: Option<&str>,
+    ) -> AppResult<Vec<AdminSessionSummary>> {
+        let order = match sort {
+            SessionSort::LastActivityDesc => "DESC",
+            SessionSort::LastActivityAsc => "ASC",
+        };
+        let sessions = sqlx::query_as::<_, AdminSessionSummary>(&format!(
+            r#"
+            SELECT 
+                id, name, last_activity, expires_at, is_active, tags,
+                get_active_participant_count(id)::bigint as participant_count
+            FROM sessions 
+            WHERE (NOT $1 OR (is_active = true AND expires_at > NOW()))
+            AND ($2::text IS NULL OR tags @> ARRAY[$2::text])
+            ORDER BY last_activity {}, id
+            "#,
+            order
+        ))
+        .bind(active_only)
+        .bind(tag)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(sessions)
+    }
+
+    /// Active, unexpired public sessions, most recently active first,
+    /// optionally only those carrying `tag`
+    pub async fn list_public_sessions(&self, tag: Option<&str>) -> AppResult<Vec<PublicSessionRow>> {
+        let sessions = sqlx::query_as::<_, PublicSessionRow>(
+            r#"
+            SELECT 
+                id, name, expires_at, join_token, tags,
+                get_active_participant_count(id)::bigint as participant_count
+            FROM sessions 
+            WHERE is_public = true AND is_active = true AND expires_at > NOW()
+            AND ($1::text IS NULL OR tags @> ARRAY[$1::text])
+            ORDER BY last_activity DESC, id
+            "#,
+        )
+        .bind(tag)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(sessions)
+    }
+
     /// Check if a user is the creator of a session
     pub async fn is_session_creator(&self, session_id: Uuid, user_id: Uuid) -> AppResult<bool> {
         let is_creator: bool = sqlx::query_scalar(
@@ -191,21 +419,24 @@ impl SessionRepository {
         Ok(is_creator)
     }
 
-    /// Get sessions that should be auto-expired due to inactivity
-    pub async fn get_sessions_to_auto_expire(&self) -> AppResult<Vec<Uuid>> {
+    /// Get sessions that should be auto-expired after `inactivity_minutes`
+    /// without activity
+    pub async fn get_sessions_to_auto_expire(&self, inactivity_minutes: i64) -> AppResult<Vec<Uuid>> {
+        let cutoff = Utc::now() - Duration::minutes(inactivity_minutes);
         let session_ids = sqlx::query_scalar::<_, Uuid>(
             r#"
             SELECT id FROM sessions 
             WHERE is_active = true 
-            AND last_activity < NOW() - INTERVAL '1 hour'
+            AND last_activity < $1
             AND NOT EXISTS (
                 SELECT 1 FROM participants 
                 WHERE participants.session_id = sessions.id 
                 AND participants.is_active = true 
-                AND participants.last_seen > NOW() - INTERVAL '1 hour'
+                AND participants.last_seen > $1
             )
             "#,
         )
+        .bind(cutoff)
         .fetch_all(&self.pool)
         .await?;
 
@@ -0,0 +1,455 @@
+use chrono::Utc;
+use redis::{aio::ConnectionManager, AsyncCommands};
+use shared::{check_publish_size, AppError, AppResult, Constants, Location, RedisKeys, SessionInfoData};
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+/// Redis client for API server operations
+#[derive(Clone)]
+pub struct RedisClient {
+    connection: ConnectionManager,
+    keys: RedisKeys,
+    max_publish_bytes: usize,
+    history_max_points: usize,
+    history_ttl_seconds: u64,
+}
+
+impl RedisClient {
+    /// Create a new Redis client
+    pub async fn new(redis_url: &str) -> AppResult<Self> {
+        info!("Connecting to Redis...");
+
+        let client = redis::Client::open(redis_url)?;
+        let connection = ConnectionManager::new(client).await?;
+
+        info!("Successfully connected to Redis");
+        Ok(Self {
+            connection,
+            keys: RedisKeys::default(),
+            max_publish_bytes: usize::MAX,
+            history_max_points: Constants::LOCATION_HISTORY_MAX_POINTS,
+            history_ttl_seconds: Constants::LOCATION_HISTORY_TTL_SECONDS,
+        })
+    }
+
+    /// Namespace every key and channel under `prefix`
+    pub fn with_key_prefix(mut self, prefix: &str) -> Self {
+        self.keys = RedisKeys::new(prefix);
+        self
+    }
+
+    /// Refuse to publish messages larger than `max_bytes`
+    pub fn with_max_publish_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_publish_bytes = max_bytes;
+        self
+    }
+
+    /// Keep at most `max_points` locations in each participant's history,
+    /// expiring it `ttl_seconds` after their last update
+    pub fn with_location_history_limits(mut self, max_points: usize, ttl_seconds: u64) -> Self {
+        self.history_max_points = max_points;
+        self.history_ttl_seconds = ttl_seconds;
+        self
+    }
+
+    /// Announce a participant's departure to their session, unless it
+    /// already was; see [`shared::announce_participant_left`]
+    pub async fn announce_participant_left(&self, session_id: &Uuid, user_id: &str) -> AppResult<bool> {
+        let mut conn = self.connection.clone();
+        shared::announce_participant_left(&mut conn, &self.keys, session_id, user_id).await
+    }
+
+    /// Clear a participant's departure tombstone so their next departure is
+    /// announced
+    pub async fn clear_participant_left(&self, session_id: &Uuid, user_id: &str) -> AppResult<()> {
+        let mut conn = self.connection.clone();
+        shared::clear_participant_left(&mut conn, &self.keys, session_id, user_id).await
+    }
+
+    /// Mark a participant as present and the session as recently active
+    pub async fn touch_participant(&self, session_id: &Uuid, user_id: &str) -> AppResult<()> {
+        let mut conn = self.connection.clone();
+        let timestamp = chrono::Utc::now().timestamp();
+
+        let _: () = redis::pipe()
+            .sadd(self.keys.session_participants(session_id), user_id)
+            .ignore()
+            .set(self.keys.session_activity(session_id), timestamp)
+            .ignore()
+            .query_async(&mut conn)
+            .await?;
+
+        debug!("Refreshed presence for user {} in session {}", user_id, session_id);
+        Ok(())
+    }
+
+    /// Get all stored locations for a session, keyed by user ID
+    pub async fn get_session_locations(&self, session_id: &Uuid) -> AppResult<Vec<(String, Location)>> {
+        let mut conn = self.connection.clone();
+        let prefix = self.keys.location(session_id, "");
+
+        let keys: Vec<String> = conn.keys(format!("{}*", prefix)).await?;
+        let mut locations = Vec::with_capacity(keys.len());
+
+        for key in keys {
+            // The key may have expired since it was listed
+            let Some(value) = conn.get::<_, Option<String>>(&key).await? else {
+                continue;
+            };
+            let Some(user_id) = key.strip_prefix(&prefix) else {
+                continue;
+            };
+
+            match serde_json::from_str::<Location>(&value) {
+                Ok(location) => locations.push((user_id.to_string(), location)),
+                Err(e) => warn!("Skipping unreadable location at {}: {}", key, e),
+            }
+        }
+
+        Ok(locations)
+    }
+
+    /// Get a participant's stored location, if it hasn't expired
+    pub async fn get_location(&self, session_id: &Uuid, user_id: &str) -> AppResult<Option<Location>> {
+        let mut conn = self.connection.clone();
+        let value: Option<String> = conn.get(self.keys.location(session_id, user_id)).await?;
+
+        Ok(value.map(|value| serde_json::from_str(&value)).transpose()?)
+    }
+
+    /// Store a participant's location and append it to their history, as
+    /// the WebSocket server does for a location update
+    pub async fn record_location(&self, session_id: &Uuid, user_id: &str, location: &Location) -> AppResult<()> {
+        let mut conn = self.connection.clone();
+        let history_key = self.keys.location_history(session_id, user_id);
+        let value = serde_json::to_string(location)?;
+        let max_points = self.history_max_points as isize;
+
+        let _: () = redis::pipe()
+            .set_ex(self.keys.location(session_id, user_id), &value, Constants::LOCATION_TTL_SECONDS as u64)
+            .ignore()
+            .rpush(&history_key, &value)
+            .ignore()
+            .ltrim(&history_key, -max_points, -1)
+            .ignore()
+            .expire(&history_key, self.history_ttl_seconds as i64)
+            .ignore()
+            .query_async(&mut conn)
+            .await?;
+
+        debug!("Recorded location for user {} in session {}", user_id, session_id);
+        Ok(())
+    }
+
+    /// Store a participant's location without adding it to their history,
+    /// for a resent point that only needs its TTL refreshed
+    pub async fn refresh_location(&self, session_id: &Uuid, user_id: &str, location: &Location) -> AppResult<()> {
+        let mut conn = self.connection.clone();
+        let value = serde_json::to_string(location)?;
+        let _: () = conn
+            .set_ex(self.keys.location(session_id, user_id), value, Constants::LOCATION_TTL_SECONDS as u64)
+            .await?;
+
+        debug!("Refreshed location for user {} in session {}", user_id, session_id);
+        Ok(())
+    }
+
+    /// Get a participant's location history, oldest first
+    pub async fn get_location_history(&self, session_id: &Uuid, user_id: &str) -> AppResult<Vec<Location>> {
+        let mut conn = self.connection.clone();
+        let key = self.keys.location_history(session_id, user_id);
+
+        let values: Vec<String> = conn.lrange(&key, 0, -1).await?;
+        let history = values
+            .iter()
+            .filter_map(|value| match serde_json::from_str::<Location>(value) {
+                Ok(location) => Some(location),
+                Err(e) => {
+                    warn!("Skipping unreadable location in {}: {}", key, e);
+                    None
+                }
+            })
+            .collect();
+
+        Ok(history)
+    }
+
+    /// Delete a participant's stored location and location history
+    pub async fn delete_location(&self, session_id: &Uuid, user_id: &str) -> AppResult<()> {
+        let mut conn = self.connection.clone();
+
+        let _: () = conn
+            .del(&[
+                self.keys.location(session_id, user_id),
+                self.keys.location_history(session_id, user_id),
+            ])
+            .await?;
+
+        debug!("Deleted location for user {} in session {}", user_id, session_id);
+        Ok(())
+    }
+
+    /// Drop a participant's presence, stored location, history and status
+    pub async fn clear_participant(&self, session_id: &Uuid, user_id: &str) -> AppResult<()> {
+        let mut conn = self.connection.clone();
+
+        let _: () = redis::pipe()
+            .srem(self.keys.session_participants(session_id), user_id)
+            .ignore()
+            .del(self.keys.location(session_id, user_id))
+            .ignore()
+            .del(self.keys.location_history(session_id, user_id))
+            .ignore()
+            .del(self.keys.status(session_id, user_id))
+            .ignore()
+            .query_async(&mut conn)
+            .await?;
+
+        debug!("Cleared presence for user {} in session {}", user_id, session_id);
+        Ok(())
+    }
+
+    /// Publish a message to a session's channel for the WebSocket servers to relay
+    pub async fn publish_to_session(&self, session_id: &Uuid, message: &str) -> AppResult<()> {
+        check_publish_size(message, self.max_publish_bytes)?;
+        let mut conn = self.connection.clone();
+
+        let _: () = conn.publish(self.keys.session_channel(session_id), message).await?;
+
+        debug!("Published message to session {} channel", session_id);
+        Ok(())
+    }
+
+    /// Check that Redis is reachable
+    pub async fn health_check(&self) -> AppResult<()> {
+        let mut conn = self.connection.clone();
+        let _: String = redis::cmd("PING").query_async(&mut conn).await?;
+        Ok(())
+    }
+
+    /// Reserve a session creation idempotency key for a request, storing
+    /// `record` unless the key is already taken
+    ///
+    /// Returns `None` once reserved, or the record already stored for the key.
+    pub async fn reserve_idempotent_session(
+        &self,
+        idempotency_key: &str,
+        record: &str,
+        ttl_seconds: u64,
+    ) -> AppResult<Option<String>> {
+        let mut conn = self.connection.clone();
+        let key = self.keys.session_idempotency(idempotency_key);
+
+        // A key released between the SET and the GET is free again, so try twice
+        for _ in 0..2 {
+            let reserved: Option<String> = redis::cmd("SET")
+                .arg(&key)
+                .arg(record)
+                .arg("NX")
+                .arg("EX")
+                .arg(ttl_seconds)
+                .query_async(&mut conn)
+                .await?;
+            if reserved.is_some() {
+                debug!("Reserved idempotency key {}", idempotency_key);
+                return Ok(None);
+            }
+
+            let existing: Option<String> = conn.get(&key).await?;
+            if existing.is_some() {
+                return Ok(existing);
+            }
+        }
+
+        Err(AppError::IdempotencyKeyInProgress)
+    }
+
+    /// Replace a reserved idempotency key's record with the finished one
+    pub async fn complete_idempotent_session(
+        &self,
+        idempotency_key: &str,
+        record: &str,
+        ttl_seconds: u64,
+    ) -> AppResult<()> {
+        let mut conn = self.connection.clone();
+        let _: () = conn
+            .set_ex(self.keys.session_idempotency(idempotency_key), record, ttl_seconds)
+            .await?;
+
+        debug!("Stored session response for idempotency key {}", idempotency_key);
+        Ok(())
+    }
+
+    /// Release a reserved idempotency key after a failed creation, so the
+    /// request can be retried
+    pub async fn release_idempotent_session(&self, idempotency_key: &str) -> AppResult<()> {
+        let mut conn = self.connection.clone();
+        let _: () = conn.del(self.keys.session_idempotency(idempotency_key)).await?;
+        Ok(())
+    }
+
+    /// Cache a session's info for WebSocket clients asking for it, so they
+    /// don't cost a database query
+    pub async fn cache_session_info(
+        &self,
+        session_id: &Uuid,
+        info: &SessionInfoData,
+        ttl_seconds: u64,
+    ) -> AppResult<()> {
+        let mut conn = self.connection.clone();
+        let value = serde_json::to_string(info)?;
+
+        let _: () = conn.set_ex(self.keys.session_info(session_id), value, ttl_seconds).await?;
+
+        debug!("Cached info for session {}", session_id);
+        Ok(())
+    }
+
+    /// Queue a waitlist entry at the tail of a session's waitlist, returning
+    /// its 1-based position
+    ///
+    /// Both keys expire with the session, so a waitlist never outlives it.
+    pub async fn enqueue_waitlist(
+        &self,
+        session_id: &Uuid,
+        ticket: &Uuid,
+        entry: &str,
+        ttl_seconds: u64,
+    ) -> AppResult<usize> {
+        let mut conn = self.connection.clone();
+        let waitlist = self.keys.session_waitlist(session_id);
+        let entries = self.keys.session_waitlist_entries(session_id);
+
+        let (position,): (usize,) = redis::pipe()
+            .hset(&entries, ticket.to_string(), entry)
+            .ignore()
+            .rpush(&waitlist, ticket.to_string())
+            .expire(&waitlist, ttl_seconds as i64)
+            .ignore()
+            .expire(&entries, ttl_seconds as i64)
+            .ignore()
+            .query_async(&mut conn)
+            .await?;
+
+        debug!("Queued ticket {} at position {} in session {}", ticket, position, session_id);
+        Ok(position)
+    }
+
+    /// Get a ticket's 1-based position in a session's waitlist, or `None`
+    /// once it has left the queue
+    pub async fn waitlist_position(&self, session_id: &Uuid, ticket: &Uuid) -> AppResult<Option<usize>> {
+        let mut conn = self.connection.clone();
+
+        let index: Option<usize> = redis::cmd("LPOS")
+            .arg(self.keys.session_waitlist(session_id))
+            .arg(ticket.to_string())
+            .query_async(&mut conn)
+            .await?;
+
+        Ok(index.map(|index| index + 1))
+    }
+
+    /// Count the tickets still queued in a session's waitlist
+    pub async fn waitlist_length(&self, session_id: &Uuid) -> AppResult<usize> {
+        let mut conn = self.connection.clone();
+        let length: usize = conn.llen(self.keys.session_waitlist(session_id)).await?;
+        Ok(length)
+    }
+
+    /// Get the entry stored for a waitlist ticket, queued or promoted
+    pub async fn get_waitlist_entry(&self, session_id: &Uuid, ticket: &Uuid) -> AppResult<Option<String>> {
+        let mut conn = self.connection.clone();
+        let entry: Option<String> = conn
+            .hget(self.keys.session_waitlist_entries(session_id), ticket.to_string())
+            .await?;
+        Ok(entry)
+    }
+
+    /// Take the ticket at the head of a session's waitlist, with its entry
+    ///
+    /// Tickets whose entry is missing are dropped, so those behind them
+    /// still get their turn.
+    pub async fn pop_waitlist(&self, session_id: &Uuid) -> AppResult<Option<(Uuid, String)>> {
+        let mut conn = self.connection.clone();
+
+        loop {
+            let Some(ticket): Option<String> = conn.lpop(self.keys.session_waitlist(session_id), None).await? else {
+                return Ok(None);
+            };
+            let ticket = Uuid::parse_str(&ticket)?;
+            let Some(entry) = self.get_waitlist_entry(session_id, &ticket).await? else {
+                warn!("Waitlist ticket {} in session {} has no entry, skipping it", ticket, session_id);
+                continue;
+            };
+
+            debug!("Took ticket {} off the waitlist of session {}", ticket, session_id);
+            return Ok(Some((ticket, entry)));
+        }
+    }
+
+    /// Put a ticket back at the head of a session's waitlist, for another
+    /// try at admitting it
+    pub async fn requeue_waitlist(&self, session_id: &Uuid, ticket: &Uuid) -> AppResult<()> {
+        let mut conn = self.connection.clone();
+        let _: () = conn.lpush(self.keys.session_waitlist(session_id), ticket.to_string()).await?;
+
+        debug!("Requeued ticket {} at the head of the waitlist of session {}", ticket, session_id);
+        Ok(())
+    }
+
+    /// Replace the entry stored for a waitlist ticket, keeping its expiry
+    pub async fn update_waitlist_entry(&self, session_id: &Uuid, ticket: &Uuid, entry: &str) -> AppResult<()> {
+        let mut conn = self.connection.clone();
+        let _: () = conn
+            .hset(self.keys.session_waitlist_entries(session_id), ticket.to_string(), entry)
+            .await?;
+        Ok(())
+    }
+
+    /// Count a request against the client's sliding window, returning the
+    /// requests in the last `window_seconds` (this one included) and the
+    /// seconds until the oldest of them leaves the window
+    ///
+    /// Requests are timestamped members of a sorted set. Dropping those that
+    /// left the window, adding this one, counting and refreshing the expiry
+    /// happen in one MULTI/EXEC transaction, so concurrent requests can't
+    /// slip past the count. A request over `max_requests` is taken back out,
+    /// so rejected retries don't keep the window full.
+    pub async fn increment_rate_limit(
+        &self,
+        client_ip: &str,
+        max_requests: u32,
+        window_seconds: u64,
+    ) -> AppResult<(u64, u64)> {
+        let mut conn = self.connection.clone();
+        let key = self.keys.rate_limit(client_ip);
+        let now_ms = Utc::now().timestamp_millis();
+        let window_ms = window_seconds as i64 * 1000;
+        let member = format!("{}:{}", now_ms, Uuid::new_v4());
+
+        let (count, oldest): (u64, Vec<(String, f64)>) = redis::pipe()
+            .atomic()
+            .zrembyscore(&key, "-inf", now_ms - window_ms)
+            .ignore()
+            .zadd(&key, &member, now_ms)
+            .ignore()
+            .zcard(&key)
+            .zrange_withscores(&key, 0, 0)
+            .pexpire(&key, window_ms)
+            .ignore()
+            .query_async(&mut conn)
+            .await?;
+
+        if count > u64::from(max_requests) {
+            if let Err(e) = conn.zrem::<_, _, ()>(&key, &member).await {
+                warn!("Failed to uncount rejected request from {}: {}", client_ip, e);
+            }
+        }
+
+        let oldest_ms = oldest.first().map_or(now_ms, |(_, score)| *score as i64);
+        let retry_after_ms = (oldest_ms + window_ms - now_ms).max(0) as u64;
+
+        debug!("Rate limit count for {} is {}", client_ip, count);
+        Ok((count, retry_after_ms.div_ceil(1000).max(1)))
+    }
+}
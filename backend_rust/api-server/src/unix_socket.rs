@@ -0,0 +1,123 @@
+use axum::Router;
+use hyper::server::conn::http1;
+use hyper_util::{rt::TokioIo, service::TowerToHyperService};
+use shared::AppResult;
+use std::future::Future;
+use std::os::unix::fs::FileTypeExt;
+use std::path::Path;
+use std::time::Duration;
+use tokio::net::UnixListener;
+use tokio::sync::watch;
+use tracing::{debug, error};
+
+/// Bind a Unix domain socket, replacing a socket left behind by a previous run
+pub fn bind(path: &Path) -> AppResult<UnixListener> {
+    if let Ok(metadata) = std::fs::symlink_metadata(path) {
+        if metadata.file_type().is_socket() {
+            std::fs::remove_file(path)?;
+        }
+    }
+
+    Ok(UnixListener::bind(path)?)
+}
+
+/// Serve the app over a Unix domain socket until `shutdown` completes
+///
+/// `axum::serve` only accepts TCP listeners, so connections are driven by
+/// hyper directly. On shutdown, no new connections are accepted and open
+/// ones finish their in-flight request before this returns.
+pub async fn serve(
+    listener: UnixListener,
+    app: Router,
+    shutdown: impl Future<Output = ()>,
+) -> AppResult<()> {
+    let (signal_tx, signal_rx) = watch::channel(());
+    let (close_tx, close_rx) = watch::channel(());
+    tokio::pin!(shutdown);
+
+    loop {
+        let stream = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    // Usually out of file descriptors; back off before retrying
+                    error!("Failed to accept Unix socket connection: {}", e);
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                    continue;
+                }
+            },
+            _ = &mut shutdown => break,
+        };
+
+        let service = TowerToHyperService::new(app.clone());
+        let mut signal_rx = signal_rx.clone();
+        let close_rx = close_rx.clone();
+
+        tokio::spawn(async move {
+            let connection = http1::Builder::new().serve_connection(TokioIo::new(stream), service);
+            tokio::pin!(connection);
+
+            let result = tokio::select! {
+                result = connection.as_mut() => result,
+                _ = signal_rx.changed() => {
+                    connection.as_mut().graceful_shutdown();
+                    connection.as_mut().await
+                }
+            };
+            if let Err(e) = result {
+                debug!("Unix socket connection closed with error: {}", e);
+            }
+
+            drop(close_rx);
+        });
+    }
+
+    // Ask open connections to finish, then wait for every one to close
+    drop(listener);
+    drop(close_rx);
+    let _ = signal_tx.send(());
+    close_tx.closed().await;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::routing::get;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::UnixStream;
+
+    #[tokio::test]
+    async fn test_health_check_over_unix_socket() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("api.sock");
+
+        let app = Router::new().route("/health/live", get(crate::liveness_check));
+        let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel::<()>();
+        let server = tokio::spawn(serve(bind(&path).unwrap(), app, async {
+            let _ = shutdown_rx.await;
+        }));
+
+        let mut stream = UnixStream::connect(&path).await.unwrap();
+        stream
+            .write_all(b"GET /health/live HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "Unexpected response: {}", response);
+        assert!(response.ends_with(r#"{"status":"alive"}"#));
+
+        shutdown_tx.send(()).unwrap();
+        tokio::time::timeout(Duration::from_secs(5), server)
+            .await
+            .expect("Server did not shut down")
+            .unwrap()
+            .unwrap();
+
+        // A stale socket file from an earlier run doesn't block binding again
+        assert!(bind(&path).is_ok());
+    }
+}
@@ -0,0 +1,6 @@
+-- Location precision chosen by each participant
+ALTER TABLE participants
+    ADD COLUMN location_precision VARCHAR(10) NOT NULL DEFAULT 'exact';
+
+ALTER TABLE participants ADD CONSTRAINT chk_participants_location_precision
+    CHECK (location_precision IN ('exact', 'coarse', 'city'));
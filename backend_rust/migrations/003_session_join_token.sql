@@ -0,0 +1,12 @@
+-- Opaque token embedded in join links, separate from the session ID so a
+-- leaked link can be rotated. Existing sessions keep their ID as the token
+-- so links already shared still work until rotated.
+ALTER TABLE sessions ADD COLUMN join_token UUID;
+
+UPDATE sessions SET join_token = id;
+
+ALTER TABLE sessions
+    ALTER COLUMN join_token SET DEFAULT uuid_generate_v4(),
+    ALTER COLUMN join_token SET NOT NULL;
+
+CREATE UNIQUE INDEX idx_sessions_join_token ON sessions(join_token);
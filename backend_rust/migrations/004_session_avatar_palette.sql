@@ -0,0 +1,3 @@
+-- Avatar colors chosen for a session's participants; NULL uses the
+-- server-wide palette
+ALTER TABLE sessions ADD COLUMN avatar_palette TEXT[];
@@ -0,0 +1,5 @@
+-- Public sessions are listed in the open session directory, join link
+-- included; private sessions (the default) are only reachable by link
+ALTER TABLE sessions ADD COLUMN is_public BOOLEAN NOT NULL DEFAULT false;
+
+CREATE INDEX idx_sessions_public ON sessions(last_activity DESC) WHERE is_public = true AND is_active = true;
@@ -0,0 +1,12 @@
+-- Append-only record of session lifecycle events for incident review.
+-- Rows outlive their session, so there is no foreign key to sessions.
+CREATE TABLE audit_events (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    event_type VARCHAR(32) NOT NULL,
+    session_id UUID NOT NULL,
+    actor VARCHAR(255),
+    metadata JSONB NOT NULL DEFAULT '{}'::jsonb,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_audit_events_session ON audit_events(session_id, created_at);
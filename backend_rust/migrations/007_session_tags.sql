@@ -0,0 +1,5 @@
+-- Lowercase categories such as "hiking" or "delivery" that session lists
+-- can be filtered by
+ALTER TABLE sessions ADD COLUMN tags TEXT[] NOT NULL DEFAULT '{}';
+
+CREATE INDEX idx_sessions_tags ON sessions USING GIN (tags);
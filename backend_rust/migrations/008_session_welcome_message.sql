@@ -0,0 +1,3 @@
+-- Organizer's instructions, e.g. "Meet at the north gate", sent to each
+-- participant when they connect
+ALTER TABLE sessions ADD COLUMN welcome_message TEXT;
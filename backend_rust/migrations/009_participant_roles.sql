@@ -0,0 +1,9 @@
+-- View-only sessions, e.g. a transit tracker, where only publishers may
+-- share their location and everyone else watches. Each participant's role
+-- is either publisher or viewer.
+ALTER TABLE sessions ADD COLUMN view_only BOOLEAN NOT NULL DEFAULT false;
+
+ALTER TABLE participants ADD COLUMN role VARCHAR(10) NOT NULL DEFAULT 'publisher';
+
+ALTER TABLE participants ADD CONSTRAINT chk_participants_role
+    CHECK (role IN ('publisher', 'viewer'));
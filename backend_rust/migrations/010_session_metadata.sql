@@ -0,0 +1,3 @@
+-- Small client-defined JSON object, e.g. a theme or icon URL, that
+-- frontends can attach to a session without schema changes
+ALTER TABLE sessions ADD COLUMN metadata JSONB NOT NULL DEFAULT '{}';
@@ -0,0 +1,3 @@
+-- Opt-in waitlist: joiners of a full session queue for the next free slot
+-- instead of being turned away
+ALTER TABLE sessions ADD COLUMN waitlist_enabled BOOLEAN NOT NULL DEFAULT false;
@@ -0,0 +1,3 @@
+-- Opt-in location interpolation: location broadcasts are preceded by
+-- waypoints filling in the hop from each participant's previous fix
+ALTER TABLE sessions ADD COLUMN interpolate_locations BOOLEAN NOT NULL DEFAULT false;
@@ -0,0 +1,3 @@
+-- Optional window after creation during which new participants can join;
+-- NULL keeps the session open to joiners until it ends
+ALTER TABLE sessions ADD COLUMN join_window_minutes BIGINT;
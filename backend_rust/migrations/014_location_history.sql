@@ -0,0 +1,13 @@
+-- Durable copy of accepted location updates, written when location
+-- persistence is enabled; removed along with its session
+CREATE TABLE location_history (
+    id BIGSERIAL PRIMARY KEY,
+    session_id UUID NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+    user_id VARCHAR(255) NOT NULL,
+    lat DOUBLE PRECISION NOT NULL,
+    lng DOUBLE PRECISION NOT NULL,
+    accuracy DOUBLE PRECISION NOT NULL,
+    recorded_at TIMESTAMP WITH TIME ZONE NOT NULL
+);
+
+CREATE INDEX idx_location_history_participant ON location_history(session_id, user_id, recorded_at);
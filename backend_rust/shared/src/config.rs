@@ -1,6 +1,11 @@
 use config::{Config, ConfigError, Environment, File};
+use jsonwebtoken::{Algorithm, Validation};
 use serde::{Deserialize, Serialize};
 use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+
+use crate::net::IpRange;
 
 /// Application configuration structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -10,6 +15,7 @@ pub struct AppConfig {
     pub server: ServerConfig,
     pub jwt: JwtConfig,
     pub app: AppSettings,
+    pub rate_limit: RateLimitConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,6 +25,13 @@ pub struct DatabaseConfig {
     pub min_connections: u32,
     pub connect_timeout: u64,
     pub idle_timeout: u64,
+    /// How many times to try reaching Postgres at startup before giving up
+    pub connect_attempts: u32,
+    /// Delay before the first retry, doubled (plus jitter) after each failure
+    pub connect_retry_base_ms: u64,
+    /// Start without reaching Postgres and connect on first use, so the API
+    /// server survives a brief database outage at startup
+    pub connect_lazy: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -27,6 +40,11 @@ pub struct RedisConfig {
     pub max_connections: u32,
     pub connection_timeout: u64,
     pub command_timeout: u64,
+    /// Namespace prepended to every key and channel (e.g. "myapp" gives
+    /// `myapp:locations:...`), so deployments can share a Redis instance
+    pub key_prefix: String,
+    /// Largest message published to a pub/sub channel, in bytes
+    pub max_publish_bytes: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -36,8 +54,27 @@ pub struct ServerConfig {
     pub ws_host: String,
     pub ws_port: u16,
     pub cors_allowed_origins: Vec<String>,
+    /// Send `Access-Control-Allow-Credentials` so browsers include cookies;
+    /// requires explicit origins, methods and headers
+    pub cors_allow_credentials: bool,
+    /// Methods allowed in cross-origin requests, or `["*"]` for any
+    pub cors_allowed_methods: Vec<String>,
+    /// Request headers allowed in cross-origin requests, or `["*"]` for any
+    pub cors_allowed_headers: Vec<String>,
     pub request_timeout: u64,
     pub max_request_size: u64,
+    /// Compress API responses the client accepts compressed; turn off when
+    /// a proxy in front already does
+    pub compression_enabled: bool,
+    /// Proxies (IP addresses or CIDR ranges) whose `X-Forwarded-For` and
+    /// `X-Real-IP` headers are believed; other peers are taken as the client
+    #[serde(default)]
+    pub trusted_proxies: Vec<String>,
+    /// PEM certificate chain for serving `wss://` directly; plain `ws://`
+    /// while unset. Set together with `ws_tls_key_path`
+    pub ws_tls_cert_path: Option<String>,
+    /// PEM private key for `ws_tls_cert_path`
+    pub ws_tls_key_path: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -45,17 +82,130 @@ pub struct JwtConfig {
     pub secret: String,
     pub expiration_hours: i64,
     pub algorithm: String,
+    /// `iss` claim set on issued tokens and accepted when verifying
+    pub issuer: String,
+    /// `aud` claim set on issued tokens and accepted when verifying
+    pub audience: String,
+    /// Reject tokens without `iss`/`aud` claims; leave off while tokens
+    /// issued before these claims existed are still in use
+    pub require_issuer_audience: bool,
+    /// Clock skew tolerated when checking `exp` and `iat`, in seconds
+    pub leeway_seconds: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppSettings {
     pub environment: String,
     pub log_level: String,
+    pub log_format: String,
+    /// Case of response field names: "snake_case" (default) or "camelCase"
+    pub json_field_case: String,
     pub base_url: String,
     pub base_ws_url: String,
+    /// Path prefix when served behind a reverse proxy (e.g. "/location"), or empty
+    pub base_path: String,
+    /// Request path the WebSocket server upgrades on; other paths get 404
+    pub ws_path: String,
+    /// Where the API server listens, `tcp://host:port` or `unix:///path/to.sock`;
+    /// empty uses `server.api_host` and `server.api_port`
+    pub api_bind: String,
     pub max_participants_per_session: usize,
+    pub max_active_sessions_per_creator: usize,
+    /// Longest session a creator can ask for, in minutes
+    pub max_session_duration_minutes: i64,
+    /// Name sessions created without one, rather than leaving them unnamed
+    pub auto_generate_session_names: bool,
+    pub distinct_avatar_colors: bool,
+    /// Hex colors assigned to participants who don't pick one; empty uses
+    /// the built-in palette
+    #[serde(default)]
+    pub avatar_palette: Vec<String>,
+    /// Hex colors participants can't pick or be assigned, e.g. one kept for
+    /// an organizer marker
+    #[serde(default)]
+    pub reserved_avatar_colors: Vec<String>,
+    /// Words a display name can't contain, matched as whole words ignoring
+    /// case
+    #[serde(default)]
+    pub display_name_denylist: Vec<String>,
+    /// Concurrent WebSocket connections a server instance accepts
+    pub max_total_connections: usize,
+    /// Refuse a second connection by a user already connected, instead of
+    /// closing their existing one in its favor
+    pub single_connection_per_user: bool,
+    /// Messages queued for a slow WebSocket client before location
+    /// broadcasts are coalesced to the latest per participant
+    pub outgoing_queue_capacity: usize,
+    /// Largest WebSocket message a client may send, in bytes; larger ones
+    /// close the connection
+    pub max_ws_message_bytes: usize,
+    /// Largest single WebSocket frame a client may send, in bytes
+    pub max_ws_frame_bytes: usize,
     pub location_ttl_seconds: usize,
+    /// Most locations kept in a participant's history
+    pub location_history_max_points: usize,
+    /// How long a participant's history outlives their last update
+    pub location_history_ttl_seconds: u64,
+    /// Also keep accepted locations in Postgres, so history survives Redis
+    /// and is read from there
+    pub persist_locations: bool,
+    /// Updates closer than this to the last broadcast position aren't
+    /// broadcast; 0 disables the filter
+    pub min_movement_meters: f64,
+    /// Location updates less accurate than this are rejected; None accepts
+    /// any accuracy
+    pub max_accuracy_meters: Option<f64>,
+    /// Longest time an update can be withheld as jitter before it is
+    /// broadcast anyway
+    pub max_suppress_seconds: i64,
+    /// Spacing of the waypoints sent between fixes in sessions that
+    /// interpolate locations
+    pub interpolation_step_meters: f64,
+    /// Most waypoints sent for a single hop; longer hops space them further apart
+    pub max_interpolated_points: usize,
+    /// Least time between a participant's accepted location updates, by
+    /// their timestamps; updates arriving sooner are dropped. Unlike the
+    /// request rate limit this is per participant and never disconnects;
+    /// 0 accepts every update
+    pub min_update_interval_ms: u64,
+    /// Skip broadcasting an update identical to the participant's stored
+    /// location, only refreshing its TTL
+    pub dedup_identical_locations: bool,
+    /// Largest direct message payload, in bytes of JSON, relayed to a peer
+    pub max_direct_message_bytes: usize,
     pub session_cleanup_interval_minutes: u64,
+    /// How long a session can go without activity before it is ended
+    pub session_inactivity_minutes: i64,
+    /// Delete ended sessions, and their participants, once past the retention window
+    pub session_purge_enabled: bool,
+    /// How long ended sessions are kept after their last activity
+    pub session_retention_days: i64,
+    pub session_purge_interval_minutes: u64,
+    /// How long before a session expires its participants are warned
+    pub session_expiry_warning_minutes: i64,
+    /// How often connections are probed for round-trip time; 0 disables probing
+    pub rtt_probe_interval_seconds: u64,
+    /// Longest a probe may go unanswered before it counts as a timeout
+    pub rtt_probe_timeout_seconds: u64,
+    /// Token admin endpoints require in `X-Admin-Token`; empty disables them
+    pub admin_token: String,
+}
+
+/// Address a server listens on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BindAddress {
+    /// TCP address; the unspecified IPv6 address (`[::]`) listens dual-stack
+    Tcp(SocketAddr),
+    /// Path of a Unix domain socket
+    Unix(PathBuf),
+}
+
+/// Per-client-IP limits for anonymous write endpoints (session create/join)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateLimitConfig {
+    pub enabled: bool,
+    pub max_requests: u32,
+    pub window_seconds: u64,
 }
 
 impl Default for AppConfig {
@@ -67,12 +217,17 @@ impl Default for AppConfig {
                 min_connections: 5,
                 connect_timeout: 30,
                 idle_timeout: 600,
+                connect_attempts: 5,
+                connect_retry_base_ms: 500,
+                connect_lazy: false,
             },
             redis: RedisConfig {
                 url: "redis://localhost:6379".to_string(),
                 max_connections: 20,
                 connection_timeout: 5,
                 command_timeout: 10,
+                key_prefix: String::new(),
+                max_publish_bytes: 256 * 1024,
             },
             server: ServerConfig {
                 api_host: "0.0.0.0".to_string(),
@@ -83,27 +238,142 @@ impl Default for AppConfig {
                     "http://localhost:3000".to_string(),
                     "http://localhost:8080".to_string(),
                 ],
+                cors_allow_credentials: false,
+                cors_allowed_methods: ["GET", "POST", "PUT", "PATCH", "DELETE", "OPTIONS"]
+                    .map(String::from)
+                    .to_vec(),
+                cors_allowed_headers: [
+                    "content-type",
+                    "authorization",
+                    "accept",
+                    "origin",
+                    "x-request-id",
+                    "x-creator-id",
+                ]
+                .map(String::from)
+                .to_vec(),
                 request_timeout: 30,
                 max_request_size: 1048576, // 1MB
+                compression_enabled: true,
+                trusted_proxies: Vec::new(),
+                ws_tls_cert_path: None,
+                ws_tls_key_path: None,
             },
             jwt: JwtConfig {
                 secret: "your-super-secret-jwt-key-change-in-production".to_string(),
                 expiration_hours: 24,
                 algorithm: "HS256".to_string(),
+                issuer: "location-sharing-api".to_string(),
+                audience: "location-sharing-websocket".to_string(),
+                require_issuer_audience: false,
+                leeway_seconds: 60,
             },
             app: AppSettings {
                 environment: "development".to_string(),
                 log_level: "info".to_string(),
+                log_format: "text".to_string(),
+                json_field_case: "snake_case".to_string(),
                 base_url: "http://localhost:8080".to_string(),
                 base_ws_url: "ws://localhost:8081".to_string(),
+                base_path: String::new(),
+                ws_path: "/ws".to_string(),
+                api_bind: String::new(),
                 max_participants_per_session: 50,
+                max_active_sessions_per_creator: 10,
+                max_session_duration_minutes: 10080, // 7 days
+                auto_generate_session_names: true,
+                distinct_avatar_colors: false,
+                avatar_palette: Vec::new(),
+                reserved_avatar_colors: Vec::new(),
+                display_name_denylist: Vec::new(),
+                max_total_connections: 10000,
+                single_connection_per_user: false,
+                outgoing_queue_capacity: 64,
+                max_ws_message_bytes: 64 * 1024,
+                max_ws_frame_bytes: 64 * 1024,
                 location_ttl_seconds: 30,
+                location_history_max_points: 1000,
+                location_history_ttl_seconds: 86400,
+                persist_locations: false,
+                min_movement_meters: 0.0,
+                max_accuracy_meters: None,
+                max_suppress_seconds: 10,
+                interpolation_step_meters: 10.0,
+                max_interpolated_points: 20,
+                min_update_interval_ms: 0,
+                dedup_identical_locations: true,
+                max_direct_message_bytes: 4096,
                 session_cleanup_interval_minutes: 5,
+                session_inactivity_minutes: 60,
+                session_purge_enabled: false,
+                session_retention_days: 30,
+                session_purge_interval_minutes: 60,
+                session_expiry_warning_minutes: 5,
+                rtt_probe_interval_seconds: 30,
+                rtt_probe_timeout_seconds: 10,
+                admin_token: String::new(),
+            },
+            rate_limit: RateLimitConfig {
+                enabled: true,
+                max_requests: 20,
+                window_seconds: 60,
             },
         }
     }
 }
 
+impl ServerConfig {
+    /// Trusted proxy ranges, skipping any that don't parse
+    pub fn trusted_proxy_ranges(&self) -> Vec<IpRange> {
+        self.trusted_proxies
+            .iter()
+            .filter_map(|range| range.parse().ok())
+            .collect()
+    }
+
+    /// WebSocket TLS certificate and key paths, with empty ones as unset
+    pub fn ws_tls_paths(&self) -> (Option<&str>, Option<&str>) {
+        fn non_empty(path: &Option<String>) -> Option<&str> {
+            path.as_deref().filter(|path| !path.is_empty())
+        }
+        (non_empty(&self.ws_tls_cert_path), non_empty(&self.ws_tls_key_path))
+    }
+}
+
+impl JwtConfig {
+    /// Parse the configured signing algorithm
+    ///
+    /// Only HMAC algorithms are supported since tokens are signed with a
+    /// shared secret.
+    pub fn signing_algorithm(&self) -> Result<Algorithm, String> {
+        match self.algorithm.to_uppercase().as_str() {
+            "HS256" => Ok(Algorithm::HS256),
+            "HS384" => Ok(Algorithm::HS384),
+            "HS512" => Ok(Algorithm::HS512),
+            other => Err(format!(
+                "Unsupported JWT algorithm \"{}\" (expected HS256, HS384 or HS512)",
+                other
+            )),
+        }
+    }
+
+    /// Build the rules tokens are verified against
+    ///
+    /// A token's `iss` and `aud` claims must match the configured values when
+    /// present, and must be present once `require_issuer_audience` is set.
+    /// Expiry is checked with `leeway_seconds` of tolerance.
+    pub fn validation(&self) -> Result<Validation, String> {
+        let mut validation = Validation::new(self.signing_algorithm()?);
+        validation.leeway = self.leeway_seconds;
+        validation.set_issuer(&[&self.issuer]);
+        validation.set_audience(&[&self.audience]);
+        if self.require_issuer_audience {
+            validation.set_required_spec_claims(&["exp", "iss", "aud"]);
+        }
+        Ok(validation)
+    }
+}
+
 impl AppConfig {
     /// Load configuration from environment variables and config files
     pub fn load() -> Result<Self, ConfigError> {
@@ -169,6 +439,12 @@ impl AppConfig {
             return Err("JWT secret should be at least 32 characters long".to_string());
         }
         
+        self.jwt.signing_algorithm()?;
+        
+        if self.jwt.issuer.is_empty() || self.jwt.audience.is_empty() {
+            return Err("JWT issuer and audience cannot be empty".to_string());
+        }
+        
         // Validate ports
         if self.server.api_port == 0 {
             return Err("API port must be specified".to_string());
@@ -182,6 +458,44 @@ impl AppConfig {
             return Err("API and WebSocket ports must be different".to_string());
         }
         
+        self.api_bind_address()?;
+        self.ws_address()?;
+        
+        // Browsers reject credentialed responses that use a wildcard
+        if self.server.cors_allow_credentials {
+            let server = &self.server;
+            let has_wildcard = |values: &[String]| values.iter().any(|value| value.trim() == "*");
+            if has_wildcard(&server.cors_allowed_origins)
+                || has_wildcard(&server.cors_allowed_methods)
+                || has_wildcard(&server.cors_allowed_headers)
+            {
+                return Err(
+                    "CORS credentials cannot be combined with a \"*\" origin, method or header".to_string(),
+                );
+            }
+            
+            if server.cors_allowed_origins.is_empty() {
+                return Err("CORS credentials require at least one allowed origin".to_string());
+            }
+        }
+        
+        let (tls_cert, tls_key) = self.server.ws_tls_paths();
+        if tls_cert.is_some() != tls_key.is_some() {
+            return Err("WebSocket TLS certificate and key paths must be set together".to_string());
+        }
+        
+        for range in &self.server.trusted_proxies {
+            range.parse::<IpRange>()?;
+        }
+        
+        if self.server.request_timeout == 0 {
+            return Err("Request timeout must be greater than 0".to_string());
+        }
+        
+        if self.server.max_request_size == 0 {
+            return Err("Max request size must be greater than 0".to_string());
+        }
+        
         // Validate connection limits
         if self.database.max_connections == 0 {
             return Err("Database max connections must be greater than 0".to_string());
@@ -191,19 +505,155 @@ impl AppConfig {
             return Err("Database min connections cannot exceed max connections".to_string());
         }
         
+        if self.database.connect_attempts == 0 {
+            return Err("Database connect attempts must be greater than 0".to_string());
+        }
+        
         if self.redis.max_connections == 0 {
             return Err("Redis max connections must be greater than 0".to_string());
         }
         
+        if self.redis.key_prefix.chars().any(|c| c.is_whitespace() || "*?[]\\".contains(c)) {
+            return Err("Redis key prefix cannot contain whitespace or glob characters".to_string());
+        }
+        
+        if self.redis.max_publish_bytes == 0 {
+            return Err("Redis max publish bytes must be greater than 0".to_string());
+        }
+        
         // Validate app settings
+        if !matches!(self.app.log_format.to_lowercase().as_str(), "text" | "json") {
+            return Err("Log format must be either \"text\" or \"json\"".to_string());
+        }
+        
+        if !matches!(self.app.json_field_case.as_str(), "snake_case" | "camelCase") {
+            return Err("JSON field case must be either \"snake_case\" or \"camelCase\"".to_string());
+        }
+        
+        if !self.app.base_path.is_empty()
+            && (!self.app.base_path.starts_with('/') || self.app.base_path.ends_with('/'))
+        {
+            return Err("Base path must start with \"/\" and not end with \"/\"".to_string());
+        }
+        
+        if !self.app.ws_path.starts_with('/') {
+            return Err("WebSocket path must start with \"/\"".to_string());
+        }
+        
         if self.app.max_participants_per_session == 0 {
             return Err("Max participants per session must be greater than 0".to_string());
         }
         
+        if self.app.max_active_sessions_per_creator == 0 {
+            return Err("Max active sessions per creator must be greater than 0".to_string());
+        }
+        
+        if self.app.max_session_duration_minutes <= 0 {
+            return Err("Max session duration must be greater than 0 minutes".to_string());
+        }
+        
+        crate::utils::validate_avatar_palette(&self.app.avatar_palette)?;
+        
+        let reserved = &self.app.reserved_avatar_colors;
+        if let Some(color) = reserved.iter().find(|color| !crate::utils::is_valid_hex_color(color)) {
+            return Err(format!("Invalid reserved avatar color: {:?}", color));
+        }
+        
+        if crate::types::Constants::DEFAULT_AVATAR_COLORS
+            .iter()
+            .all(|color| crate::utils::is_reserved_avatar_color(color, reserved))
+        {
+            return Err("Reserved avatar colors cannot include every built-in color".to_string());
+        }
+        
+        // Names are matched word by word, so anything else would never match
+        let is_word = |word: &str| {
+            let word = word.trim();
+            !word.is_empty() && word.chars().all(char::is_alphanumeric)
+        };
+        if let Some(word) = self.app.display_name_denylist.iter().find(|word| !is_word(word)) {
+            return Err(format!("Display name denylist entries must be single words: {:?}", word));
+        }
+        
+        if self.app.max_total_connections == 0 {
+            return Err("Max total connections must be greater than 0".to_string());
+        }
+        
+        if self.app.min_movement_meters < 0.0 {
+            return Err("Min movement meters cannot be negative".to_string());
+        }
+        
+        if self.app.max_accuracy_meters.is_some_and(|max| max.is_nan() || max <= 0.0) {
+            return Err("Max accuracy must be greater than 0 meters".to_string());
+        }
+        
+        if self.app.max_suppress_seconds <= 0 {
+            return Err("Max suppress seconds must be greater than 0".to_string());
+        }
+        
+        if self.app.interpolation_step_meters.is_nan() || self.app.interpolation_step_meters <= 0.0 {
+            return Err("Interpolation step must be greater than 0 meters".to_string());
+        }
+        
+        if self.app.outgoing_queue_capacity == 0 {
+            return Err("Outgoing queue capacity must be greater than 0".to_string());
+        }
+        
+        if self.app.max_ws_frame_bytes == 0 || self.app.max_ws_frame_bytes > self.app.max_ws_message_bytes {
+            return Err("Max WebSocket frame size must be between 1 byte and the max message size".to_string());
+        }
+        
+        if self.app.max_direct_message_bytes == 0 {
+            return Err("Max direct message size must be greater than 0 bytes".to_string());
+        }
+        
+        if self.app.session_inactivity_minutes <= 0 {
+            return Err("Session inactivity window must be greater than 0 minutes".to_string());
+        }
+        
+        if self.app.session_retention_days <= 0 {
+            return Err("Session retention must be greater than 0 days".to_string());
+        }
+        
+        if self.app.session_purge_interval_minutes == 0 {
+            return Err("Session purge interval must be greater than 0 minutes".to_string());
+        }
+        
+        if self.app.session_expiry_warning_minutes <= 0 {
+            return Err("Session expiry warning must be greater than 0 minutes".to_string());
+        }
+        
+        if self.app.rtt_probe_timeout_seconds == 0 {
+            return Err("RTT probe timeout must be greater than 0 seconds".to_string());
+        }
+        
+        if !self.app.admin_token.is_empty() && self.app.admin_token.len() < 32 {
+            return Err("Admin token should be at least 32 characters long".to_string());
+        }
+        
         if self.app.location_ttl_seconds == 0 {
             return Err("Location TTL must be greater than 0".to_string());
         }
         
+        if self.app.location_history_max_points == 0 {
+            return Err("Location history max points must be greater than 0".to_string());
+        }
+        
+        if self.app.location_history_ttl_seconds == 0 {
+            return Err("Location history TTL must be greater than 0".to_string());
+        }
+        
+        // Validate rate limiting
+        if self.rate_limit.enabled {
+            if self.rate_limit.max_requests == 0 {
+                return Err("Rate limit max requests must be greater than 0".to_string());
+            }
+            
+            if self.rate_limit.window_seconds == 0 {
+                return Err("Rate limit window must be greater than 0".to_string());
+            }
+        }
+        
         Ok(())
     }
     
@@ -243,17 +693,63 @@ impl AppConfig {
         self.app.environment.to_lowercase() == "development"
     }
     
+    /// Check if logs should be emitted as JSON lines
+    pub fn is_json_logging(&self) -> bool {
+        self.app.log_format.to_lowercase() == "json"
+    }
+    
+    /// Check if response field names should be camelCase
+    pub fn is_camel_case_json(&self) -> bool {
+        self.app.json_field_case == "camelCase"
+    }
+    
     /// Get API server address
-    pub fn api_address(&self) -> String {
-        format!("{}:{}", self.server.api_host, self.server.api_port)
+    pub fn api_address(&self) -> Result<SocketAddr, String> {
+        socket_address(&self.server.api_host, self.server.api_port)
+    }
+    
+    /// Parse where the API server should listen
+    pub fn api_bind_address(&self) -> Result<BindAddress, String> {
+        let bind = self.app.api_bind.trim();
+        if bind.is_empty() {
+            return self.api_address().map(BindAddress::Tcp);
+        }
+        
+        if let Some(address) = bind.strip_prefix("tcp://") {
+            return match address.parse::<SocketAddr>() {
+                Ok(address) if address.port() != 0 => Ok(BindAddress::Tcp(address)),
+                _ => Err(format!(
+                    "API bind \"{}\" must be tcp://ip:port (IPv6 as tcp://[ip]:port)",
+                    bind
+                )),
+            };
+        }
+        
+        if let Some(path) = bind.strip_prefix("unix://") {
+            if !path.starts_with('/') {
+                return Err(format!("API bind \"{}\" must be unix:// followed by an absolute path", bind));
+            }
+            return Ok(BindAddress::Unix(PathBuf::from(path)));
+        }
+        
+        Err(format!("API bind \"{}\" must start with tcp:// or unix://", bind))
     }
     
     /// Get WebSocket server address
-    pub fn ws_address(&self) -> String {
-        format!("{}:{}", self.server.ws_host, self.server.ws_port)
+    pub fn ws_address(&self) -> Result<SocketAddr, String> {
+        socket_address(&self.server.ws_host, self.server.ws_port)
     }
 }
 
+/// Combine a configured host, an IPv4 or IPv6 address (optionally in
+/// brackets), with a port
+fn socket_address(host: &str, port: u16) -> Result<SocketAddr, String> {
+    let ip = host.trim().trim_start_matches('[').trim_end_matches(']');
+    ip.parse::<IpAddr>()
+        .map(|ip| SocketAddr::new(ip, port))
+        .map_err(|_| format!("Host \"{}\" must be an IPv4 or IPv6 address", host))
+}
+
 // Custom Display implementation to hide sensitive information
 impl fmt::Display for AppConfig {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -0,0 +1,91 @@
+use rand::Rng;
+use std::future::Future;
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::config::DatabaseConfig;
+use crate::error::AppResult;
+
+/// Call `connect` until it succeeds or `connect_attempts` is used up,
+/// waiting an exponentially growing, jittered delay between attempts
+pub async fn connect_with_retry<T, F, Fut>(config: &DatabaseConfig, mut connect: F) -> AppResult<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, sqlx::Error>>,
+{
+    let max_attempts = config.connect_attempts.max(1);
+    let mut backoff = Duration::from_millis(config.connect_retry_base_ms);
+    let mut attempt = 1;
+
+    loop {
+        info!("PostgreSQL connection attempt {}/{}", attempt, max_attempts);
+        match connect().await {
+            Ok(connection) => return Ok(connection),
+            Err(e) if attempt < max_attempts => {
+                let delay = with_jitter(backoff);
+                warn!(
+                    "PostgreSQL connection attempt {}/{} failed: {}; retrying in {:?}",
+                    attempt, max_attempts, e, delay
+                );
+                tokio::time::sleep(delay).await;
+                backoff *= 2;
+                attempt += 1;
+            }
+            Err(e) => {
+                warn!("Giving up on PostgreSQL after {} attempts: {}", attempt, e);
+                return Err(e.into());
+            }
+        }
+    }
+}
+
+/// Add up to 50% random jitter so restarted replicas don't retry in lockstep
+fn with_jitter(delay: Duration) -> Duration {
+    let jitter = rand::thread_rng().gen_range(0.0..0.5);
+    delay.mul_f64(1.0 + jitter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::AppError;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn retry_config(connect_attempts: u32) -> DatabaseConfig {
+        let mut config = crate::config::AppConfig::default().database;
+        config.connect_attempts = connect_attempts;
+        config.connect_retry_base_ms = 1;
+        config
+    }
+
+    /// Stub connector that fails until its `succeed_on`-th call
+    fn flaky_connector(
+        calls: &AtomicU32,
+        succeed_on: u32,
+    ) -> impl FnMut() -> std::future::Ready<Result<u32, sqlx::Error>> + '_ {
+        move || {
+            let call = calls.fetch_add(1, Ordering::SeqCst) + 1;
+            std::future::ready(if call >= succeed_on {
+                Ok(call)
+            } else {
+                Err(sqlx::Error::PoolTimedOut)
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_connect_succeeds_after_retries() {
+        let calls = AtomicU32::new(0);
+        let result = connect_with_retry(&retry_config(5), flaky_connector(&calls, 3)).await;
+        assert_eq!(result.unwrap(), 3);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_connect_gives_up_after_max_attempts() {
+        let calls = AtomicU32::new(0);
+        let result = connect_with_retry(&retry_config(2), flaky_connector(&calls, 3)).await;
+        assert!(matches!(result, Err(AppError::Database(sqlx::Error::PoolTimedOut))));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+}
\ No newline at end of file
@@ -1,3 +1,4 @@
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 /// Application-wide error types for comprehensive error handling
@@ -40,9 +41,18 @@ pub enum AppError {
     #[error("Session capacity exceeded (max {max} participants)")]
     SessionCapacityExceeded { max: usize },
     
+    #[error("Session limit exceeded (max {max} active sessions per creator)")]
+    SessionLimitExceeded { max: usize },
+    
     #[error("Unauthorized session operation")]
     UnauthorizedSessionOperation,
     
+    #[error("Session is no longer accepting new participants")]
+    JoinWindowClosed,
+    
+    #[error("Could not be admitted from the waitlist: {reason}")]
+    WaitlistAdmissionFailed { reason: String },
+    
     /// Participant-specific errors
     #[error("Participant not found")]
     ParticipantNotFound,
@@ -63,12 +73,19 @@ pub enum AppError {
     #[error("Insufficient permissions")]
     InsufficientPermissions,
     
-    /// Input validation errors
-    #[error("Validation error: {field} - {message}")]
-    Validation { field: String, message: String },
+    /// Input validation errors, one per invalid field
+    #[error("Validation error: {}", describe_field_errors(.errors))]
+    Validation { errors: Vec<FieldError> },
     
-    #[error("Invalid request format")]
-    InvalidRequest,
+    #[error("Invalid request format: {message}")]
+    InvalidRequest { message: String },
+    
+    /// Idempotent request errors
+    #[error("Idempotency-Key was already used for a different request")]
+    IdempotencyKeyReused,
+    
+    #[error("A request with this Idempotency-Key is still in progress")]
+    IdempotencyKeyInProgress,
     
     /// WebSocket-specific errors
     #[error("WebSocket connection error: {0}")]
@@ -77,6 +94,12 @@ pub enum AppError {
     #[error("Invalid WebSocket message format")]
     InvalidWebSocketMessage,
     
+    #[error("Connection replaced by a newer connection for the same user")]
+    ConnectionReplaced,
+    
+    #[error("Already connected from another client")]
+    AlreadyConnected,
+    
     /// Location-related errors
     #[error("Invalid location data: {message}")]
     InvalidLocation { message: String },
@@ -99,19 +122,26 @@ pub enum AppError {
     #[error("Service unavailable: {service}")]
     ServiceUnavailable { service: String },
     
-    #[error("Rate limit exceeded")]
-    RateLimitExceeded,
+    #[error("Rate limit exceeded, retry after {retry_after_seconds} seconds")]
+    RateLimitExceeded { retry_after_seconds: u64 },
+    
+    #[error("Message too large to publish ({size} bytes, max {max})")]
+    PublishTooLarge { size: usize, max: usize },
 }
 
 impl AppError {
     /// Create a validation error with field and message
     pub fn validation(field: &str, message: &str) -> Self {
         Self::Validation {
-            field: field.to_string(),
-            message: message.to_string(),
+            errors: vec![FieldError::new(field, message)],
         }
     }
     
+    /// Create a validation error reporting several invalid fields at once
+    pub fn validation_errors(errors: Vec<FieldError>) -> Self {
+        Self::Validation { errors }
+    }
+    
     /// Create an invalid participant data error
     pub fn invalid_participant_data(message: &str) -> Self {
         Self::InvalidParticipantData {
@@ -119,6 +149,13 @@ impl AppError {
         }
     }
     
+    /// Create an invalid request format error, e.g. for a malformed body
+    pub fn invalid_request(message: &str) -> Self {
+        Self::InvalidRequest {
+            message: message.to_string(),
+        }
+    }
+    
     /// Create an invalid location error
     pub fn invalid_location(message: &str) -> Self {
         Self::InvalidLocation {
@@ -138,6 +175,38 @@ impl AppError {
         }
     }
     
+    /// Create a rate limit error telling the client when to retry
+    pub fn rate_limit_exceeded(retry_after_seconds: u64) -> Self {
+        Self::RateLimitExceeded { retry_after_seconds }
+    }
+    
+    /// Get the invalid fields behind a validation error, if any
+    pub fn field_errors(&self) -> &[FieldError] {
+        match self {
+            Self::Validation { errors } => errors,
+            _ => &[],
+        }
+    }
+    
+    /// Get the number of seconds a client should wait before retrying, if any
+    pub fn retry_after_seconds(&self) -> Option<u64> {
+        match self {
+            Self::RateLimitExceeded { retry_after_seconds } => Some(*retry_after_seconds),
+            _ => None,
+        }
+    }
+    
+    /// Check if error means Redis itself is unreachable, rather than a
+    /// failure of a single command
+    pub fn is_redis_connection_error(&self) -> bool {
+        match self {
+            Self::Redis(e) => {
+                e.is_io_error() || e.is_connection_refusal() || e.is_connection_dropped() || e.is_timeout()
+            }
+            _ => false,
+        }
+    }
+    
     /// Check if error is client-side (4xx status code equivalent)
     pub fn is_client_error(&self) -> bool {
         matches!(
@@ -146,7 +215,10 @@ impl AppError {
                 | Self::SessionExpired
                 | Self::SessionInactive
                 | Self::SessionCapacityExceeded { .. }
+                | Self::SessionLimitExceeded { .. }
                 | Self::UnauthorizedSessionOperation
+                | Self::JoinWindowClosed
+                | Self::WaitlistAdmissionFailed { .. }
                 | Self::ParticipantNotFound
                 | Self::ParticipantAlreadyExists
                 | Self::InvalidParticipantData { .. }
@@ -154,10 +226,15 @@ impl AppError {
                 | Self::TokenExpired
                 | Self::InsufficientPermissions
                 | Self::Validation { .. }
-                | Self::InvalidRequest
+                | Self::InvalidRequest { .. }
+                | Self::IdempotencyKeyReused
+                | Self::IdempotencyKeyInProgress
                 | Self::InvalidWebSocketMessage
+                | Self::ConnectionReplaced
+                | Self::AlreadyConnected
                 | Self::InvalidLocation { .. }
-                | Self::RateLimitExceeded
+                | Self::RateLimitExceeded { .. }
+                | Self::PublishTooLarge { .. }
         )
     }
     
@@ -167,16 +244,35 @@ impl AppError {
             Self::SessionNotFound | Self::ParticipantNotFound => 404,
             Self::SessionExpired | Self::SessionInactive => 410, // Gone
             Self::SessionCapacityExceeded { .. } => 409, // Conflict
+            Self::SessionLimitExceeded { .. } => 409, // Conflict
+            Self::WaitlistAdmissionFailed { .. } => 409, // Conflict
             Self::UnauthorizedSessionOperation | Self::InsufficientPermissions => 403,
+            Self::JoinWindowClosed => 403,
             Self::ParticipantAlreadyExists => 409, // Conflict
+            Self::ConnectionReplaced | Self::AlreadyConnected => 409, // Conflict
             Self::InvalidToken | Self::TokenExpired => 401,
-            Self::Validation { .. } | Self::InvalidRequest | Self::InvalidParticipantData { .. } | Self::InvalidLocation { .. } => 400,
-            Self::RateLimitExceeded => 429,
+            Self::Validation { .. } | Self::InvalidRequest { .. } | Self::InvalidParticipantData { .. } | Self::InvalidLocation { .. } => 400,
+            Self::IdempotencyKeyReused => 422, // Unprocessable Entity
+            Self::IdempotencyKeyInProgress => 409, // Conflict
+            Self::RateLimitExceeded { .. } => 429,
+            Self::PublishTooLarge { .. } => 413, // Payload Too Large
             Self::ServiceUnavailable { .. } => 503,
             _ => 500, // Internal server error
         }
     }
     
+    /// Get the WebSocket close code for ending a connection over this error
+    ///
+    /// Client errors use the application range as 4000 plus their HTTP status
+    /// (4001 invalid token, 4010 session gone, 4029 rate limited, ...).
+    pub fn close_code(&self) -> u16 {
+        match self {
+            _ if self.is_client_error() => 4000 + (self.status_code() - 400),
+            Self::ServiceUnavailable { .. } => 1013, // Try again later
+            _ => 1011, // Internal error
+        }
+    }
+    
     /// Get error code for client communication
     pub fn error_code(&self) -> &'static str {
         match self {
@@ -184,7 +280,10 @@ impl AppError {
             Self::SessionExpired => "SESSION_EXPIRED",
             Self::SessionInactive => "SESSION_INACTIVE",
             Self::SessionCapacityExceeded { .. } => "SESSION_CAPACITY_EXCEEDED",
+            Self::SessionLimitExceeded { .. } => "SESSION_LIMIT_EXCEEDED",
             Self::UnauthorizedSessionOperation => "UNAUTHORIZED_SESSION_OPERATION",
+            Self::JoinWindowClosed => "JOIN_WINDOW_CLOSED",
+            Self::WaitlistAdmissionFailed { .. } => "WAITLIST_ADMISSION_FAILED",
             Self::ParticipantNotFound => "PARTICIPANT_NOT_FOUND",
             Self::ParticipantAlreadyExists => "PARTICIPANT_ALREADY_EXISTS",
             Self::InvalidParticipantData { .. } => "INVALID_PARTICIPANT_DATA",
@@ -192,16 +291,46 @@ impl AppError {
             Self::TokenExpired => "TOKEN_EXPIRED",
             Self::InsufficientPermissions => "INSUFFICIENT_PERMISSIONS",
             Self::Validation { .. } => "VALIDATION_ERROR",
-            Self::InvalidRequest => "INVALID_REQUEST",
+            Self::InvalidRequest { .. } => "INVALID_REQUEST",
+            Self::IdempotencyKeyReused => "IDEMPOTENCY_KEY_REUSED",
+            Self::IdempotencyKeyInProgress => "IDEMPOTENCY_KEY_IN_PROGRESS",
             Self::InvalidWebSocketMessage => "INVALID_WEBSOCKET_MESSAGE",
+            Self::ConnectionReplaced => "REPLACED",
+            Self::AlreadyConnected => "ALREADY_CONNECTED",
             Self::InvalidLocation { .. } => "INVALID_LOCATION",
             Self::LocationUpdateFailed => "LOCATION_UPDATE_FAILED",
-            Self::RateLimitExceeded => "RATE_LIMIT_EXCEEDED",
+            Self::RateLimitExceeded { .. } => "RATE_LIMIT_EXCEEDED",
+            Self::PublishTooLarge { .. } => "PAYLOAD_TOO_LARGE",
             Self::ServiceUnavailable { .. } => "SERVICE_UNAVAILABLE",
             _ => "INTERNAL_ERROR",
         }
     }
 }
 
+/// One invalid field in a request, reported alongside any others
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: &str, message: &str) -> Self {
+        Self {
+            field: field.to_string(),
+            message: message.to_string(),
+        }
+    }
+}
+
+/// Every field error as `field - message`, separated by semicolons
+fn describe_field_errors(errors: &[FieldError]) -> String {
+    errors
+        .iter()
+        .map(|error| format!("{} - {}", error.field, error.message))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
 /// Result type alias for application operations
 pub type AppResult<T> = Result<T, AppError>;
\ No newline at end of file
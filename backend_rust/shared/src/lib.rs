@@ -1,18 +1,26 @@
-/// Shared library for location sharing application
-/// 
-/// This library provides common types, error handling, and utilities
-/// used across both the API server and WebSocket server components.
+//! Shared library for location sharing application
+//!
+//! This library provides common types, error handling, and utilities
+//! used across both the API server and WebSocket server components.
 
 pub mod types;
 pub mod error;
 pub mod utils;
 pub mod config;
+pub mod net;
+pub mod logging;
+pub mod database;
+pub mod presence;
 
 // Re-export commonly used types
 pub use types::*;
 pub use error::*;
 pub use utils::*;
 pub use config::*;
+pub use net::*;
+pub use logging::*;
+pub use database::*;
+pub use presence::*;
 
 #[cfg(test)]
 mod tests {
@@ -23,15 +31,53 @@ mod tests {
     fn test_create_session_request_validation() {
         let valid_request = CreateSessionRequest {
             name: Some("Test Session".to_string()),
-            expires_in_minutes: 60,
+            creator_id: None,
+            expires_in_minutes: Some(60),
+            avatar_palette: None,
+            is_public: false,
+            tags: Vec::new(),
+            welcome_message: None,
+            view_only: false,
+            waitlist_enabled: false,
+            interpolate_locations: false,
+            join_window_minutes: None,
+            metadata: None,
         };
-        assert!(valid_request.validate().is_ok());
+        assert!(valid_request.validate(10080).is_ok());
 
         let invalid_request = CreateSessionRequest {
             name: Some("".to_string()),
-            expires_in_minutes: 0,
+            creator_id: None,
+            expires_in_minutes: Some(0),
+            avatar_palette: None,
+            is_public: false,
+            tags: Vec::new(),
+            welcome_message: None,
+            view_only: false,
+            waitlist_enabled: false,
+            interpolate_locations: false,
+            join_window_minutes: None,
+            metadata: None,
         };
-        assert!(invalid_request.validate().is_err());
+        let fields: Vec<_> = invalid_request.validate(10080).unwrap_err().into_iter().map(|error| error.field).collect();
+        assert_eq!(fields, vec!["name", "expires_in_minutes"]);
+    }
+
+    #[test]
+    fn test_create_session_duration_cap() {
+        let mut request: CreateSessionRequest = serde_json::from_str("{}").unwrap();
+        assert!(request.validate(120).is_ok());
+        assert_eq!(request.duration_minutes(120), 120);
+        assert_eq!(request.duration_minutes(10080), Constants::DEFAULT_SESSION_DURATION_MINUTES);
+
+        request.expires_in_minutes = Some(1440);
+        let errors = request.validate(120).unwrap_err();
+        assert_eq!(errors[0].field, "expires_in_minutes");
+        assert!(errors[0].message.contains("120 minutes"), "Unexpected message: {}", errors[0].message);
+
+        request.expires_in_minutes = Some(60);
+        assert!(request.validate(120).is_ok());
+        assert_eq!(request.duration_minutes(120), 60);
     }
 
     #[test]
@@ -39,14 +85,49 @@ mod tests {
         let valid_request = JoinSessionRequest {
             display_name: "John Doe".to_string(),
             avatar_color: Some("#FF5733".to_string()),
+            precision: LocationPrecision::Exact,
+            device_id: None,
+            role: None,
         };
         assert!(valid_request.validate().is_ok());
 
         let invalid_request = JoinSessionRequest {
             display_name: "".to_string(),
             avatar_color: Some("invalid-color".to_string()),
+            precision: LocationPrecision::Exact,
+            device_id: None,
+            role: None,
+        };
+        // Every problem is reported, not just the first
+        let fields: Vec<_> = invalid_request.validate().unwrap_err().into_iter().map(|error| error.field).collect();
+        assert_eq!(fields, vec!["display_name", "avatar_color"]);
+
+        let shorthand_request = JoinSessionRequest {
+            display_name: "John Doe".to_string(),
+            avatar_color: Some("#F53".to_string()),
+            precision: LocationPrecision::Exact,
+            device_id: None,
+            role: None,
+        };
+        assert!(shorthand_request.validate().is_ok());
+
+        let bad_length_request = JoinSessionRequest {
+            display_name: "John Doe".to_string(),
+            avatar_color: Some("#FF57".to_string()),
+            precision: LocationPrecision::Exact,
+            device_id: None,
+            role: None,
+        };
+        assert!(bad_length_request.validate().is_err());
+
+        let bad_device_request = JoinSessionRequest {
+            display_name: "John Doe".to_string(),
+            avatar_color: None,
+            precision: LocationPrecision::Exact,
+            device_id: Some("not a device id".to_string()),
+            role: None,
         };
-        assert!(invalid_request.validate().is_err());
+        assert!(bad_device_request.validate().is_err());
     }
 
     #[test]
@@ -56,6 +137,7 @@ mod tests {
             lng: -122.4194,
             accuracy: 5.0,
             timestamp: Utc::now(),
+            seq: None,
         };
         assert!(valid_location.validate().is_ok());
 
@@ -64,31 +146,145 @@ mod tests {
             lng: -122.4194,
             accuracy: -1.0, // Invalid accuracy
             timestamp: Utc::now(),
+            seq: None,
         };
         assert!(invalid_location.validate().is_err());
     }
 
+    #[test]
+    fn test_location_accuracy_threshold() {
+        let location = |accuracy| LocationUpdateData {
+            lat: 37.7749,
+            lng: -122.4194,
+            accuracy,
+            timestamp: Utc::now(),
+            seq: None,
+        };
+
+        assert!(location(50.0).is_accurate_enough(Some(100.0)));
+        assert!(location(100.0).is_accurate_enough(Some(100.0)));
+        assert!(!location(100.5).is_accurate_enough(Some(100.0)));
+        assert!(location(50_000.0).is_accurate_enough(None));
+    }
+
+    #[test]
+    fn test_paginated_envelope() {
+        let page = Paginated::all(vec!["a", "b"]);
+        assert_eq!(
+            serde_json::to_value(&page).unwrap(),
+            serde_json::json!({ "items": ["a", "b"], "total": 2, "limit": null, "offset": null })
+        );
+
+        let page: Paginated<String> =
+            serde_json::from_str(r#"{"items":["c"],"total":41,"limit":20,"offset":40}"#).unwrap();
+        assert_eq!(page.items, vec!["c"]);
+        assert_eq!((page.total, page.limit, page.offset), (Some(41), Some(20), Some(40)));
+    }
+
+    #[test]
+    fn test_status_update_validation() {
+        let status = StatusUpdateData {
+            status: "On my way".to_string(),
+            emoji: Some("📍".to_string()),
+        };
+        assert!(status.validate().is_ok());
+
+        let at_limit = StatusUpdateData {
+            status: "é".repeat(Constants::MAX_STATUS_LENGTH),
+            emoji: None,
+        };
+        assert!(at_limit.validate().is_ok());
+
+        let too_long = StatusUpdateData {
+            status: "a".repeat(Constants::MAX_STATUS_LENGTH + 1),
+            emoji: None,
+        };
+        assert!(too_long.validate().is_err());
+
+        let empty = StatusUpdateData {
+            status: "   ".to_string(),
+            emoji: None,
+        };
+        assert!(empty.validate().is_err());
+    }
+
+    #[test]
+    fn test_waypoint_validation() {
+        let waypoint = |lat: f64, label: &str| SetWaypointData {
+            lat,
+            lng: -122.4194,
+            label: label.to_string(),
+        };
+        assert!(waypoint(37.7749, "Rally here").validate().is_ok());
+        assert!(waypoint(37.7749, &"é".repeat(Constants::MAX_WAYPOINT_LABEL_LENGTH)).validate().is_ok());
+
+        assert!(waypoint(91.0, "Rally here").validate().is_err());
+        assert!(waypoint(37.7749, " ").validate().is_err());
+        assert!(waypoint(37.7749, &"a".repeat(Constants::MAX_WAYPOINT_LABEL_LENGTH + 1)).validate().is_err());
+    }
+
     #[test]
     fn test_redis_keys() {
         let session_id = uuid::Uuid::new_v4();
         let user_id = "test-user";
+        let keys = RedisKeys::default();
 
         assert_eq!(
-            RedisKeys::location(&session_id, user_id),
+            keys.location(&session_id, user_id),
             format!("locations:{}:{}", session_id, user_id)
         );
 
         assert_eq!(
-            RedisKeys::session_participants(&session_id),
+            keys.session_participants(&session_id),
             format!("session_participants:{}", session_id)
         );
 
         assert_eq!(
-            RedisKeys::session_channel(&session_id),
+            keys.session_channel(&session_id),
             format!("channel:session:{}", session_id)
         );
     }
 
+    #[test]
+    fn test_prefixed_redis_keys() {
+        let session_id = uuid::Uuid::new_v4();
+        let keys = RedisKeys::new("myapp");
+
+        assert_eq!(
+            keys.location(&session_id, "test-user"),
+            format!("myapp:locations:{}:test-user", session_id)
+        );
+        assert_eq!(keys.pattern("connections:*"), "myapp:connections:*");
+        // A trailing separator isn't doubled
+        assert_eq!(RedisKeys::new("myapp:").rate_limit("1.2.3.4"), "myapp:rate_limit:1.2.3.4");
+
+        let channel = keys.session_channel(&session_id);
+        assert_eq!(channel, format!("myapp:channel:session:{}", session_id));
+        assert_eq!(keys.session_channel_pattern(), "myapp:channel:session:*");
+        assert_eq!(keys.session_id_from_channel(&channel), Some(session_id));
+
+        // Channels of another namespace aren't ours
+        assert_eq!(RedisKeys::default().session_id_from_channel(&channel), None);
+        assert_eq!(keys.session_id_from_channel(&RedisKeys::new("other").session_channel(&session_id)), None);
+
+        // Direct channels ride the session subscription but aren't session broadcasts
+        let direct = keys.direct_channel(&session_id, "peer");
+        assert!(direct.starts_with("myapp:channel:session:"));
+        assert_eq!(keys.session_id_from_channel(&direct), None);
+        assert_eq!(keys.direct_target_from_channel(&direct), Some((session_id, "peer".to_string())));
+        assert_eq!(keys.direct_target_from_channel(&channel), None);
+    }
+
+    #[test]
+    fn test_redis_key_prefix_validation() {
+        let mut config = AppConfig::default();
+        config.redis.key_prefix = "tenant-a".to_string();
+        assert!(config.validate().is_ok());
+
+        config.redis.key_prefix = "tenant*".to_string();
+        assert!(config.validate().is_err());
+    }
+
     #[test]
     fn test_error_types() {
         let error = AppError::SessionNotFound;
@@ -100,4 +296,183 @@ mod tests {
         assert_eq!(error.status_code(), 500);
         assert!(!error.is_client_error());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_error_close_codes() {
+        assert_eq!(AppError::InvalidToken.close_code(), 4001);
+        assert_eq!(AppError::TokenExpired.close_code(), 4001);
+        assert_eq!(AppError::UnauthorizedSessionOperation.close_code(), 4003);
+        assert_eq!(AppError::SessionExpired.close_code(), 4010);
+        assert_eq!(AppError::SessionInactive.close_code(), 4010);
+        assert_eq!(AppError::rate_limit_exceeded(30).close_code(), 4029);
+        assert_eq!(AppError::service_unavailable("redis").close_code(), 1013);
+        assert_eq!(AppError::Internal(anyhow::anyhow!("boom")).close_code(), 1011);
+    }
+
+    #[test]
+    fn test_cors_credentials_validation() {
+        let mut config = AppConfig::default();
+        config.server.cors_allow_credentials = true;
+        config.server.cors_allowed_origins = vec!["https://app.example.com".to_string()];
+        assert!(config.validate().is_ok());
+
+        config.server.cors_allowed_origins = vec!["*".to_string()];
+        assert!(config.validate().unwrap_err().contains("CORS credentials"));
+
+        config.server.cors_allowed_origins = vec!["https://app.example.com".to_string()];
+        config.server.cors_allowed_headers = vec!["*".to_string()];
+        assert!(config.validate().is_err());
+
+        config.server.cors_allowed_headers = vec!["content-type".to_string()];
+        config.server.cors_allowed_origins.clear();
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_ws_tls_paths_validation() {
+        let mut config = AppConfig::default();
+        config.server.ws_tls_cert_path = Some("/etc/location/ws.crt".to_string());
+        assert!(config.validate().unwrap_err().contains("TLS"));
+
+        config.server.ws_tls_key_path = Some("/etc/location/ws.key".to_string());
+        assert!(config.validate().is_ok());
+
+        config.server.ws_tls_cert_path = Some(String::new());
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_ws_size_limits_validation() {
+        let mut config = AppConfig::default();
+        config.app.max_ws_frame_bytes = config.app.max_ws_message_bytes + 1;
+        assert!(config.validate().unwrap_err().contains("frame size"));
+
+        config.app.max_ws_frame_bytes = 0;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_api_bind_address() {
+        let mut config = AppConfig::default();
+        assert_eq!(config.api_bind_address(), Ok(BindAddress::Tcp("0.0.0.0:8080".parse().unwrap())));
+
+        config.app.api_bind = "tcp://127.0.0.1:9000".to_string();
+        assert_eq!(config.api_bind_address(), Ok(BindAddress::Tcp("127.0.0.1:9000".parse().unwrap())));
+
+        config.app.api_bind = "tcp://[::]:9000".to_string();
+        assert_eq!(config.api_bind_address(), Ok(BindAddress::Tcp("[::]:9000".parse().unwrap())));
+
+        config.app.api_bind = "unix:///run/location/api.sock".to_string();
+        assert_eq!(
+            config.api_bind_address(),
+            Ok(BindAddress::Unix("/run/location/api.sock".into()))
+        );
+
+        for invalid in [
+            "127.0.0.1:9000",
+            "tcp://127.0.0.1",
+            "tcp://:9000",
+            "tcp://localhost:9000",
+            "tcp://::1:9000",
+            "unix://api.sock",
+            "http://localhost:80",
+        ] {
+            config.app.api_bind = invalid.to_string();
+            assert!(config.validate().is_err(), "{} was accepted", invalid);
+        }
+    }
+
+    #[test]
+    fn test_server_hosts() {
+        let mut config = AppConfig::default();
+        config.server.api_host = "::".to_string();
+        config.server.ws_host = "[::1]".to_string();
+        assert!(config.validate().is_ok());
+        assert_eq!(config.api_address(), Ok("[::]:8080".parse().unwrap()));
+        assert_eq!(config.ws_address(), Ok("[::1]:8081".parse().unwrap()));
+
+        config.server.ws_host = "ws.example.com".to_string();
+        assert!(config.validate().unwrap_err().contains("ws.example.com"));
+    }
+
+    #[test]
+    fn test_avatar_palette_validation() {
+        let mut config = AppConfig::default();
+        config.app.avatar_palette = vec!["#1A2B3C".to_string(), "#fff".to_string()];
+        assert!(config.validate().is_ok());
+
+        config.app.avatar_palette.push("not-a-color".to_string());
+        assert!(config.validate().unwrap_err().contains("not-a-color"));
+
+        config.app.avatar_palette.pop();
+        config.app.reserved_avatar_colors = vec!["#f00".to_string()];
+        assert!(config.validate().is_ok());
+
+        config.app.reserved_avatar_colors = Constants::DEFAULT_AVATAR_COLORS.iter().map(|c| c.to_string()).collect();
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_load_keeps_empty_list_defaults() {
+        // Empty lists don't survive the round trip through the config
+        // builder, so loading relies on their serde defaults
+        let config = AppConfig::load().unwrap();
+        assert!(config.app.avatar_palette.is_empty());
+        assert!(config.app.reserved_avatar_colors.is_empty());
+        assert!(config.server.trusted_proxies.is_empty());
+    }
+
+    #[test]
+    fn test_display_name_denylist_validation() {
+        let mut config = AppConfig::default();
+        config.app.display_name_denylist = vec!["spam".to_string(), " Admin ".to_string()];
+        assert!(config.validate().is_ok());
+
+        config.app.display_name_denylist.push("two words".to_string());
+        assert!(config.validate().unwrap_err().contains("single words"));
+    }
+
+    #[test]
+    fn test_jwt_signing_algorithm() {
+        let mut config = AppConfig::default();
+        assert_eq!(config.jwt.signing_algorithm(), Ok(jsonwebtoken::Algorithm::HS256));
+
+        config.jwt.algorithm = "hs512".to_string();
+        assert_eq!(config.jwt.signing_algorithm(), Ok(jsonwebtoken::Algorithm::HS512));
+
+        config.jwt.algorithm = "RS256".to_string();
+        assert!(config.jwt.signing_algorithm().is_err());
+    }
+
+    #[test]
+    fn test_location_precision() {
+        let location = Location {
+            lat: 37.774934,
+            lng: -122.419416,
+            accuracy: 5.0,
+            timestamp: chrono::Utc::now(),
+        };
+
+        let exact = LocationPrecision::Exact.apply(&location);
+        assert_eq!(exact.lat, location.lat);
+        assert_eq!(exact.lng, location.lng);
+        assert_eq!(exact.accuracy, location.accuracy);
+
+        let coarse = LocationPrecision::Coarse.apply(&location);
+        assert!((coarse.lat - 37.775).abs() < 1e-9);
+        assert!((coarse.lng - -122.419).abs() < 1e-9);
+        assert!(coarse.accuracy >= 100.0);
+        assert_eq!(coarse.timestamp, location.timestamp);
+
+        let city = LocationPrecision::City.apply(&location);
+        assert!((city.lat - 37.8).abs() < 1e-9);
+        assert!((city.lng - -122.4).abs() < 1e-9);
+        assert!(city.accuracy >= 10_000.0);
+
+        let request: JoinSessionRequest =
+            serde_json::from_str(r#"{"display_name": "John", "avatar_color": null, "precision": "coarse"}"#).unwrap();
+        assert_eq!(request.precision, LocationPrecision::Coarse);
+        let request: JoinSessionRequest = serde_json::from_str(r#"{"display_name": "John", "avatar_color": null}"#).unwrap();
+        assert_eq!(request.precision, LocationPrecision::Exact);
+    }
+}
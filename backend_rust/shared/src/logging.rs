@@ -0,0 +1,82 @@
+use tracing::Subscriber;
+use tracing_subscriber::{fmt::MakeWriter, registry::LookupSpan, Layer};
+
+use crate::config::AppConfig;
+
+/// Build the log output layer, emitting JSON lines when configured
+pub fn fmt_layer<S, W>(config: &AppConfig, writer: W) -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    if config.is_json_logging() {
+        tracing_subscriber::fmt::layer()
+            .json()
+            .with_current_span(true)
+            .with_span_list(true)
+            .with_writer(writer)
+            .boxed()
+    } else {
+        tracing_subscriber::fmt::layer()
+            .with_target(false)
+            .with_writer(writer)
+            .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use tracing::info;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    /// Writer that captures log output in memory
+    #[derive(Clone, Default)]
+    struct CapturedWriter(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for CapturedWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl<'w> MakeWriter<'w> for CapturedWriter {
+        type Writer = Self;
+
+        fn make_writer(&'w self) -> Self::Writer {
+            self.clone()
+        }
+    }
+
+    #[test]
+    fn test_json_log_format_emits_json_lines() {
+        let mut config = AppConfig::default();
+        config.app.log_format = "json".to_string();
+
+        let writer = CapturedWriter::default();
+        let subscriber = tracing_subscriber::registry().with(fmt_layer(&config, writer.clone()));
+
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("request", request_id = "abc-123");
+            let _guard = span.enter();
+            info!(session_id = "s1", "Created session");
+        });
+
+        let output = String::from_utf8(writer.0.lock().unwrap().clone()).unwrap();
+        let line = output.lines().next().expect("no log line written");
+        let entry: serde_json::Value = serde_json::from_str(line).expect("log line is not JSON");
+
+        assert!(entry["timestamp"].is_string());
+        assert_eq!(entry["level"], "INFO");
+        assert!(entry["target"].is_string());
+        assert_eq!(entry["fields"]["message"], "Created session");
+        assert_eq!(entry["fields"]["session_id"], "s1");
+        assert_eq!(entry["span"]["request_id"], "abc-123");
+    }
+}
@@ -0,0 +1,145 @@
+use socket2::{Domain, Protocol, Socket, Type};
+use std::io;
+use std::net::{IpAddr, SocketAddr, TcpListener};
+use std::str::FromStr;
+
+/// Pending connections queued by a listener before they are accepted
+const LISTEN_BACKLOG: i32 = 1024;
+
+/// Bind a non-blocking TCP listener, ready to hand to an async runtime
+///
+/// Binding the unspecified IPv6 address (`::`) listens dual-stack, accepting
+/// IPv4 clients as well regardless of the platform's `IPV6_V6ONLY` default.
+/// Any other IPv6 address only accepts IPv6.
+pub fn bind_tcp(addr: SocketAddr) -> io::Result<TcpListener> {
+    let socket = Socket::new(Domain::for_address(addr), Type::STREAM, Some(Protocol::TCP))?;
+
+    if addr.is_ipv6() {
+        socket.set_only_v6(!addr.ip().is_unspecified())?;
+    }
+    #[cfg(unix)]
+    socket.set_reuse_address(true)?;
+    socket.set_nonblocking(true)?;
+
+    socket.bind(&addr.into())?;
+    socket.listen(LISTEN_BACKLOG)?;
+
+    Ok(socket.into())
+}
+
+/// A single IP address or a CIDR range such as `10.0.0.0/8`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpRange {
+    network: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpRange {
+    /// Whether `ip` is in the range; IPv4-mapped IPv6 addresses, as seen on
+    /// dual-stack listeners, match their IPv4 form
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.network, ip.to_canonical()) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                prefix_matches(&network.octets(), &ip.octets(), self.prefix_len)
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                prefix_matches(&network.octets(), &ip.octets(), self.prefix_len)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for IpRange {
+    type Err = String;
+
+    fn from_str(range: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid IP address or CIDR range: {}", range);
+        let (ip, prefix_len) = match range.trim().split_once('/') {
+            Some((ip, prefix_len)) => (ip, Some(prefix_len.parse::<u8>().map_err(|_| invalid())?)),
+            None => (range.trim(), None),
+        };
+        let network = ip.parse::<IpAddr>().map_err(|_| invalid())?.to_canonical();
+        let max_len = if network.is_ipv4() { 32 } else { 128 };
+        let prefix_len = prefix_len.unwrap_or(max_len);
+        if prefix_len > max_len {
+            return Err(invalid());
+        }
+
+        Ok(Self { network, prefix_len })
+    }
+}
+
+/// Whether the first `prefix_len` bits of two addresses agree
+fn prefix_matches(network: &[u8], ip: &[u8], prefix_len: u8) -> bool {
+    let full_bytes = usize::from(prefix_len / 8);
+    let rest_bits = prefix_len % 8;
+    if network[..full_bytes] != ip[..full_bytes] {
+        return false;
+    }
+    if rest_bits == 0 {
+        return true;
+    }
+
+    let mask = 0xFFu8 << (8 - rest_bits);
+    network[full_bytes] & mask == ip[full_bytes] & mask
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::{Ipv4Addr, Ipv6Addr, TcpStream};
+
+    /// Connect to the listener over `ip` and check a byte makes it through
+    fn assert_connects(listener: &TcpListener, ip: std::net::IpAddr) {
+        let port = listener.local_addr().unwrap().port();
+        let mut client = TcpStream::connect(SocketAddr::new(ip, port)).unwrap();
+        client.write_all(b"x").unwrap();
+
+        listener.set_nonblocking(false).unwrap();
+        let (mut server, peer) = listener.accept().unwrap();
+        let mut byte = [0u8];
+        server.read_exact(&mut byte).unwrap();
+        assert_eq!(&byte, b"x");
+        // IPv4 clients of a dual-stack listener show up as IPv4-mapped addresses
+        assert_eq!(peer.ip().to_canonical(), ip);
+    }
+
+    #[test]
+    fn test_ip_range() {
+        let range: IpRange = "10.1.0.0/16".parse().unwrap();
+        assert!(range.contains("10.1.200.3".parse().unwrap()));
+        assert!(!range.contains("10.2.0.1".parse().unwrap()));
+        assert!(range.contains("::ffff:10.1.0.9".parse().unwrap()));
+
+        let single: IpRange = "192.168.1.10".parse().unwrap();
+        assert!(single.contains("192.168.1.10".parse().unwrap()));
+        assert!(!single.contains("192.168.1.11".parse().unwrap()));
+
+        let v6: IpRange = "fd00::/12".parse().unwrap();
+        assert!(v6.contains("fd0a::1".parse().unwrap()));
+        assert!(!v6.contains("fe80::1".parse().unwrap()));
+        assert!(!v6.contains("10.1.0.1".parse().unwrap()));
+
+        for invalid in ["10.0.0.0/33", "proxy.internal", "10.0.0.0/x", "::1/129"] {
+            assert!(invalid.parse::<IpRange>().is_err(), "{} was accepted", invalid);
+        }
+    }
+
+    #[test]
+    fn test_bind_ipv6_loopback() {
+        let listener = bind_tcp("[::1]:0".parse().unwrap()).unwrap();
+        assert!(listener.local_addr().unwrap().is_ipv6());
+
+        assert_connects(&listener, Ipv6Addr::LOCALHOST.into());
+    }
+
+    #[test]
+    fn test_bind_dual_stack() {
+        let listener = bind_tcp("[::]:0".parse().unwrap()).unwrap();
+
+        assert_connects(&listener, Ipv6Addr::LOCALHOST.into());
+        assert_connects(&listener, Ipv4Addr::LOCALHOST.into());
+    }
+}
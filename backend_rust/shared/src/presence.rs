@@ -0,0 +1,74 @@
+use redis::aio::ConnectionLike;
+use tracing::{debug, error};
+use uuid::Uuid;
+
+use crate::error::AppResult;
+use crate::types::{Constants, ParticipantLeftData, RedisKeys, WebSocketMessage};
+
+/// Announce a participant's departure to their session, once per departure
+///
+/// Leaving through the API, being removed by the creator and the WebSocket
+/// disconnect cleanup can all report the same departure, possibly from
+/// different instances, so a short-lived Redis tombstone (`SET NX EX`) lets
+/// only the first through. If the tombstone can't be written the departure is
+/// announced anyway; a duplicate is preferable to peers never learning of it.
+///
+/// The event is published to the session's channel, from which every
+/// WebSocket server instance relays it to its connections. Returns whether
+/// this call announced it.
+pub async fn announce_participant_left<C>(
+    conn: &mut C,
+    keys: &RedisKeys,
+    session_id: &Uuid,
+    user_id: &str,
+) -> AppResult<bool>
+where
+    C: ConnectionLike + Send,
+{
+    let marked: redis::RedisResult<Option<String>> = redis::cmd("SET")
+        .arg(keys.participant_left(session_id, user_id))
+        .arg(1)
+        .arg("NX")
+        .arg("EX")
+        .arg(Constants::PARTICIPANT_LEFT_TOMBSTONE_SECONDS)
+        .query_async(conn)
+        .await;
+    match marked {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            debug!("Departure of {} from session {} already announced", user_id, session_id);
+            return Ok(false);
+        }
+        Err(e) => error!("Failed to record departure tombstone for {}: {}", user_id, e),
+    }
+
+    let message = serde_json::to_string(&WebSocketMessage::ParticipantLeft(ParticipantLeftData {
+        user_id: user_id.to_string(),
+    }))?;
+    redis::cmd("PUBLISH")
+        .arg(keys.session_channel(session_id))
+        .arg(message)
+        .query_async::<_, ()>(conn)
+        .await?;
+
+    debug!("Announced participant {} leaving session {}", user_id, session_id);
+    Ok(true)
+}
+
+/// Clear a participant's departure tombstone, as a (re)join starts a new
+/// stay whose departure must be announced again
+pub async fn clear_participant_left<C>(
+    conn: &mut C,
+    keys: &RedisKeys,
+    session_id: &Uuid,
+    user_id: &str,
+) -> AppResult<()>
+where
+    C: ConnectionLike + Send,
+{
+    redis::cmd("DEL")
+        .arg(keys.participant_left(session_id, user_id))
+        .query_async::<_, ()>(conn)
+        .await?;
+    Ok(())
+}
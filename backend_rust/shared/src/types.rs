@@ -2,6 +2,8 @@ use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
 
+use crate::error::FieldError;
+
 /// Session model representing a location sharing session
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct Session {
@@ -12,6 +14,27 @@ pub struct Session {
     pub creator_id: Uuid,
     pub is_active: bool,
     pub last_activity: DateTime<Utc>,
+    /// Opaque token in the session's join link, rotated if the link leaks
+    #[serde(skip_serializing)]
+    pub join_token: Uuid,
+    /// Avatar colors for this session, or `None` for the server's palette
+    pub avatar_palette: Option<Vec<String>>,
+    /// Whether the session is listed in the public session directory
+    pub is_public: bool,
+    /// Lowercase categories the session can be filtered by
+    pub tags: Vec<String>,
+    /// Instructions shown to each participant on joining
+    pub welcome_message: Option<String>,
+    /// Whether only publishers, rather than every participant, may share locations
+    pub view_only: bool,
+    /// Whether joiners of a full session queue for a slot rather than being turned away
+    pub waitlist_enabled: bool,
+    /// Whether location broadcasts are followed by waypoints filling in each hop
+    pub interpolate_locations: bool,
+    /// Minutes after creation that new participants can join, or `None` until the session ends
+    pub join_window_minutes: Option<i64>,
+    /// Client-defined JSON object, such as a theme or icon URL
+    pub metadata: serde_json::Value,
 }
 
 /// Participant model representing a user in a session
@@ -25,6 +48,34 @@ pub struct Participant {
     pub joined_at: DateTime<Utc>,
     pub last_seen: DateTime<Utc>,
     pub is_active: bool,
+    pub location_precision: LocationPrecision,
+    pub role: ParticipantRole,
+}
+
+/// How precisely a participant's location is shared with peers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "varchar", rename_all = "lowercase")]
+pub enum LocationPrecision {
+    /// Coordinates are shared as reported
+    #[default]
+    Exact,
+    /// Coordinates are snapped to a grid of roughly 100 meters
+    Coarse,
+    /// Coordinates are snapped to a grid of roughly 10 kilometers
+    City,
+}
+
+/// Whether a participant may share their location or only watch others'
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "varchar", rename_all = "lowercase")]
+pub enum ParticipantRole {
+    /// Sends location updates as well as receiving them
+    #[default]
+    Publisher,
+    /// Only receives other participants' locations
+    Viewer,
 }
 
 /// Location data for real-time tracking
@@ -36,35 +87,208 @@ pub struct Location {
     pub timestamp: DateTime<Utc>,
 }
 
+/// Kind of session lifecycle event recorded in the audit log
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "varchar", rename_all = "snake_case")]
+pub enum AuditEventType {
+    SessionCreated,
+    ParticipantJoined,
+    ParticipantLeft,
+    SessionEnded,
+}
+
+/// Audit log entry for a session lifecycle event
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct AuditEvent {
+    pub id: Uuid,
+    pub event_type: AuditEventType,
+    pub session_id: Uuid,
+    /// Creator or user ID of whoever caused the event
+    pub actor: Option<String>,
+    pub metadata: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+}
+
 /// Request DTOs for API endpoints
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct CreateSessionRequest {
     pub name: Option<String>,
-    #[serde(default = "default_expires_in_minutes")]
-    pub expires_in_minutes: i64,
-}
-
-fn default_expires_in_minutes() -> i64 {
-    1440 // 24 hours
+    /// Creator ID returned by a previous create, so repeat creators are
+    /// counted against the per-creator session limit
+    #[serde(default, alias = "creatorId")]
+    pub creator_id: Option<Uuid>,
+    /// Minutes until the session expires; omitted uses the default duration,
+    /// capped at the configured maximum
+    #[serde(default, alias = "expiresInMinutes")]
+    pub expires_in_minutes: Option<i64>,
+    /// Avatar colors for the session's participants, instead of the
+    /// server's palette
+    #[serde(default, alias = "avatarPalette")]
+    pub avatar_palette: Option<Vec<String>>,
+    /// List the session, with its join link, in the public session directory
+    #[serde(default, alias = "isPublic")]
+    pub is_public: bool,
+    /// Categories such as "hiking", stored lowercased
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Instructions such as "Meet at the north gate", shown to each participant on joining
+    #[serde(default, alias = "welcomeMessage")]
+    pub welcome_message: Option<String>,
+    /// Make joiners viewers unless the creator lets them publish
+    #[serde(default, alias = "viewOnly")]
+    pub view_only: bool,
+    /// Queue joiners once the session is full instead of rejecting them
+    #[serde(default, alias = "waitlistEnabled")]
+    pub waitlist_enabled: bool,
+    /// Send interpolated waypoints between each participant's fixes, for smooth map animation
+    #[serde(default, alias = "interpolateLocations")]
+    pub interpolate_locations: bool,
+    /// Only admit new participants this many minutes after creation;
+    /// participants who already joined can still reconnect
+    #[serde(default, alias = "joinWindowMinutes")]
+    pub join_window_minutes: Option<i64>,
+    /// Client-defined JSON object, such as a theme or icon URL
+    #[serde(default)]
+    pub metadata: Option<serde_json::Value>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct JoinSessionRequest {
+    #[serde(alias = "displayName")]
     pub display_name: String,
+    #[serde(alias = "avatarColor")]
     pub avatar_color: Option<String>,
+    #[serde(default)]
+    pub precision: LocationPrecision,
+    /// Stable client identifier; rejoining with the same one keeps the same user ID
+    #[serde(alias = "deviceId")]
+    pub device_id: Option<String>,
+    /// Role to join with; publishing in a view-only session takes the creator's ID
+    #[serde(default)]
+    pub role: Option<ParticipantRole>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RefreshTokenRequest {
+    pub token: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RemoveParticipantsRequest {
+    pub user_ids: Vec<String>,
+}
+
+/// One participant's position in a bulk location ingestion
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IngestLocationEntry {
+    pub user_id: String,
+    pub lat: f64,
+    pub lng: f64,
+    pub accuracy: f64,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Positions pushed by a server-side publisher, sent as a bare JSON array
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct IngestLocationsRequest {
+    pub locations: Vec<IngestLocationEntry>,
+}
+
+/// Change a running session's settings; omitted fields are left as they are
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct UpdateSessionRequest {
+    /// Replaces the session's tags
+    #[serde(default)]
+    pub tags: Option<Vec<String>>,
+    /// Merged into the session's metadata; keys set to null are removed
+    #[serde(default)]
+    pub metadata: Option<serde_json::Value>,
+}
+
+/// Hand a session over to one of its active participants
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TransferOwnershipRequest {
+    /// User ID of the participant taking over
+    pub new_creator_id: Uuid,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ParticipantStatsQuery {
+    pub dest_lat: Option<f64>,
+    pub dest_lng: Option<f64>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ListParticipantsQuery {
+    /// Also list participants who have left the session
+    #[serde(default)]
+    pub include_inactive: bool,
+}
+
+/// Order of the admin session list
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionSort {
+    /// Most recently active first
+    #[default]
+    LastActivityDesc,
+    LastActivityAsc,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AdminSessionsQuery {
+    /// Only list sessions that are active and unexpired
+    #[serde(default)]
+    pub active_only: bool,
+    #[serde(default)]
+    pub sort: SessionSort,
+    /// Only list sessions carrying this tag
+    #[serde(default)]
+    pub tag: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PublicSessionsQuery {
+    /// Only list sessions carrying this tag
+    #[serde(default)]
+    pub tag: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SessionQrCodeQuery {
+    /// Smallest width and height of the PNG, in pixels
+    #[serde(default)]
+    pub size: Option<u32>,
 }
 
 /// Response DTOs for API endpoints
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateSessionResponse {
     pub session_id: Uuid,
+    pub creator_id: Uuid,
     pub join_link: String,
     pub expires_at: DateTime<Utc>,
     pub name: Option<String>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RotateJoinLinkResponse {
+    pub join_link: String,
+}
+
+/// What a session's QR code carries, for apps rendering it themselves
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SessionQrCodeResponse {
+    /// The join token, for apps that scan the code and join directly
+    pub payload: String,
+    /// The join link encoded in the QR code
+    pub url: String,
+}
+
 #[derive(Debug, Serialize)]
 pub struct SessionDetailsResponse {
     pub id: Uuid,
@@ -73,15 +297,76 @@ pub struct SessionDetailsResponse {
     pub expires_at: DateTime<Utc>,
     pub participant_count: i64,
     pub is_active: bool,
+    pub last_activity: DateTime<Utc>,
+    pub tags: Vec<String>,
+    pub welcome_message: Option<String>,
+    pub view_only: bool,
+    pub waitlist_enabled: bool,
+    pub interpolate_locations: bool,
+    pub join_window_minutes: Option<i64>,
+    pub metadata: serde_json::Value,
+}
+
+/// A session's activity, as listed for monitoring dashboards
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+pub struct AdminSessionSummary {
+    pub id: Uuid,
+    pub name: Option<String>,
+    pub participant_count: i64,
+    pub last_activity: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub is_active: bool,
+    pub tags: Vec<String>,
+}
+
+/// An open session as listed in the public directory
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PublicSessionSummary {
+    pub id: Uuid,
+    pub name: Option<String>,
+    pub participant_count: i64,
+    pub expires_at: DateTime<Utc>,
+    pub join_link: String,
+    pub tags: Vec<String>,
 }
 
 #[derive(Debug, Serialize)]
 pub struct JoinSessionResponse {
+    pub session_id: Uuid,
     pub user_id: Uuid,
+    pub role: ParticipantRole,
     pub websocket_token: String,
     pub websocket_url: String,
 }
 
+/// Returned instead of a join when a full session queues the joiner
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JoinQueuedResponse {
+    pub session_id: Uuid,
+    /// Secret for checking the queue position and collecting the join once promoted
+    pub ticket: Uuid,
+    /// 1 for the head of the waitlist
+    pub position: usize,
+}
+
+/// A joiner waiting for a slot in a full session, as stored in Redis
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WaitlistEntry {
+    pub user_id: String,
+    pub role: ParticipantRole,
+    pub request: JoinSessionRequest,
+    /// Why the joiner couldn't be admitted once their turn came, reported
+    /// to them on their ticket
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failure: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RefreshTokenResponse {
+    pub websocket_token: String,
+    pub expires_at: DateTime<Utc>,
+}
+
 #[derive(Debug, Serialize, sqlx::FromRow)]
 pub struct ParticipantResponse {
     pub user_id: String,
@@ -89,11 +374,69 @@ pub struct ParticipantResponse {
     pub avatar_color: String,
     pub last_seen: DateTime<Utc>,
     pub is_active: bool,
+    pub role: ParticipantRole,
 }
 
-#[derive(Debug, Serialize)]
-pub struct ParticipantsListResponse {
-    pub participants: Vec<ParticipantResponse>,
+/// Envelope returned by every list endpoint
+///
+/// `total` counts every matching item when known; `limit` and `offset`
+/// echo the requested page and are null when the whole list is returned.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Paginated<T> {
+    pub items: Vec<T>,
+    pub total: Option<i64>,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+}
+
+impl<T> Paginated<T> {
+    /// A complete list, returned as a single page
+    pub fn all(items: Vec<T>) -> Self {
+        Self {
+            total: Some(items.len() as i64),
+            items,
+            limit: None,
+            offset: None,
+        }
+    }
+}
+
+/// A participant's current location along with their profile
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SessionLocationResponse {
+    #[serde(flatten)]
+    pub location: LocationBroadcastData,
+    pub display_name: String,
+    pub avatar_color: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SessionLocationsResponse {
+    pub locations: Vec<SessionLocationResponse>,
+}
+
+/// Where a session's participants are gathered, from their current locations
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SessionCentroidResponse {
+    pub lat: f64,
+    pub lng: f64,
+    pub location_count: usize,
+    /// Mean distance of the locations from the centroid
+    pub average_distance_meters: f64,
+    /// Distance of the farthest location from the centroid
+    pub max_distance_meters: f64,
+}
+
+/// Movement summary for a participant, from their location history
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ParticipantStatsResponse {
+    pub user_id: String,
+    pub point_count: usize,
+    pub distance_meters: f64,
+    pub average_speed_mps: f64,
+    pub max_speed_mps: f64,
+    /// Seconds to the requested destination at the recent average speed
+    pub eta_seconds: Option<f64>,
 }
 
 #[derive(Debug, Serialize)]
@@ -101,6 +444,36 @@ pub struct SuccessResponse {
     pub success: bool,
 }
 
+/// Outcome of removing one participant in a bulk removal
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RemovedParticipantResult {
+    pub user_id: String,
+    pub removed: bool,
+    /// Error code when the participant couldn't be removed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RemoveParticipantsResponse {
+    pub results: Vec<RemovedParticipantResult>,
+}
+
+/// Outcome of one entry in a bulk location ingestion, in request order
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IngestedLocationResult {
+    pub user_id: String,
+    pub accepted: bool,
+    /// Error code when the location wasn't accepted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IngestLocationsResponse {
+    pub results: Vec<IngestedLocationResult>,
+}
+
 /// WebSocket message types
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -110,16 +483,72 @@ pub enum WebSocketMessage {
     LocationUpdate(LocationUpdateData),
     #[serde(rename = "ping")]
     Ping,
+    #[serde(rename = "request_location")]
+    RequestLocation(RequestLocationData),
+    #[serde(rename = "status_update")]
+    StatusUpdate(StatusUpdateData),
+    #[serde(rename = "set_receive")]
+    SetReceive(SetReceiveData),
+    #[serde(rename = "direct_message")]
+    DirectMessage(DirectMessageData),
+    #[serde(rename = "whoami")]
+    WhoAmI,
+    #[serde(rename = "get_session_info")]
+    GetSessionInfo,
+    #[serde(rename = "request_distance")]
+    RequestDistance(RequestDistanceData),
+    #[serde(rename = "follow_request")]
+    FollowRequest(FollowRequestData),
+    #[serde(rename = "unfollow")]
+    Unfollow,
+    #[serde(rename = "set_waypoint")]
+    SetWaypoint(SetWaypointData),
+    #[serde(rename = "remove_waypoint")]
+    RemoveWaypoint,
+    #[serde(rename = "hello")]
+    Hello(HelloData),
     #[serde(rename = "participant_joined")]
     ParticipantJoined(ParticipantJoinedData),
     #[serde(rename = "participant_left")]
     ParticipantLeft(ParticipantLeftData),
+    #[serde(rename = "participant_count")]
+    ParticipantCount(ParticipantCountData),
     #[serde(rename = "location_broadcast")]
     LocationBroadcast(LocationBroadcastData),
+    #[serde(rename = "location_path")]
+    LocationPath(LocationPathData),
+    #[serde(rename = "status_broadcast")]
+    StatusBroadcast(StatusBroadcastData),
+    #[serde(rename = "session_expires_soon")]
+    SessionExpiresSoon(SessionExpiresSoonData),
     #[serde(rename = "session_ended")]
     SessionEnded(SessionEndedData),
+    #[serde(rename = "ownership_transferred")]
+    OwnershipTransferred(OwnershipTransferredData),
+    #[serde(rename = "direct_message_received")]
+    DirectMessageReceived(DirectMessageReceivedData),
+    #[serde(rename = "connection_info")]
+    ConnectionInfo(ConnectionInfoData),
+    #[serde(rename = "distance_result")]
+    DistanceResult(DistanceResultData),
+    #[serde(rename = "follow_notice")]
+    FollowNotice(FollowNoticeData),
+    #[serde(rename = "waypoint_broadcast")]
+    WaypointBroadcast(WaypointBroadcastData),
+    #[serde(rename = "waypoint_removed")]
+    WaypointRemoved(WaypointRemovedData),
+    #[serde(rename = "session_info")]
+    SessionInfo(SessionInfoData),
+    #[serde(rename = "location_ack")]
+    LocationAck(LocationAckData),
     #[serde(rename = "pong")]
     Pong,
+    #[serde(rename = "ping_probe")]
+    PingProbe(ProbeData),
+    #[serde(rename = "pong_probe")]
+    PongProbe(ProbeData),
+    #[serde(rename = "welcome")]
+    Welcome(WelcomeData),
     #[serde(rename = "error")]
     Error(ErrorData),
 }
@@ -130,6 +559,64 @@ pub struct LocationUpdateData {
     pub lng: f64,
     pub accuracy: f64,
     pub timestamp: DateTime<Utc>,
+    /// Client sequence number; when set, the stored update is acknowledged
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seq: Option<u64>,
+}
+
+/// How server messages are framed on a WebSocket connection
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FrameEncoding {
+    /// JSON in text frames
+    #[default]
+    Json,
+    /// MessagePack in binary frames, with the same structure as the JSON
+    Msgpack,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HelloData {
+    pub client_version: String,
+    pub protocol_version: u32,
+    /// Encoding the client wants server messages in
+    #[serde(default)]
+    pub encoding: FrameEncoding,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WelcomeData {
+    pub server_version: String,
+    pub accepted_protocol: u32,
+    pub features: Vec<String>,
+    /// Encoding used for every server message from the welcome on
+    #[serde(default)]
+    pub encoding: FrameEncoding,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestLocationData {
+    pub user_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatusUpdateData {
+    pub status: String,
+    #[serde(default)]
+    pub emoji: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetReceiveData {
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatusBroadcastData {
+    pub user_id: String,
+    pub status: String,
+    #[serde(default)]
+    pub emoji: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -144,6 +631,11 @@ pub struct ParticipantLeftData {
     pub user_id: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParticipantCountData {
+    pub count: usize,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LocationBroadcastData {
     pub user_id: String,
@@ -153,9 +645,139 @@ pub struct LocationBroadcastData {
     pub timestamp: DateTime<Utc>,
 }
 
+/// Waypoints leading up to a participant's latest location, in sessions
+/// that interpolate locations; the location itself follows as a broadcast
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocationPathData {
+    pub user_id: String,
+    pub points: Vec<LocationBroadcastData>,
+}
+
+/// Confirms a numbered location update was stored
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocationAckData {
+    pub seq: u64,
+}
+
+/// The session a participant joined, sent once they are caught up on peers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionInfoData {
+    pub name: Option<String>,
+    /// The organizer's instructions, omitted when the session has none
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub welcome_message: Option<String>,
+    pub expires_at: DateTime<Utc>,
+    /// Whether the session is listed in the public session directory
+    #[serde(default)]
+    pub is_public: bool,
+    /// Participants currently in the session, in replies to `get_session_info`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub participant_count: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionExpiresSoonData {
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OwnershipTransferredData {
+    /// User ID of the participant who now owns the session
+    pub new_creator_id: Uuid,
+}
+
+/// Opaque payload for a single peer in the sender's session, e.g. call signaling
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirectMessageData {
+    pub to_user_id: String,
+    pub payload: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirectMessageReceivedData {
+    pub from_user_id: String,
+    pub payload: serde_json::Value,
+}
+
+/// Who the server thinks a connection belongs to, in reply to `whoami`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConnectionInfoData {
+    pub user_id: String,
+    pub session_id: Uuid,
+    pub connected_since: DateTime<Utc>,
+    /// Rolling average round-trip time, once a probe has been answered
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rtt_ms: Option<f64>,
+    /// Probes that went unanswered or were answered too late
+    #[serde(default)]
+    pub probe_timeouts: u64,
+}
+
+/// A round-trip time probe, sent by the server and echoed back by the client
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProbeData {
+    pub nonce: u64,
+    pub sent_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestDistanceData {
+    pub to_user_id: String,
+}
+
+/// How far away a peer is from the requester, by latest locations
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DistanceResultData {
+    pub to_user_id: String,
+    pub meters: f64,
+    /// Degrees clockwise from north, from the requester toward the peer
+    pub bearing: f64,
+}
+
+/// Start following a peer, e.g. a dispatcher watching a field worker
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FollowRequestData {
+    pub target_user_id: String,
+}
+
+/// Tells a participant someone started or stopped following them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FollowNoticeData {
+    pub follower_user_id: String,
+    /// `false` once the follower unfollows or disconnects
+    pub following: bool,
+    /// How many users now follow the participant
+    pub follower_count: usize,
+}
+
+/// Drop a pin for the whole session, e.g. "rally here"
+///
+/// Each participant has at most one waypoint; setting another replaces it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetWaypointData {
+    pub lat: f64,
+    pub lng: f64,
+    pub label: String,
+}
+
+/// A participant's waypoint, shared with the session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WaypointBroadcastData {
+    pub user_id: String,
+    pub lat: f64,
+    pub lng: f64,
+    pub label: String,
+}
+
+/// Tells the session a participant removed their waypoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WaypointRemovedData {
+    pub user_id: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SessionEndedData {
-    pub reason: String, // "expired" or "ended_by_creator"
+    pub reason: String, // "expired", "inactive" or "ended_by_creator"
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -171,35 +793,152 @@ pub struct JwtClaims {
     pub session_id: Uuid, // session UUID
     pub exp: i64,         // expiration timestamp
     pub iat: i64,         // issued at timestamp
+    #[serde(default)]
+    pub precision: LocationPrecision,
+    #[serde(default)]
+    pub role: ParticipantRole,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iss: Option<String>, // issuing service
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aud: Option<String>, // intended recipient
 }
 
 /// Redis key builders for consistent key naming
-pub struct RedisKeys;
+///
+/// Every key and channel starts with the configured prefix, so deployments
+/// sharing a Redis instance don't collide.
+#[derive(Debug, Clone, Default)]
+pub struct RedisKeys {
+    prefix: String,
+}
 
 impl RedisKeys {
+    /// Key builders under `prefix` (e.g. "myapp" gives `myapp:locations:...`);
+    /// an empty prefix leaves keys unprefixed
+    pub fn new(prefix: &str) -> Self {
+        let prefix = match prefix.trim() {
+            "" => String::new(),
+            prefix if prefix.ends_with(':') => prefix.to_string(),
+            prefix => format!("{}:", prefix),
+        };
+        Self { prefix }
+    }
+    
+    /// Glob matching keys under the prefix, e.g. `pattern("locations:*")`
+    pub fn pattern(&self, pattern: &str) -> String {
+        format!("{}{}", self.prefix, pattern)
+    }
+    
     /// Key for storing location data: locations:{session_id}:{user_id}
-    pub fn location(session_id: &Uuid, user_id: &str) -> String {
-        format!("locations:{}:{}", session_id, user_id)
+    pub fn location(&self, session_id: &Uuid, user_id: &str) -> String {
+        format!("{}locations:{}:{}", self.prefix, session_id, user_id)
+    }
+    
+    /// List of a participant's recent locations, oldest first: location_history:{session_id}:{user_id}
+    pub fn location_history(&self, session_id: &Uuid, user_id: &str) -> String {
+        format!("{}location_history:{}:{}", self.prefix, session_id, user_id)
+    }
+    
+    /// Key for storing a participant's status: status:{session_id}:{user_id}
+    pub fn status(&self, session_id: &Uuid, user_id: &str) -> String {
+        format!("{}status:{}:{}", self.prefix, session_id, user_id)
     }
     
     /// Key for storing active session participants: session_participants:{session_id}
-    pub fn session_participants(session_id: &Uuid) -> String {
-        format!("session_participants:{}", session_id)
+    pub fn session_participants(&self, session_id: &Uuid) -> String {
+        format!("{}session_participants:{}", self.prefix, session_id)
     }
     
     /// Key for WebSocket connection mapping: connections:{user_id}
-    pub fn connection(user_id: &str) -> String {
-        format!("connections:{}", user_id)
+    pub fn connection(&self, user_id: &str) -> String {
+        format!("{}connections:{}", self.prefix, user_id)
     }
     
     /// Key for session activity tracking: session_activity:{session_id}
-    pub fn session_activity(session_id: &Uuid) -> String {
-        format!("session_activity:{}", session_id)
+    pub fn session_activity(&self, session_id: &Uuid) -> String {
+        format!("{}session_activity:{}", self.prefix, session_id)
     }
     
     /// Channel for pub/sub messaging: channel:session:{session_id}
-    pub fn session_channel(session_id: &Uuid) -> String {
-        format!("channel:session:{}", session_id)
+    pub fn session_channel(&self, session_id: &Uuid) -> String {
+        format!("{}channel:session:{}", self.prefix, session_id)
+    }
+    
+    /// Session info cached for WebSocket clients, expiring with the session: session_info:{session_id}
+    pub fn session_info(&self, session_id: &Uuid) -> String {
+        format!("{}session_info:{}", self.prefix, session_id)
+    }
+    
+    /// Marker that a session's expiry warning was sent: session_expiry_warned:{session_id}
+    pub fn session_expiry_warned(&self, session_id: &Uuid) -> String {
+        format!("{}session_expiry_warned:{}", self.prefix, session_id)
+    }
+    
+    /// Sorted set of a client's recent request times: rate_limit:{client_ip}
+    pub fn rate_limit(&self, client_ip: &str) -> String {
+        format!("{}rate_limit:{}", self.prefix, client_ip)
+    }
+    
+    /// Request fingerprint and created session for a session creation's
+    /// Idempotency-Key: idempotency:sessions:{key}
+    pub fn session_idempotency(&self, idempotency_key: &str) -> String {
+        format!("{}idempotency:sessions:{}", self.prefix, idempotency_key)
+    }
+    
+    /// Key for a full session's queue of waitlist tickets, head first: waitlist:{session_id}
+    pub fn session_waitlist(&self, session_id: &Uuid) -> String {
+        format!("{}waitlist:{}", self.prefix, session_id)
+    }
+    
+    /// Key for waitlist entries by ticket, kept after promotion: waitlist_entries:{session_id}
+    pub fn session_waitlist_entries(&self, session_id: &Uuid) -> String {
+        format!("{}waitlist_entries:{}", self.prefix, session_id)
+    }
+    
+    /// Tombstone marking a participant's departure as announced: participant_left:{session_id}:{user_id}
+    pub fn participant_left(&self, session_id: &Uuid, user_id: &str) -> String {
+        format!("{}participant_left:{}:{}", self.prefix, session_id, user_id)
+    }
+    
+    /// Hash of a session's waypoints by the user who set them: waypoints:{session_id}
+    pub fn session_waypoints(&self, session_id: &Uuid) -> String {
+        format!("{}waypoints:{}", self.prefix, session_id)
+    }
+    
+    /// Users following a participant: followers:{session_id}:{user_id}
+    pub fn followers(&self, session_id: &Uuid, user_id: &str) -> String {
+        format!("{}followers:{}:{}", self.prefix, session_id, user_id)
+    }
+    
+    /// The participant a user is following: following:{session_id}:{user_id}
+    pub fn following(&self, session_id: &Uuid, user_id: &str) -> String {
+        format!("{}following:{}:{}", self.prefix, session_id, user_id)
+    }
+    
+    /// Pattern matching every session channel: channel:session:*
+    pub fn session_channel_pattern(&self) -> String {
+        self.pattern("channel:session:*")
+    }
+    
+    /// Channel for messages to one participant: channel:session:{session_id}:direct:{user_id}
+    ///
+    /// Matches [`RedisKeys::session_channel_pattern`], so instances receive it
+    /// on their existing subscription.
+    pub fn direct_channel(&self, session_id: &Uuid, user_id: &str) -> String {
+        format!("{}channel:session:{}:direct:{}", self.prefix, session_id, user_id)
+    }
+    
+    /// Session and user ID of a channel built by [`RedisKeys::direct_channel`]
+    pub fn direct_target_from_channel(&self, channel: &str) -> Option<(Uuid, String)> {
+        let target = channel.strip_prefix(&self.prefix)?.strip_prefix("channel:session:")?;
+        let (session_id, user_id) = target.split_once(":direct:")?;
+        Some((Uuid::parse_str(session_id).ok()?, user_id.to_string()))
+    }
+    
+    /// Session ID of a channel built by [`RedisKeys::session_channel`]
+    pub fn session_id_from_channel(&self, channel: &str) -> Option<Uuid> {
+        let session_id = channel.strip_prefix(&self.prefix)?.strip_prefix("channel:session:")?;
+        Uuid::parse_str(session_id).ok()
     }
 }
 
@@ -213,15 +952,93 @@ impl Constants {
     /// Location data TTL in Redis (30 seconds)
     pub const LOCATION_TTL_SECONDS: usize = 30;
     
+    /// Most locations accepted in one bulk ingestion request
+    pub const MAX_LOCATION_BATCH_SIZE: usize = 500;
+    
+    /// Most locations kept in a participant's history, unless configured
+    pub const LOCATION_HISTORY_MAX_POINTS: usize = 1000;
+    
+    /// Location history TTL in Redis, refreshed on each update, unless
+    /// configured (24 hours)
+    pub const LOCATION_HISTORY_TTL_SECONDS: u64 = 86400;
+    
+    /// How far back movement is averaged for an ETA (5 minutes)
+    pub const ETA_SPEED_WINDOW_SECONDS: i64 = 300;
+    
     /// Default session duration (24 hours)
     pub const DEFAULT_SESSION_DURATION_MINUTES: i64 = 1440;
     
-    /// Session auto-expire duration (1 hour of inactivity)
-    pub const SESSION_AUTO_EXPIRE_MINUTES: i64 = 60;
+    /// How long a participant-left tombstone suppresses duplicate notifications (30 seconds)
+    pub const PARTICIPANT_LEFT_TOMBSTONE_SECONDS: u64 = 30;
+    
+    /// Maximum length of a client-provided device ID
+    pub const MAX_DEVICE_ID_LENGTH: usize = 128;
+    
+    /// Maximum length of an `Idempotency-Key` header
+    pub const MAX_IDEMPOTENCY_KEY_LENGTH: usize = 255;
+    
+    /// How long a created session is replayed for a repeated idempotency key (24 hours)
+    pub const IDEMPOTENCY_KEY_TTL_SECONDS: u64 = 86400;
+    
+    /// How long an idempotency key stays reserved for a creation in progress,
+    /// should the server handling it die before finishing (1 minute)
+    pub const IDEMPOTENCY_RESERVATION_SECONDS: u64 = 60;
+    
+    /// Largest session metadata object, in bytes of JSON
+    pub const MAX_SESSION_METADATA_BYTES: usize = 4096;
+    
+    /// Maximum length of a session welcome message, in characters
+    pub const MAX_WELCOME_MESSAGE_LENGTH: usize = 500;
+    
+    /// Maximum length of a participant status, in characters
+    pub const MAX_STATUS_LENGTH: usize = 140;
+    
+    /// Maximum length of a status emoji, in characters (allows multi-codepoint emoji)
+    pub const MAX_STATUS_EMOJI_LENGTH: usize = 16;
+    
+    /// Status data TTL in Redis (1 hour)
+    pub const STATUS_TTL_SECONDS: u64 = 3600;
+    
+    /// Maximum length of a waypoint label, in characters
+    pub const MAX_WAYPOINT_LABEL_LENGTH: usize = 100;
+    
+    /// Session waypoints TTL in Redis, refreshed when one is set (24 hours)
+    pub const WAYPOINT_TTL_SECONDS: u64 = 86400;
+    
+    /// Follow relationship TTL in Redis, as long as a WebSocket token lasts
+    /// (24 hours); cleared sooner on unfollow or disconnect
+    pub const FOLLOW_TTL_SECONDS: u64 = 86400;
     
     /// WebSocket JWT token duration (24 hours)
     pub const WS_TOKEN_DURATION_HOURS: i64 = 24;
     
+    /// How long after expiry a WebSocket token can still be refreshed (1 hour)
+    pub const WS_TOKEN_REFRESH_GRACE_MINUTES: i64 = 60;
+    
+    /// WebSocket protocol versions the server can speak
+    pub const SUPPORTED_PROTOCOL_VERSIONS: &'static [u32] = &[1];
+    
+    /// Optional WebSocket features advertised in the welcome message
+    pub const WEBSOCKET_FEATURES: &'static [&'static str] = &["request_location", "status", "set_receive", "direct_message", "msgpack", "whoami", "distance", "follow", "session_info", "location_ack", "location_path", "rtt_probe", "waypoint", "get_session_info"];
+    
+    /// Most colors an avatar palette can hold
+    pub const MAX_AVATAR_PALETTE_SIZE: usize = 64;
+    
+    /// Most tags a session can carry
+    pub const MAX_SESSION_TAGS: usize = 10;
+    
+    /// Maximum length of a session tag, in characters
+    pub const MAX_SESSION_TAG_LENGTH: usize = 30;
+    
+    /// Default width and height of a session QR code PNG, in pixels
+    pub const QR_CODE_DEFAULT_SIZE: u32 = 256;
+    
+    /// Smallest QR code PNG that can be requested, in pixels
+    pub const QR_CODE_MIN_SIZE: u32 = 64;
+    
+    /// Largest QR code PNG that can be requested, in pixels
+    pub const QR_CODE_MAX_SIZE: u32 = 2048;
+    
     /// Default avatar colors for participants
     pub const DEFAULT_AVATAR_COLORS: &'static [&'static str] = &[
         "#FF5733", "#33FF57", "#3357FF", "#FF33F5", "#F5FF33",
@@ -231,45 +1048,250 @@ impl Constants {
 
 /// Validation helpers
 impl CreateSessionRequest {
-    pub fn validate(&self) -> Result<(), String> {
+    /// Validate the request against the configured longest session duration,
+    /// reporting every invalid field
+    pub fn validate(&self, max_duration_minutes: i64) -> Result<(), Vec<FieldError>> {
+        let mut errors = Vec::new();
+        
         if let Some(name) = &self.name {
             if name.trim().is_empty() {
-                return Err("Session name cannot be empty".to_string());
+                errors.push(FieldError::new("name", "Session name cannot be empty"));
+            } else if name.len() > 255 {
+                errors.push(FieldError::new("name", "Session name cannot exceed 255 characters"));
+            }
+        }
+        
+        if let Some(minutes) = self.expires_in_minutes {
+            if minutes <= 0 {
+                errors.push(FieldError::new("expires_in_minutes", "Session duration must be positive"));
+            } else if minutes > max_duration_minutes {
+                errors.push(FieldError::new(
+                    "expires_in_minutes",
+                    &format!("Session duration cannot exceed {} minutes", max_duration_minutes),
+                ));
+            }
+        }
+        
+        if let Some(minutes) = self.join_window_minutes {
+            if minutes <= 0 {
+                errors.push(FieldError::new("join_window_minutes", "Join window must be positive"));
             }
-            if name.len() > 255 {
-                return Err("Session name cannot exceed 255 characters".to_string());
+        }
+        
+        if let Some(palette) = &self.avatar_palette {
+            if let Err(message) = crate::utils::validate_avatar_palette(palette) {
+                errors.push(FieldError::new("avatar_palette", &message));
             }
         }
         
-        if self.expires_in_minutes <= 0 {
-            return Err("Session duration must be positive".to_string());
+        if let Err(message) = crate::utils::validate_session_tags(&self.tags) {
+            errors.push(FieldError::new("tags", &message));
         }
         
-        if self.expires_in_minutes > 10080 { // 7 days
-            return Err("Session duration cannot exceed 7 days".to_string());
+        if let Some(metadata) = &self.metadata {
+            if let Err(message) = crate::utils::validate_session_metadata(metadata) {
+                errors.push(FieldError::new("metadata", &message));
+            }
         }
         
-        Ok(())
+        if let Some(message) = &self.welcome_message {
+            if message.chars().count() > Constants::MAX_WELCOME_MESSAGE_LENGTH {
+                errors.push(FieldError::new(
+                    "welcome_message",
+                    &format!("Welcome message cannot exceed {} characters", Constants::MAX_WELCOME_MESSAGE_LENGTH),
+                ));
+            }
+        }
+        
+        field_errors(errors)
+    }
+}
+
+impl Session {
+    /// Whether the session's join window, if it has one, has closed by `now`
+    pub fn join_window_closed(&self, now: DateTime<Utc>) -> bool {
+        self.join_window_minutes
+            .is_some_and(|minutes| self.created_at + chrono::Duration::minutes(minutes) < now)
+    }
+}
+
+impl CreateSessionRequest {
+    /// Minutes until the session expires, with the default duration clamped
+    /// to the configured maximum
+    pub fn duration_minutes(&self, max_duration_minutes: i64) -> i64 {
+        self.expires_in_minutes
+            .unwrap_or(Constants::DEFAULT_SESSION_DURATION_MINUTES)
+            .min(max_duration_minutes)
+    }
+}
+
+impl UpdateSessionRequest {
+    /// Validate the request, reporting every invalid field
+    pub fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let mut errors = Vec::new();
+        
+        if let Some(tags) = &self.tags {
+            if let Err(message) = crate::utils::validate_session_tags(tags) {
+                errors.push(FieldError::new("tags", &message));
+            }
+        }
+        
+        if let Some(metadata) = &self.metadata {
+            if let Err(message) = crate::utils::validate_session_metadata(metadata) {
+                errors.push(FieldError::new("metadata", &message));
+            }
+        }
+        
+        field_errors(errors)
     }
 }
 
 impl JoinSessionRequest {
-    pub fn validate(&self) -> Result<(), String> {
+    /// Validate the request, reporting every invalid field
+    pub fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let mut errors = Vec::new();
+        
         if self.display_name.trim().is_empty() {
-            return Err("Display name cannot be empty".to_string());
+            errors.push(FieldError::new("display_name", "Display name cannot be empty"));
+        } else if self.display_name.len() > 100 {
+            errors.push(FieldError::new("display_name", "Display name cannot exceed 100 characters"));
         }
         
-        if self.display_name.len() > 100 {
-            return Err("Display name cannot exceed 100 characters".to_string());
+        if let Some(color) = &self.avatar_color {
+            if !crate::utils::is_valid_hex_color(color) {
+                errors.push(FieldError::new(
+                    "avatar_color",
+                    "Avatar color must be a hex color such as #F53, #FF5733 or #FF5733AA",
+                ));
+            }
         }
         
-        if let Some(color) = &self.avatar_color {
-            if !color.starts_with('#') || color.len() != 7 {
-                return Err("Avatar color must be a valid hex color (e.g., #FF5733)".to_string());
+        if let Some(device_id) = &self.device_id {
+            if !crate::utils::is_valid_device_id(device_id) {
+                errors.push(FieldError::new(
+                    "device_id",
+                    &format!(
+                        "Device ID must be 1-{} letters, digits, '-', '_' or '.'",
+                        Constants::MAX_DEVICE_ID_LENGTH
+                    ),
+                ));
             }
         }
         
+        field_errors(errors)
+    }
+}
+
+/// `Ok` when no field failed validation, otherwise every failure
+fn field_errors(errors: Vec<FieldError>) -> Result<(), Vec<FieldError>> {
+    if errors.is_empty() {
         Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+impl RemoveParticipantsRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.user_ids.is_empty() {
+            return Err("At least one user ID is required".to_string());
+        }
+        
+        if self.user_ids.len() > Constants::MAX_PARTICIPANTS_PER_SESSION {
+            return Err(format!(
+                "Cannot remove more than {} participants at once",
+                Constants::MAX_PARTICIPANTS_PER_SESSION
+            ));
+        }
+        
+        Ok(())
+    }
+}
+
+impl IngestLocationsRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.locations.is_empty() {
+            return Err("At least one location is required".to_string());
+        }
+        
+        if self.locations.len() > Constants::MAX_LOCATION_BATCH_SIZE {
+            return Err(format!(
+                "Cannot ingest more than {} locations at once",
+                Constants::MAX_LOCATION_BATCH_SIZE
+            ));
+        }
+        
+        Ok(())
+    }
+}
+
+impl IngestLocationEntry {
+    /// Validated like a WebSocket `location_update`
+    pub fn validate(&self) -> Result<(), String> {
+        self.as_update().validate()
+    }
+    
+    /// The entry as the `location_update` a connected participant would send
+    pub fn as_update(&self) -> LocationUpdateData {
+        LocationUpdateData {
+            lat: self.lat,
+            lng: self.lng,
+            accuracy: self.accuracy,
+            timestamp: self.timestamp,
+            seq: None,
+        }
+    }
+}
+
+impl ParticipantStatsQuery {
+    /// The requested destination, if any, once both coordinates are given
+    /// and in range
+    pub fn destination(&self) -> Result<Option<(f64, f64)>, String> {
+        let (lat, lng) = match (self.dest_lat, self.dest_lng) {
+            (None, None) => return Ok(None),
+            (Some(lat), Some(lng)) => (lat, lng),
+            _ => return Err("Both dest_lat and dest_lng are required for an ETA".to_string()),
+        };
+        
+        if !(-90.0..=90.0).contains(&lat) {
+            return Err("Latitude must be between -90 and 90 degrees".to_string());
+        }
+        
+        if !(-180.0..=180.0).contains(&lng) {
+            return Err("Longitude must be between -180 and 180 degrees".to_string());
+        }
+        
+        Ok(Some((lat, lng)))
+    }
+}
+
+impl LocationPrecision {
+    /// Approximate length of one degree of latitude in meters
+    const METERS_PER_DEGREE: f64 = 111_320.0;
+
+    /// Grid spacing in degrees that coordinates are snapped to
+    fn grid_degrees(self) -> Option<f64> {
+        match self {
+            LocationPrecision::Exact => None,
+            LocationPrecision::Coarse => Some(0.001),
+            LocationPrecision::City => Some(0.1),
+        }
+    }
+
+    /// Snap a location to this precision's grid, widening its accuracy to
+    /// at least the grid spacing
+    pub fn apply(self, location: &Location) -> Location {
+        let Some(grid) = self.grid_degrees() else {
+            return location.clone();
+        };
+        let snap = |degrees: f64| (degrees / grid).round() * grid;
+
+        Location {
+            lat: snap(location.lat),
+            lng: snap(location.lng),
+            accuracy: location.accuracy.max(grid * Self::METERS_PER_DEGREE),
+            timestamp: location.timestamp,
+        }
     }
 }
 
@@ -302,4 +1324,61 @@ impl LocationUpdateData {
         
         Ok(())
     }
+    
+    /// Whether the fix is at least as accurate as `max_accuracy_meters`
+    /// (any fix when None)
+    pub fn is_accurate_enough(&self, max_accuracy_meters: Option<f64>) -> bool {
+        max_accuracy_meters.is_none_or(|max| self.accuracy <= max)
+    }
+}
+
+impl SetWaypointData {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.lat < -90.0 || self.lat > 90.0 {
+            return Err("Latitude must be between -90 and 90 degrees".to_string());
+        }
+        
+        if self.lng < -180.0 || self.lng > 180.0 {
+            return Err("Longitude must be between -180 and 180 degrees".to_string());
+        }
+        
+        if self.label.trim().is_empty() {
+            return Err("Waypoint label cannot be empty".to_string());
+        }
+        
+        if self.label.chars().count() > Constants::MAX_WAYPOINT_LABEL_LENGTH {
+            return Err(format!(
+                "Waypoint label cannot exceed {} characters",
+                Constants::MAX_WAYPOINT_LABEL_LENGTH
+            ));
+        }
+        
+        Ok(())
+    }
+}
+
+impl StatusUpdateData {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.status.trim().is_empty() {
+            return Err("Status cannot be empty".to_string());
+        }
+        
+        if self.status.chars().count() > Constants::MAX_STATUS_LENGTH {
+            return Err(format!(
+                "Status cannot exceed {} characters",
+                Constants::MAX_STATUS_LENGTH
+            ));
+        }
+        
+        if let Some(emoji) = &self.emoji {
+            if emoji.trim().is_empty() || emoji.chars().count() > Constants::MAX_STATUS_EMOJI_LENGTH {
+                return Err(format!(
+                    "Emoji must be between 1 and {} characters",
+                    Constants::MAX_STATUS_EMOJI_LENGTH
+                ));
+            }
+        }
+        
+        Ok(())
+    }
 }
\ No newline at end of file
@@ -1,16 +1,203 @@
 use chrono::{DateTime, Utc, Duration};
 use uuid::Uuid;
 use rand::Rng;
-use crate::types::Constants;
+use sha2::{Digest, Sha256};
+use crate::error::{AppError, AppResult};
+use crate::types::{Constants, Location};
+use unicode_general_category::{get_general_category, GeneralCategory};
+use unicode_segmentation::UnicodeSegmentation;
 
-/// Utility functions for common operations
-
-/// Generate a random avatar color from predefined set
-pub fn generate_avatar_color() -> String {
+/// Generate a random avatar color from `palette`, or the built-in palette
+/// when it is empty, skipping `reserved` colors
+pub fn generate_avatar_color(palette: &[String], reserved: &[String]) -> String {
     let mut rng = rand::thread_rng();
-    let colors = Constants::DEFAULT_AVATAR_COLORS;
+    let colors = palette_colors(palette, reserved);
     let index = rng.gen_range(0..colors.len());
-    colors[index].to_string()
+    colors[index].clone()
+}
+
+/// Pick an unreserved palette color not already in use, falling back to a
+/// color derived from `seed` once every palette color is taken
+pub fn generate_distinct_avatar_color(
+    palette: &[String],
+    reserved: &[String],
+    used_colors: &[String],
+    seed: &str,
+) -> String {
+    palette_colors(palette, reserved)
+        .into_iter()
+        .find(|color| !used_colors.iter().any(|used| used.eq_ignore_ascii_case(color)))
+        .unwrap_or_else(|| {
+            // Re-salt the seed until the derived color isn't reserved
+            std::iter::once(hashed_avatar_color(seed))
+                .chain((1u32..).map(|salt| hashed_avatar_color(&format!("{}:{}", seed, salt))))
+                .find(|color| !is_reserved_avatar_color(color, reserved))
+                .unwrap_or_default()
+        })
+}
+
+/// Whether a color is on the reserved list, regardless of case or the
+/// `#RGB`/`#RRGGBB`/`#RRGGBBAA` form either is written in
+pub fn is_reserved_avatar_color(color: &str, reserved: &[String]) -> bool {
+    let Some(color) = normalize_hex_color(color) else {
+        return false;
+    };
+    reserved
+        .iter()
+        .filter_map(|reserved| normalize_hex_color(reserved))
+        .any(|reserved| reserved.eq_ignore_ascii_case(&color))
+}
+
+/// A palette's unreserved colors as stored on participants (`#RRGGBB`), or
+/// the unreserved built-in colors when it has none
+fn palette_colors(palette: &[String], reserved: &[String]) -> Vec<String> {
+    let unreserved = |colors: Vec<String>| -> Vec<String> {
+        colors
+            .into_iter()
+            .filter(|color| !is_reserved_avatar_color(color, reserved))
+            .collect()
+    };
+    
+    let colors = unreserved(palette.iter().filter_map(|color| normalize_hex_color(color)).collect());
+    if colors.is_empty() {
+        return unreserved(Constants::DEFAULT_AVATAR_COLORS.iter().map(|color| color.to_string()).collect());
+    }
+    colors
+}
+
+/// Check an avatar palette's size and that every entry is a hex color
+pub fn validate_avatar_palette(palette: &[String]) -> Result<(), String> {
+    if palette.len() > Constants::MAX_AVATAR_PALETTE_SIZE {
+        return Err(format!(
+            "Avatar palette cannot have more than {} colors",
+            Constants::MAX_AVATAR_PALETTE_SIZE
+        ));
+    }
+    
+    match palette.iter().find(|color| !is_valid_hex_color(color)) {
+        Some(color) => Err(format!("Invalid avatar palette color: {:?}", color)),
+        None => Ok(()),
+    }
+}
+
+/// Check a session's tag count and that every tag fits the length limit
+pub fn validate_session_tags(tags: &[String]) -> Result<(), String> {
+    if tags.len() > Constants::MAX_SESSION_TAGS {
+        return Err(format!(
+            "A session cannot have more than {} tags",
+            Constants::MAX_SESSION_TAGS
+        ));
+    }
+    
+    match tags.iter().find(|tag| {
+        let tag = tag.trim();
+        tag.is_empty() || tag.chars().count() > Constants::MAX_SESSION_TAG_LENGTH
+    }) {
+        Some(tag) => Err(format!(
+            "Invalid session tag {:?}: tags must be 1 to {} characters",
+            tag,
+            Constants::MAX_SESSION_TAG_LENGTH
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Check that session metadata is a JSON object within the size limit
+pub fn validate_session_metadata(metadata: &serde_json::Value) -> Result<(), String> {
+    if !metadata.is_object() {
+        return Err("Session metadata must be a JSON object".to_string());
+    }
+    
+    if metadata.to_string().len() > Constants::MAX_SESSION_METADATA_BYTES {
+        return Err(format!(
+            "Session metadata cannot exceed {} bytes",
+            Constants::MAX_SESSION_METADATA_BYTES
+        ));
+    }
+    
+    Ok(())
+}
+
+/// Merge a metadata patch into a session's metadata, replacing top-level
+/// keys and removing those the patch sets to null
+pub fn merge_session_metadata(metadata: &mut serde_json::Value, patch: &serde_json::Value) {
+    let (Some(metadata), Some(patch)) = (metadata.as_object_mut(), patch.as_object()) else {
+        return;
+    };
+    
+    for (key, value) in patch {
+        if value.is_null() {
+            metadata.remove(key);
+        } else {
+            metadata.insert(key.clone(), value.clone());
+        }
+    }
+}
+
+/// Convert a snake_case field name to camelCase, e.g. `expires_at` to `expiresAt`
+pub fn to_camel_case(name: &str) -> String {
+    let mut camel = String::with_capacity(name.len());
+    let mut upper_next = false;
+    for c in name.chars() {
+        if c == '_' {
+            upper_next = !camel.is_empty();
+        } else if upper_next {
+            camel.extend(c.to_uppercase());
+            upper_next = false;
+        } else {
+            camel.push(c);
+        }
+    }
+    camel
+}
+
+/// Rename object keys to camelCase throughout a JSON value
+///
+/// Client-defined `metadata` objects are kept exactly as they were stored.
+pub fn camel_case_keys(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, mut field) in std::mem::take(map) {
+                if key != "metadata" {
+                    camel_case_keys(&mut field);
+                }
+                map.insert(to_camel_case(&key), field);
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(camel_case_keys),
+        _ => {}
+    }
+}
+
+/// Check that a message fits within the pub/sub size limit before publishing it
+pub fn check_publish_size(message: &str, max_bytes: usize) -> AppResult<()> {
+    if message.len() > max_bytes {
+        return Err(AppError::PublishTooLarge {
+            size: message.len(),
+            max: max_bytes,
+        });
+    }
+    Ok(())
+}
+
+/// Trim and lowercase session tags, dropping repeats but keeping order
+pub fn normalize_session_tags(tags: &[String]) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::with_capacity(tags.len());
+    for tag in tags {
+        let tag = tag.trim().to_lowercase();
+        if !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
+    }
+    normalized
+}
+
+/// Derive a stable color from a string (FNV-1a hash of its bytes)
+fn hashed_avatar_color(seed: &str) -> String {
+    let hash = seed.bytes().fold(0x811c9dc5u32, |hash, byte| {
+        (hash ^ u32::from(byte)).wrapping_mul(0x01000193)
+    });
+    format!("#{:06X}", hash & 0xFFFFFF)
 }
 
 /// Calculate session expiration time based on duration in minutes
@@ -23,34 +210,119 @@ pub fn is_session_expired(expires_at: DateTime<Utc>) -> bool {
     Utc::now() > expires_at
 }
 
-/// Check if a session should auto-expire due to inactivity
-pub fn should_auto_expire(last_activity: DateTime<Utc>) -> bool {
-    let inactivity_threshold = Utc::now() - Duration::minutes(Constants::SESSION_AUTO_EXPIRE_MINUTES);
+/// Check if a session should auto-expire after `inactivity_minutes` without activity
+pub fn should_auto_expire(last_activity: DateTime<Utc>, inactivity_minutes: i64) -> bool {
+    let inactivity_threshold = Utc::now() - Duration::minutes(inactivity_minutes);
     last_activity < inactivity_threshold
 }
 
-/// Generate a join link for a session
-pub fn generate_join_link(session_id: Uuid, base_url: &str) -> String {
-    format!("{}/join/{}", base_url, session_id)
+/// Generate a join link embedding a session's join token
+pub fn generate_join_link(join_token: Uuid, base_url: &str, base_path: &str) -> String {
+    format!("{}{}/join/{}", base_url, base_path, join_token)
 }
 
-/// Generate a WebSocket URL for connection
-pub fn generate_websocket_url(base_ws_url: &str) -> String {
-    format!("{}/ws", base_ws_url)
+/// Generate a WebSocket URL for connection on `ws_path`, the path the
+/// WebSocket server upgrades on. A proxy that strips `base_path` needs it put
+/// back in front; a `ws_path` that already carries it is used as is.
+pub fn generate_websocket_url(base_ws_url: &str, base_path: &str, ws_path: &str) -> String {
+    let prefixed = !base_path.is_empty()
+        && ws_path.strip_prefix(base_path).is_some_and(|rest| rest.starts_with('/'));
+    if prefixed {
+        format!("{}{}", base_ws_url, ws_path)
+    } else {
+        format!("{}{}{}", base_ws_url, base_path, ws_path)
+    }
 }
 
-/// Validate hex color format
+/// Validate hex color format (`#RGB`, `#RRGGBB` or `#RRGGBBAA`)
 pub fn is_valid_hex_color(color: &str) -> bool {
-    if !color.starts_with('#') || color.len() != 7 {
+    let Some(digits) = color.strip_prefix('#') else {
         return false;
+    };
+    
+    matches!(digits.len(), 3 | 6 | 8) && digits.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Normalize a valid hex color to `#RRGGBB`, expanding `#RGB` shorthand and
+/// dropping any alpha channel
+pub fn normalize_hex_color(color: &str) -> Option<String> {
+    if !is_valid_hex_color(color) {
+        return None;
     }
     
-    color.chars().skip(1).all(|c| c.is_ascii_hexdigit())
+    let digits = &color[1..];
+    let normalized = match digits.len() {
+        3 => digits.chars().flat_map(|c| [c, c]).collect(),
+        _ => digits[..6].to_string(),
+    };
+    Some(format!("#{}", normalized))
 }
 
-/// Sanitize display name by trimming whitespace and limiting length
+/// Sanitize display name by stripping invisible characters, trimming
+/// whitespace and limiting length
+///
+/// Control, format (zero-width and bidi), private-use and unassigned
+/// characters are dropped, except format characters inside an emoji
+/// sequence such as 👩‍💻; other whitespace becomes a plain space. The length
+/// limit of 100 characters never splits a grapheme cluster.
 pub fn sanitize_display_name(name: &str) -> String {
-    name.trim().chars().take(100).collect()
+    let stripped: String = name
+        .graphemes(true)
+        .flat_map(|grapheme| {
+            let is_emoji = grapheme
+                .chars()
+                .any(|c| get_general_category(c) == GeneralCategory::OtherSymbol);
+            grapheme.chars().filter_map(move |c| match get_general_category(c) {
+                _ if c.is_whitespace() => Some(' '),
+                GeneralCategory::Format if is_emoji => Some(c),
+                GeneralCategory::Control
+                | GeneralCategory::Format
+                | GeneralCategory::PrivateUse
+                | GeneralCategory::Unassigned => None,
+                _ => Some(c),
+            })
+        })
+        .collect();
+
+    let mut sanitized = String::new();
+    let mut length = 0;
+    for grapheme in stripped.trim().graphemes(true) {
+        length += grapheme.chars().count();
+        if length > 100 {
+            break;
+        }
+        sanitized.push_str(grapheme);
+    }
+    sanitized.trim_end().to_string()
+}
+
+/// Sanitize a display name and check it against the configured denylist
+///
+/// Every path that sets a display name goes through this, so deployments
+/// only have to configure `display_name_denylist` once.
+pub fn validate_display_name(name: &str, denylist: &[String]) -> Result<String, String> {
+    let name = sanitize_display_name(name);
+    if name.is_empty() {
+        return Err("Display name cannot be empty".to_string());
+    }
+    if contains_denied_word(&name, denylist) {
+        return Err("Display name is not allowed".to_string());
+    }
+    Ok(name)
+}
+
+/// Whether any whole word of `text` is on the denylist, ignoring case
+///
+/// Words are runs of letters and digits in any script, so "Glass" doesn't
+/// match a denied "ass".
+pub fn contains_denied_word(text: &str, denylist: &[String]) -> bool {
+    if denylist.is_empty() {
+        return false;
+    }
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .any(|word| denylist.iter().any(|denied| denied.trim().to_lowercase() == word))
 }
 
 /// Sanitize session name by trimming whitespace and limiting length
@@ -58,11 +330,74 @@ pub fn sanitize_session_name(name: &str) -> String {
     name.trim().chars().take(255).collect()
 }
 
+/// Trim a session welcome message, or `None` if nothing is left
+pub fn sanitize_welcome_message(message: &str) -> Option<String> {
+    let message = message.trim();
+    (!message.is_empty()).then(|| message.to_string())
+}
+
 /// Generate a unique user ID for anonymous participants
 pub fn generate_user_id() -> String {
     Uuid::new_v4().to_string()
 }
 
+/// Derive a participant's user ID from their device ID
+///
+/// A UUIDv5 namespaced by the session, so the same device always maps to the
+/// same participant within a session but to unrelated IDs across sessions.
+pub fn derive_user_id(session_id: &Uuid, device_id: &str) -> String {
+    Uuid::new_v5(session_id, device_id.as_bytes()).to_string()
+}
+
+/// Validate a client-provided device ID (ASCII letters, digits, `-`, `_`, `.`)
+pub fn is_valid_device_id(device_id: &str) -> bool {
+    !device_id.is_empty()
+        && device_id.len() <= Constants::MAX_DEVICE_ID_LENGTH
+        && device_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+/// Validate an `Idempotency-Key` header (visible ASCII, no spaces)
+pub fn is_valid_idempotency_key(key: &str) -> bool {
+    !key.is_empty()
+        && key.len() <= Constants::MAX_IDEMPOTENCY_KEY_LENGTH
+        && key.chars().all(|c| c.is_ascii_graphic())
+}
+
+/// Fingerprint a request sent under an `Idempotency-Key` as the SHA-256 hex
+/// digest of the key and the request body
+///
+/// Only a retry repeating both gets the original response back.
+pub fn idempotency_fingerprint(key: &str, body: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(key.as_bytes());
+    hasher.update([0]);
+    hasher.update(body.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+/// Whether a location timestamped `timestamp` follows the stored one by less
+/// than `min_update_interval_ms`; 0 never counts as too soon
+pub fn is_too_soon(stored: &Location, timestamp: DateTime<Utc>, min_update_interval_ms: u64) -> bool {
+    if min_update_interval_ms == 0 {
+        return false;
+    }
+
+    timestamp - stored.timestamp < Duration::milliseconds(min_update_interval_ms as i64)
+}
+
+/// Whether a location is the same point as the stored one, e.g. a client
+/// resending an update it wasn't sure got through
+pub fn is_duplicate_location(stored: &Location, location: &Location) -> bool {
+    const EPSILON: f64 = 1e-9;
+
+    stored.timestamp == location.timestamp
+        && (stored.lat - location.lat).abs() < EPSILON
+        && (stored.lng - location.lng).abs() < EPSILON
+        && (stored.accuracy - location.accuracy).abs() < EPSILON
+}
+
 /// Calculate distance between two coordinates using Haversine formula
 pub fn calculate_distance(lat1: f64, lng1: f64, lat2: f64, lng2: f64) -> f64 {
     const R: f64 = 6371000.0; // Earth's radius in meters
@@ -79,6 +414,106 @@ pub fn calculate_distance(lat1: f64, lng1: f64, lat2: f64, lng2: f64) -> f64 {
     R * c
 }
 
+/// Initial bearing in degrees clockwise from north (0-360) for the great
+/// circle from the first coordinate to the second
+pub fn calculate_bearing(lat1: f64, lng1: f64, lat2: f64, lng2: f64) -> f64 {
+    let lat1_rad = lat1.to_radians();
+    let lat2_rad = lat2.to_radians();
+    let delta_lng = (lng2 - lng1).to_radians();
+
+    let y = delta_lng.sin() * lat2_rad.cos();
+    let x = lat1_rad.cos() * lat2_rad.sin() - lat1_rad.sin() * lat2_rad.cos() * delta_lng.cos();
+
+    y.atan2(x).to_degrees().rem_euclid(360.0)
+}
+
+/// Total distance in meters along a path of locations, in order
+pub fn path_distance(points: &[Location]) -> f64 {
+    points
+        .windows(2)
+        .map(|pair| calculate_distance(pair[0].lat, pair[0].lng, pair[1].lat, pair[1].lng))
+        .sum()
+}
+
+/// Average speed in meters per second over a path, or zero when no time passes
+pub fn average_speed(points: &[Location]) -> f64 {
+    let (Some(first), Some(last)) = (points.first(), points.last()) else {
+        return 0.0;
+    };
+    let elapsed = (last.timestamp - first.timestamp).num_milliseconds() as f64 / 1000.0;
+    
+    if elapsed > 0.0 {
+        path_distance(points) / elapsed
+    } else {
+        0.0
+    }
+}
+
+/// Fastest speed in meters per second between consecutive locations
+pub fn max_speed(points: &[Location]) -> f64 {
+    points
+        .windows(2)
+        .filter_map(|pair| {
+            let elapsed = (pair[1].timestamp - pair[0].timestamp).num_milliseconds() as f64 / 1000.0;
+            (elapsed > 0.0).then(|| {
+                calculate_distance(pair[0].lat, pair[0].lng, pair[1].lat, pair[1].lng) / elapsed
+            })
+        })
+        .fold(0.0, f64::max)
+}
+
+/// Evenly spaced waypoints strictly between two locations, about
+/// `step_meters` apart
+///
+/// Coordinates, accuracy and timestamps are interpolated linearly, which
+/// is accurate enough over the distances between consecutive GPS fixes.
+/// A hop shorter than a step yields none; a long hop yields at most
+/// `max_points`, spread out further apart.
+pub fn interpolate_path(from: &Location, to: &Location, step_meters: f64, max_points: usize) -> Vec<Location> {
+    let distance = calculate_distance(from.lat, from.lng, to.lat, to.lng);
+    if step_meters <= 0.0 || distance <= step_meters {
+        return Vec::new();
+    }
+
+    let count = ((distance / step_meters).ceil() as usize - 1).min(max_points);
+    let elapsed = to.timestamp - from.timestamp;
+    (1..=count)
+        .map(|i| {
+            let fraction = i as f64 / (count + 1) as f64;
+            Location {
+                lat: from.lat + (to.lat - from.lat) * fraction,
+                lng: from.lng + (to.lng - from.lng) * fraction,
+                accuracy: from.accuracy + (to.accuracy - from.accuracy) * fraction,
+                timestamp: from.timestamp
+                    + Duration::milliseconds((elapsed.num_milliseconds() as f64 * fraction) as i64),
+            }
+        })
+        .collect()
+}
+
+/// Geographic center of a set of locations, as `(lat, lng)`
+///
+/// Averages the points as unit vectors, so a group straddling the 180°
+/// meridian is centered on it rather than on the far side of the globe.
+/// `None` without points, or when they cancel out, e.g. antipodal pairs.
+pub fn centroid(points: &[Location]) -> Option<(f64, f64)> {
+    let (x, y, z) = points.iter().fold((0.0, 0.0, 0.0), |(x, y, z), point| {
+        let (lat, lng) = (point.lat.to_radians(), point.lng.to_radians());
+        (x + lat.cos() * lng.cos(), y + lat.cos() * lng.sin(), z + lat.sin())
+    });
+    let horizontal = f64::hypot(x, y);
+    if horizontal.hypot(z) < 1e-9 {
+        return None;
+    }
+
+    Some((z.atan2(horizontal).to_degrees(), y.atan2(x).to_degrees()))
+}
+
+/// Seconds to cover `distance_meters` at `speed_mps`, or `None` when not moving
+pub fn estimate_eta_seconds(distance_meters: f64, speed_mps: f64) -> Option<f64> {
+    (speed_mps > 0.0).then(|| distance_meters / speed_mps)
+}
+
 /// Format duration in a human-readable way
 pub fn format_duration(duration: Duration) -> String {
     let total_seconds = duration.num_seconds();
@@ -145,12 +580,163 @@ mod tests {
 
     #[test]
     fn test_generate_avatar_color() {
-        let color = generate_avatar_color();
+        let color = generate_avatar_color(&[], &[]);
         assert!(color.starts_with('#'));
         assert_eq!(color.len(), 7);
         assert!(Constants::DEFAULT_AVATAR_COLORS.contains(&color.as_str()));
     }
 
+    #[test]
+    fn test_generate_avatar_color_from_palette() {
+        let palette = vec!["#123456".to_string(), "#abc".to_string()];
+        for _ in 0..20 {
+            let color = generate_avatar_color(&palette, &[]);
+            assert!(color == "#123456" || color == "#aabbcc", "{} is not in the palette", color);
+        }
+
+        let first = generate_distinct_avatar_color(&palette, &[], &[], "user");
+        let second = generate_distinct_avatar_color(&palette, &[], std::slice::from_ref(&first), "user");
+        assert_eq!((first.as_str(), second.as_str()), ("#123456", "#aabbcc"));
+    }
+
+    #[test]
+    fn test_validate_avatar_palette() {
+        assert!(validate_avatar_palette(&[]).is_ok());
+        assert!(validate_avatar_palette(&["#123456".to_string(), "#ABC".to_string()]).is_ok());
+        assert!(validate_avatar_palette(&["#123456".to_string(), "blue".to_string()]).is_err());
+
+        let oversized = vec!["#123456".to_string(); Constants::MAX_AVATAR_PALETTE_SIZE + 1];
+        assert!(validate_avatar_palette(&oversized).is_err());
+    }
+
+    #[test]
+    fn test_session_tags() {
+        let tags = vec![" Hiking ".to_string(), "delivery".to_string(), "HIKING".to_string()];
+        assert!(validate_session_tags(&tags).is_ok());
+        assert_eq!(normalize_session_tags(&tags), vec!["hiking", "delivery"]);
+
+        assert!(validate_session_tags(&["  ".to_string()]).is_err());
+        assert!(validate_session_tags(&["a".repeat(Constants::MAX_SESSION_TAG_LENGTH + 1)]).is_err());
+        assert!(validate_session_tags(&vec!["tag".to_string(); Constants::MAX_SESSION_TAGS + 1]).is_err());
+    }
+
+    #[test]
+    fn test_camel_case_keys() {
+        assert_eq!(to_camel_case("expires_in_minutes"), "expiresInMinutes");
+        assert_eq!(to_camel_case("items"), "items");
+
+        let mut value = serde_json::json!({
+            "session_id": "abc",
+            "items": [{ "display_name": "Ann" }],
+            "metadata": { "icon_url": "van.png" },
+        });
+        camel_case_keys(&mut value);
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "sessionId": "abc",
+                "items": [{ "displayName": "Ann" }],
+                "metadata": { "icon_url": "van.png" },
+            })
+        );
+    }
+
+    #[test]
+    fn test_check_publish_size() {
+        assert!(check_publish_size("{}", 2).is_ok());
+
+        let error = check_publish_size("{\"a\":1}", 2).unwrap_err();
+        assert!(matches!(error, AppError::PublishTooLarge { size: 7, max: 2 }));
+        assert_eq!(error.error_code(), "PAYLOAD_TOO_LARGE");
+        assert_eq!(error.status_code(), 413);
+    }
+
+    #[test]
+    fn test_session_metadata() {
+        let mut metadata = serde_json::json!({ "theme": "dark", "icon": "van" });
+        assert!(validate_session_metadata(&metadata).is_ok());
+
+        merge_session_metadata(&mut metadata, &serde_json::json!({ "theme": "light", "icon": null, "route": 7 }));
+        assert_eq!(metadata, serde_json::json!({ "theme": "light", "route": 7 }));
+
+        assert!(validate_session_metadata(&serde_json::json!(["theme"])).is_err());
+        assert!(validate_session_metadata(&serde_json::json!("dark")).is_err());
+        let oversized = serde_json::json!({ "notes": "a".repeat(Constants::MAX_SESSION_METADATA_BYTES) });
+        assert!(validate_session_metadata(&oversized).is_err());
+    }
+
+    #[test]
+    fn test_generate_distinct_avatar_color() {
+        let palette = Constants::DEFAULT_AVATAR_COLORS;
+        let mut used = Vec::new();
+        for _ in 0..palette.len() {
+            let color = generate_distinct_avatar_color(&[], &[], &used, "user");
+            assert!(palette.contains(&color.as_str()));
+            assert!(!used.contains(&color));
+            used.push(color);
+        }
+
+        // Palette exhausted: fall back to a stable hashed color
+        let color = generate_distinct_avatar_color(&[], &[], &used, "user");
+        assert!(is_valid_hex_color(&color));
+        assert_eq!(color, generate_distinct_avatar_color(&[], &[], &used, "user"));
+    }
+
+    #[test]
+    fn test_reserved_avatar_colors() {
+        let reserved = vec!["#f00".to_string(), "#00FF00".to_string()];
+        assert!(is_reserved_avatar_color("#FF0000", &reserved));
+        assert!(is_reserved_avatar_color("#00ff00cc", &reserved));
+        assert!(!is_reserved_avatar_color("#0000FF", &reserved));
+        
+        let palette = vec!["#ff0000".to_string(), "#0000ff".to_string()];
+        for _ in 0..20 {
+            assert_eq!(generate_avatar_color(&palette, &reserved), "#0000ff");
+        }
+        assert_eq!(generate_distinct_avatar_color(&palette, &reserved, &[], "user"), "#0000ff");
+        
+        // A fully reserved palette falls back to the unreserved built-in colors
+        let reserved_palette = vec!["#FF0000".to_string()];
+        for _ in 0..20 {
+            let color = generate_avatar_color(&reserved_palette, &reserved);
+            assert!(Constants::DEFAULT_AVATAR_COLORS.contains(&color.as_str()));
+            assert!(!is_reserved_avatar_color(&color, &reserved));
+        }
+        
+        // So does the hashed color once every palette color is taken
+        let blue = vec!["#0000ff".to_string()];
+        let hashed = generate_distinct_avatar_color(&blue, &[], &blue, "user");
+        let color = generate_distinct_avatar_color(&blue, std::slice::from_ref(&hashed), &blue, "user");
+        assert!(is_valid_hex_color(&color));
+        assert_ne!(color, hashed);
+    }
+
+    #[test]
+    fn test_generate_links_with_base_path() {
+        let join_token = Uuid::new_v4();
+
+        assert_eq!(
+            generate_join_link(join_token, "https://app.com", ""),
+            format!("https://app.com/join/{}", join_token)
+        );
+        assert_eq!(
+            generate_join_link(join_token, "https://app.com", "/location"),
+            format!("https://app.com/location/join/{}", join_token)
+        );
+        assert_eq!(generate_websocket_url("wss://app.com", "", "/ws"), "wss://app.com/ws");
+        assert_eq!(generate_websocket_url("wss://app.com", "/location", "/ws"), "wss://app.com/location/ws");
+        assert_eq!(generate_websocket_url("wss://app.com", "", "/socket"), "wss://app.com/socket");
+        // A ws_path that already includes the prefix is not prefixed twice
+        assert_eq!(
+            generate_websocket_url("wss://app.com", "/location", "/location/ws"),
+            "wss://app.com/location/ws"
+        );
+        assert_eq!(
+            generate_websocket_url("wss://app.com", "/location", "/locations/ws"),
+            "wss://app.com/location/locations/ws"
+        );
+    }
+
     #[test]
     fn test_is_valid_hex_color() {
         assert!(is_valid_hex_color("#FF5733"));
@@ -159,6 +745,62 @@ mod tests {
         assert!(!is_valid_hex_color("FF5733"));
         assert!(!is_valid_hex_color("#FF573"));
         assert!(!is_valid_hex_color("#GG5733"));
+        assert!(is_valid_hex_color("#FFF"));
+        assert!(is_valid_hex_color("#FF5733AA"));
+        assert!(!is_valid_hex_color("#FF57"));
+        assert!(!is_valid_hex_color("#GGG"));
+    }
+
+    #[test]
+    fn test_normalize_hex_color() {
+        assert_eq!(normalize_hex_color("#FFF").as_deref(), Some("#FFFFFF"));
+        assert_eq!(normalize_hex_color("#a1c").as_deref(), Some("#aa11cc"));
+        assert_eq!(normalize_hex_color("#FF5733").as_deref(), Some("#FF5733"));
+        assert_eq!(normalize_hex_color("#FF5733AA").as_deref(), Some("#FF5733"));
+        assert_eq!(normalize_hex_color("#FF57"), None);
+        assert_eq!(normalize_hex_color("#GGG"), None);
+    }
+
+    #[test]
+    fn test_derive_user_id() {
+        let session_id = Uuid::new_v4();
+
+        let user_id = derive_user_id(&session_id, "device-1");
+        assert_eq!(user_id, derive_user_id(&session_id, "device-1"));
+        assert_ne!(user_id, derive_user_id(&session_id, "device-2"));
+        assert_ne!(user_id, derive_user_id(&Uuid::new_v4(), "device-1"));
+        assert!(Uuid::parse_str(&user_id).is_ok());
+    }
+
+    #[test]
+    fn test_is_valid_device_id() {
+        assert!(is_valid_device_id("a1b2-c3d4_e5.f6"));
+        assert!(is_valid_device_id(&"a".repeat(Constants::MAX_DEVICE_ID_LENGTH)));
+        assert!(!is_valid_device_id(""));
+        assert!(!is_valid_device_id(&"a".repeat(Constants::MAX_DEVICE_ID_LENGTH + 1)));
+        assert!(!is_valid_device_id("device id"));
+        assert!(!is_valid_device_id("device/1"));
+    }
+
+    #[test]
+    fn test_is_valid_idempotency_key() {
+        assert!(is_valid_idempotency_key("5f1c2a9e-7d4b-4e0a-9c1d-3b8e6f2a0d47"));
+        assert!(is_valid_idempotency_key(&"k".repeat(Constants::MAX_IDEMPOTENCY_KEY_LENGTH)));
+        assert!(!is_valid_idempotency_key(""));
+        assert!(!is_valid_idempotency_key(&"k".repeat(Constants::MAX_IDEMPOTENCY_KEY_LENGTH + 1)));
+        assert!(!is_valid_idempotency_key("retry 1"));
+        assert!(!is_valid_idempotency_key("clé"));
+    }
+
+    #[test]
+    fn test_idempotency_fingerprint() {
+        let fingerprint = idempotency_fingerprint("key", "{}");
+        assert_eq!(fingerprint.len(), 64);
+        assert_eq!(fingerprint, idempotency_fingerprint("key", "{}"));
+        assert_ne!(fingerprint, idempotency_fingerprint("key", "{\"name\":null}"));
+        assert_ne!(fingerprint, idempotency_fingerprint("other-key", "{}"));
+        // The separator keeps the key and body from running together
+        assert_ne!(idempotency_fingerprint("ab", "c"), idempotency_fingerprint("a", "bc"));
     }
 
     #[test]
@@ -169,6 +811,41 @@ mod tests {
         let long_name = "a".repeat(150);
         let sanitized = sanitize_display_name(&long_name);
         assert_eq!(sanitized.len(), 100);
+        
+        // Truncation keeps combining marks with their letter
+        let accented = format!("{}e\u{301}", "a".repeat(99));
+        assert_eq!(sanitize_display_name(&accented), "a".repeat(99));
+    }
+
+    #[test]
+    fn test_display_name_control_characters_stripped() {
+        assert_eq!(sanitize_display_name("John\u{0}\u{7} Doe"), "John Doe");
+        assert_eq!(sanitize_display_name("Jo\nhn"), "Jo hn");
+        // Zero-width and bidi characters used to spoof other names
+        assert_eq!(sanitize_display_name("Ad\u{200D}min\u{200B}"), "Admin");
+        assert_eq!(sanitize_display_name("\u{202E}nimda"), "nimda");
+        assert_eq!(sanitize_display_name("\u{FEFF} \u{E000}"), "");
+        // Joiners inside an emoji sequence are part of the emoji
+        assert_eq!(sanitize_display_name("Dev 👩\u{200D}💻"), "Dev 👩\u{200D}💻");
+    }
+
+    #[test]
+    fn test_display_name_denylist() {
+        let denylist = vec!["Spam".to_string(), "moderator".to_string()];
+        assert!(validate_display_name("spam bot", &denylist).is_err());
+        assert!(validate_display_name("Not-A-MODERATOR", &denylist).is_err());
+        // Spoofing characters are stripped before matching
+        assert!(validate_display_name("mod\u{200B}erator", &denylist).is_err());
+        // Only whole words match
+        assert_eq!(validate_display_name("Spammington", &denylist).unwrap(), "Spammington");
+        assert!(validate_display_name("\u{200B}", &[]).is_err());
+    }
+
+    #[test]
+    fn test_display_name_multi_script() {
+        for name in ["Zoë Ångström", "Андрей", "李小龍", "محمد", "नमस्ते", "José 🚴"] {
+            assert_eq!(validate_display_name(name, &["spam".to_string()]).unwrap(), name);
+        }
     }
 
     #[test]
@@ -183,6 +860,18 @@ mod tests {
         assert!(distance > 1000.0 && distance < 2000.0); // Roughly 1 km
     }
 
+    #[test]
+    fn test_calculate_bearing() {
+        assert!((calculate_bearing(0.0, 0.0, 1.0, 0.0) - 0.0).abs() < 1e-9);
+        assert!((calculate_bearing(0.0, 0.0, 0.0, 1.0) - 90.0).abs() < 1e-9);
+        assert!((calculate_bearing(1.0, 0.0, 0.0, 0.0) - 180.0).abs() < 1e-9);
+        assert!((calculate_bearing(0.0, 1.0, 0.0, 0.0) - 270.0).abs() < 1e-9);
+
+        // San Francisco to New York heads roughly east-northeast
+        let bearing = calculate_bearing(37.7749, -122.4194, 40.7128, -74.0060);
+        assert!(bearing > 65.0 && bearing < 75.0);
+    }
+
     #[test]
     fn test_is_session_expired() {
         let future_time = Utc::now() + Duration::hours(1);
@@ -197,8 +886,9 @@ mod tests {
         let recent_activity = Utc::now() - Duration::minutes(30);
         let old_activity = Utc::now() - Duration::hours(2);
         
-        assert!(!should_auto_expire(recent_activity));
-        assert!(should_auto_expire(old_activity));
+        assert!(!should_auto_expire(recent_activity, 60));
+        assert!(should_auto_expire(old_activity, 60));
+        assert!(should_auto_expire(recent_activity, 15));
     }
 
     #[test]
@@ -219,4 +909,94 @@ mod tests {
         assert_eq!(truncate_text("Hello World", 5), "He...");
         assert_eq!(truncate_text("Hi", 5), "Hi");
     }
+    fn point_at(lng: f64, seconds: i64) -> Location {
+        Location {
+            lat: 0.0,
+            lng,
+            accuracy: 5.0,
+            timestamp: DateTime::from_timestamp(1_700_000_000 + seconds, 0).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_path_distance() {
+        // Two hundredths of a degree along the equator, in two equal legs
+        let leg = calculate_distance(0.0, 0.0, 0.0, 0.01);
+        let path = [point_at(0.0, 0), point_at(0.01, 100), point_at(0.02, 200)];
+        
+        assert!((leg - 1111.95).abs() < 0.01);
+        assert!((path_distance(&path) - 2.0 * leg).abs() < 1e-6);
+        assert!((average_speed(&path) - leg / 100.0).abs() < 1e-6);
+        assert!((max_speed(&path) - leg / 100.0).abs() < 1e-6);
+        
+        // Doubling back still counts every leg
+        let there_and_back = [point_at(0.0, 0), point_at(0.01, 100), point_at(0.0, 150)];
+        assert!((path_distance(&there_and_back) - 2.0 * leg).abs() < 1e-6);
+        assert!((max_speed(&there_and_back) - leg / 50.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_path_with_fewer_than_two_points() {
+        assert_eq!(path_distance(&[]), 0.0);
+        assert_eq!(path_distance(&[point_at(0.0, 0)]), 0.0);
+        assert_eq!(average_speed(&[point_at(0.0, 0)]), 0.0);
+        assert_eq!(max_speed(&[point_at(0.0, 0)]), 0.0);
+    }
+
+    #[test]
+    fn test_interpolate_short_hop() {
+        // About 11 meters, barely over one 10 meter step
+        let points = interpolate_path(&point_at(0.0, 0), &point_at(0.0001, 10), 10.0, 20);
+        assert_eq!(points.len(), 1);
+        
+        assert!(interpolate_path(&point_at(0.0, 0), &point_at(0.00005, 10), 10.0, 20).is_empty());
+        assert!(interpolate_path(&point_at(0.0, 0), &point_at(0.0, 10), 10.0, 20).is_empty());
+    }
+
+    #[test]
+    fn test_interpolate_long_straight_hop() {
+        // About 1112 meters along the equator in 100 meter steps
+        let from = point_at(0.0, 0);
+        let to = point_at(0.01, 120);
+        let points = interpolate_path(&from, &to, 100.0, 20);
+        assert_eq!(points.len(), 11);
+        
+        let mut path = vec![from.clone()];
+        path.extend(points.iter().cloned());
+        path.push(to.clone());
+        let spacing = path_distance(&path) / 12.0;
+        for pair in path.windows(2) {
+            let leg = calculate_distance(pair[0].lat, pair[0].lng, pair[1].lat, pair[1].lng);
+            assert!((leg - spacing).abs() < 1e-6);
+            assert_eq!(pair[1].timestamp - pair[0].timestamp, Duration::seconds(10));
+        }
+        
+        // Capped hops space their points further apart
+        let capped = interpolate_path(&from, &to, 100.0, 3);
+        assert_eq!(capped.len(), 3);
+        assert!((capped[0].lng - 0.0025).abs() < 1e-9);
+        assert!((capped[2].lng - 0.0075).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_estimate_eta_seconds() {
+        assert_eq!(estimate_eta_seconds(1000.0, 5.0), Some(200.0));
+        assert_eq!(estimate_eta_seconds(1000.0, 0.0), None);
+    }
+
+    #[test]
+    fn test_centroid() {
+        let at = |lat: f64, lng: f64| Location { lat, ..point_at(lng, 0) };
+
+        let (lat, lng) = centroid(&[at(10.0, 20.0), at(10.0, 20.02), at(10.02, 20.01)]).unwrap();
+        assert!((lat - 10.00667).abs() < 1e-3 && (lng - 20.01).abs() < 1e-3);
+
+        // Across the antimeridian the center is on it, not at longitude 0
+        let (lat, lng) = centroid(&[at(0.0, 179.0), at(0.0, -179.0)]).unwrap();
+        assert!(lat.abs() < 1e-9);
+        assert!((lng.abs() - 180.0).abs() < 1e-9);
+
+        assert_eq!(centroid(&[]), None);
+        assert_eq!(centroid(&[at(0.0, 0.0), at(0.0, 180.0)]), None);
+    }
 }
\ No newline at end of file
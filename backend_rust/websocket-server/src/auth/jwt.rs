@@ -1,30 +1,66 @@
-use jsonwebtoken::{decode, DecodingKey, Validation, Algorithm};
+use jsonwebtoken::{decode, DecodingKey, Validation};
+use jsonwebtoken::errors::ErrorKind;
 use shared::{AppError, AppResult, JwtClaims};
-use tracing::debug;
+use tracing::{debug, warn};
+use uuid::Uuid;
 
-/// Verify JWT token and return claims
-pub fn verify_jwt_token(token: &str, secret: &str) -> AppResult<JwtClaims> {
+/// Verify JWT token against the given validation rules and return claims
+///
+/// The rules come from `JwtConfig::validation`, which fixes the signing
+/// algorithm, the accepted issuer and audience, and the clock-skew leeway
+/// applied to `exp` and `iat`.
+pub fn verify_jwt_token(token: &str, secret: &str, validation: &Validation) -> AppResult<JwtClaims> {
     debug!("Verifying JWT token");
     
-    let validation = Validation::new(Algorithm::HS256);
     let token_data = decode::<JwtClaims>(
         token,
         &DecodingKey::from_secret(secret.as_ref()),
-        &validation,
-    )?;
+        validation,
+    )
+    .map_err(|e| match e.kind() {
+        ErrorKind::ExpiredSignature => AppError::TokenExpired,
+        _ => AppError::from(e),
+    })?;
 
     let claims = token_data.claims;
     
-    // Check if token is expired
+    // Check if token is expired, allowing for clock skew
     let now = chrono::Utc::now().timestamp();
-    if claims.exp < now {
+    let leeway = validation.leeway as i64;
+    if claims.exp + leeway < now {
         return Err(AppError::TokenExpired);
     }
 
+    // Reject tokens claiming to be issued in the future
+    if claims.iat > now + leeway {
+        warn!("Token for user {} issued in the future (iat {})", claims.sub, claims.iat);
+        return Err(AppError::InvalidToken);
+    }
+
     debug!("JWT token verified for user: {}", claims.sub);
     Ok(claims)
 }
 
+/// Verify JWT token and ensure it was issued for the given session
+pub fn verify_session_token(
+    token: &str,
+    secret: &str,
+    validation: &Validation,
+    session_id: &Uuid,
+) -> AppResult<JwtClaims> {
+    let claims = verify_jwt_token(token, secret, validation)?;
+
+    if claims.session_id != *session_id {
+        warn!(
+            "Token for session {} used for session {} by user {}",
+            claims.session_id, session_id, claims.sub
+        );
+        return Err(AppError::UnauthorizedSessionOperation);
+    }
+
+    Ok(claims)
+}
+
 /// Extract token from WebSocket URL query parameters
 pub fn extract_token_from_url(url: &str) -> Option<String> {
     url::Url::parse(url)
@@ -38,7 +74,8 @@ pub fn extract_token_from_url(url: &str) -> Option<String> {
 mod tests {
     use super::*;
     use chrono::{Duration, Utc};
-    use jsonwebtoken::{encode, EncodingKey, Header};
+    use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+    use shared::{AppConfig, JwtConfig};
     use uuid::Uuid;
 
     #[test]
@@ -51,6 +88,10 @@ mod tests {
             session_id,
             exp: (Utc::now() + Duration::hours(1)).timestamp(),
             iat: Utc::now().timestamp(),
+            precision: Default::default(),
+            role: Default::default(),
+            iss: None,
+            aud: None,
         };
 
         let token = encode(
@@ -59,7 +100,7 @@ mod tests {
             &EncodingKey::from_secret(secret.as_ref()),
         ).unwrap();
 
-        let result = verify_jwt_token(&token, secret);
+        let result = verify_jwt_token(&token, secret, &Validation::new(Algorithm::HS256));
         assert!(result.is_ok());
         
         let verified_claims = result.unwrap();
@@ -77,6 +118,10 @@ mod tests {
             session_id,
             exp: (Utc::now() - Duration::hours(1)).timestamp(), // Expired
             iat: Utc::now().timestamp(),
+            precision: Default::default(),
+            role: Default::default(),
+            iss: None,
+            aud: None,
         };
 
         let token = encode(
@@ -85,17 +130,71 @@ mod tests {
             &EncodingKey::from_secret(secret.as_ref()),
         ).unwrap();
 
-        let result = verify_jwt_token(&token, secret);
+        let result = verify_jwt_token(&token, secret, &Validation::new(Algorithm::HS256));
         assert!(result.is_err());
         assert!(matches!(result.unwrap_err(), AppError::TokenExpired));
     }
 
     #[test]
     fn test_verify_invalid_token() {
-        let result = verify_jwt_token("invalid-token", "secret");
+        let result = verify_jwt_token("invalid-token", "secret", &Validation::new(Algorithm::HS256));
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_verify_session_token_rejects_other_session() {
+        let secret = "test-secret";
+        let session_id = Uuid::new_v4();
+        let other_session_id = Uuid::new_v4();
+        
+        let claims = JwtClaims {
+            sub: "test-user".to_string(),
+            session_id,
+            exp: (Utc::now() + Duration::hours(1)).timestamp(),
+            iat: Utc::now().timestamp(),
+            precision: Default::default(),
+            role: Default::default(),
+            iss: None,
+            aud: None,
+        };
+
+        let token = encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(secret.as_ref()),
+        ).unwrap();
+
+        assert!(verify_session_token(&token, secret, &Validation::new(Algorithm::HS256), &session_id).is_ok());
+
+        let result = verify_session_token(&token, secret, &Validation::new(Algorithm::HS256), &other_session_id);
+        assert!(matches!(result.unwrap_err(), AppError::UnauthorizedSessionOperation));
+    }
+
+    #[test]
+    fn test_verify_token_with_configured_algorithm() {
+        let secret = "test-secret";
+        
+        let claims = JwtClaims {
+            sub: "test-user".to_string(),
+            session_id: Uuid::new_v4(),
+            exp: (Utc::now() + Duration::hours(1)).timestamp(),
+            iat: Utc::now().timestamp(),
+            precision: Default::default(),
+            role: Default::default(),
+            iss: None,
+            aud: None,
+        };
+
+        let token = encode(
+            &Header::new(Algorithm::HS512),
+            &claims,
+            &EncodingKey::from_secret(secret.as_ref()),
+        ).unwrap();
+
+        assert!(verify_jwt_token(&token, secret, &Validation::new(Algorithm::HS512)).is_ok());
+        assert!(verify_jwt_token(&token, secret, &Validation::new(Algorithm::HS256)).is_err());
+    }
+
     #[test]
     fn test_extract_token_from_url() {
         let url = "ws://localhost:8081/ws?token=abc123";
@@ -106,4 +205,125 @@ mod tests {
         let token = extract_token_from_url(url_no_token);
         assert_eq!(token, None);
     }
-}
\ No newline at end of file
+    fn issued_token(config: &JwtConfig, audience: &str) -> String {
+        let claims = JwtClaims {
+            sub: "test-user".to_string(),
+            session_id: Uuid::new_v4(),
+            exp: (Utc::now() + Duration::hours(1)).timestamp(),
+            iat: Utc::now().timestamp(),
+            precision: Default::default(),
+            role: Default::default(),
+            iss: Some(config.issuer.clone()),
+            aud: Some(audience.to_string()),
+        };
+
+        encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(config.secret.as_ref()),
+        ).unwrap()
+    }
+
+    #[test]
+    fn test_verify_token_with_matching_audience() {
+        let config = AppConfig::default().jwt;
+        let token = issued_token(&config, &config.audience);
+
+        let claims = verify_jwt_token(&token, &config.secret, &config.validation().unwrap()).unwrap();
+        assert_eq!(claims.aud.as_deref(), Some(config.audience.as_str()));
+    }
+
+    #[test]
+    fn test_verify_token_rejects_mismatched_audience() {
+        let config = AppConfig::default().jwt;
+        let token = issued_token(&config, "some-other-service");
+
+        let result = verify_jwt_token(&token, &config.secret, &config.validation().unwrap());
+        assert!(matches!(
+            result.unwrap_err(),
+            AppError::Jwt(e) if matches!(e.kind(), ErrorKind::InvalidAudience)
+        ));
+    }
+
+    #[test]
+    fn test_require_issuer_audience() {
+        let config = JwtConfig {
+            require_issuer_audience: true,
+            ..AppConfig::default().jwt
+        };
+        let validation = config.validation().unwrap();
+
+        let claims = JwtClaims {
+            sub: "test-user".to_string(),
+            session_id: Uuid::new_v4(),
+            exp: (Utc::now() + Duration::hours(1)).timestamp(),
+            iat: Utc::now().timestamp(),
+            precision: Default::default(),
+            role: Default::default(),
+            iss: None,
+            aud: None,
+        };
+        let legacy_token = encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(config.secret.as_ref()),
+        ).unwrap();
+
+        assert!(verify_jwt_token(&legacy_token, &config.secret, &validation).is_err());
+        assert!(verify_jwt_token(&issued_token(&config, &config.audience), &config.secret, &validation).is_ok());
+    }
+
+    fn token_with_times(secret: &str, exp: i64, iat: i64) -> String {
+        let claims = JwtClaims {
+            sub: "test-user".to_string(),
+            session_id: Uuid::new_v4(),
+            exp,
+            iat,
+            precision: Default::default(),
+            role: Default::default(),
+            iss: None,
+            aud: None,
+        };
+
+        encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(secret.as_ref()),
+        ).unwrap()
+    }
+
+    #[test]
+    fn test_expiry_leeway() {
+        let secret = "test-secret";
+        let mut validation = Validation::new(Algorithm::HS256);
+        validation.leeway = 60;
+        let now = Utc::now();
+
+        let within = token_with_times(secret, (now - Duration::seconds(30)).timestamp(), (now - Duration::hours(1)).timestamp());
+        assert!(verify_jwt_token(&within, secret, &validation).is_ok());
+
+        let beyond = token_with_times(secret, (now - Duration::seconds(120)).timestamp(), (now - Duration::hours(1)).timestamp());
+        assert!(matches!(
+            verify_jwt_token(&beyond, secret, &validation).unwrap_err(),
+            AppError::TokenExpired
+        ));
+    }
+
+    #[test]
+    fn test_reject_token_issued_in_future() {
+        let secret = "test-secret";
+        let mut validation = Validation::new(Algorithm::HS256);
+        validation.leeway = 60;
+        let now = Utc::now();
+        let exp = (now + Duration::hours(2)).timestamp();
+
+        let skewed = token_with_times(secret, exp, (now + Duration::seconds(30)).timestamp());
+        assert!(verify_jwt_token(&skewed, secret, &validation).is_ok());
+
+        let future = token_with_times(secret, exp, (now + Duration::hours(1)).timestamp());
+        assert!(matches!(
+            verify_jwt_token(&future, secret, &validation).unwrap_err(),
+            AppError::InvalidToken
+        ));
+    }
+}
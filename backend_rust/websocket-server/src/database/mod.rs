@@ -0,0 +1 @@
+pub mod postgres;
\ No newline at end of file
@@ -0,0 +1,195 @@
+use chrono::{DateTime, Utc};
+use shared::{connect_with_retry, AppConfig, AppResult, Location, SessionInfoData};
+use sqlx::PgPool;
+use tracing::{debug, info};
+use uuid::Uuid;
+
+/// Create a PostgreSQL connection pool, connecting on first use when
+/// `connect_lazy` is set
+///
+/// Otherwise failed connections are retried with backoff, like the
+/// api-server's, since Postgres may still be starting.
+pub async fn create_pool(config: &AppConfig) -> AppResult<PgPool> {
+    if config.database.connect_lazy {
+        info!("PostgreSQL connections will be opened on first use");
+        return Ok(config
+            .database_pool_options()
+            .connect_lazy_with(config.database_options()));
+    }
+
+    info!("Connecting to PostgreSQL database...");
+
+    let pool = connect_with_retry(&config.database, || {
+        config
+            .database_pool_options()
+            .connect_with(config.database_options())
+    })
+    .await?;
+
+    info!("Successfully connected to PostgreSQL database");
+    Ok(pool)
+}
+
+/// End sessions that have expired or gone idle for `inactivity`, returning
+/// their IDs and whether each had expired (rather than gone idle)
+///
+/// Uses the same inactivity rule as the api-server's
+/// `SessionRepository::get_sessions_to_auto_expire`, but selects and ends the
+/// sessions in a single transaction so a session cannot be revived between
+/// the two steps.
+pub async fn end_expired_sessions(
+    pool: &PgPool,
+    inactivity: chrono::Duration,
+) -> AppResult<Vec<(Uuid, bool)>> {
+    let mut tx = pool.begin().await?;
+    let cutoff = Utc::now() - inactivity;
+
+    let sessions = sqlx::query_as::<_, (Uuid, bool)>(
+        r#"
+        UPDATE sessions SET is_active = false
+        WHERE is_active = true
+        AND (
+            expires_at < NOW()
+            OR (
+                last_activity < $1
+                AND NOT EXISTS (
+                    SELECT 1 FROM participants
+                    WHERE participants.session_id = sessions.id
+                    AND participants.is_active = true
+                    AND participants.last_seen > $1
+                )
+            )
+        )
+        RETURNING id, expires_at < NOW()
+        "#,
+    )
+    .bind(cutoff)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let session_ids: Vec<Uuid> = sessions.iter().map(|(id, _)| *id).collect();
+    if !session_ids.is_empty() {
+        sqlx::query("UPDATE participants SET is_active = false WHERE session_id = ANY($1)")
+            .bind(&session_ids)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    tx.commit().await?;
+
+    debug!("Ended {} expired sessions", session_ids.len());
+    Ok(sessions)
+}
+
+/// Delete ended sessions whose last activity is older than `retention`,
+/// returning how many sessions and participants were removed
+///
+/// Participants are deleted explicitly, in the same transaction, so they can
+/// be counted. Audit events are kept; they are meant to outlive sessions.
+pub async fn purge_ended_sessions(
+    pool: &PgPool,
+    retention: chrono::Duration,
+) -> AppResult<(u64, u64)> {
+    let mut tx = pool.begin().await?;
+    let cutoff = Utc::now() - retention;
+
+    let participants = sqlx::query(
+        r#"
+        DELETE FROM participants
+        WHERE session_id IN (
+            SELECT id FROM sessions WHERE is_active = false AND last_activity < $1
+        )
+        "#,
+    )
+    .bind(cutoff)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    let sessions = sqlx::query("DELETE FROM sessions WHERE is_active = false AND last_activity < $1")
+        .bind(cutoff)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+    tx.commit().await?;
+
+    debug!("Purged {} ended sessions and {} participants", sessions, participants);
+    Ok((sessions, participants))
+}
+
+/// Active sessions expiring between now and `cutoff`, with their expiry times
+pub async fn sessions_expiring_before(
+    pool: &PgPool,
+    cutoff: DateTime<Utc>,
+) -> AppResult<Vec<(Uuid, DateTime<Utc>)>> {
+    let sessions = sqlx::query_as::<_, (Uuid, DateTime<Utc>)>(
+        r#"
+        SELECT id, expires_at FROM sessions
+        WHERE is_active = true
+        AND expires_at > NOW()
+        AND expires_at <= $1
+        "#,
+    )
+    .bind(cutoff)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(sessions)
+}
+
+/// A session's name, welcome message, expiry and visibility, as sent to connecting participants
+pub async fn session_info(pool: &PgPool, session_id: Uuid) -> AppResult<Option<SessionInfoData>> {
+    let session = sqlx::query_as::<_, (Option<String>, Option<String>, DateTime<Utc>, bool)>(
+        "SELECT name, welcome_message, expires_at, is_public FROM sessions WHERE id = $1",
+    )
+    .bind(session_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(session.map(|(name, welcome_message, expires_at, is_public)| SessionInfoData {
+        name,
+        welcome_message,
+        expires_at,
+        is_public,
+        participant_count: None,
+    }))
+}
+
+/// Whether a session sends waypoints between its participants' fixes
+pub async fn interpolates_locations(pool: &PgPool, session_id: Uuid) -> AppResult<bool> {
+    let enabled = sqlx::query_scalar::<_, bool>("SELECT interpolate_locations FROM sessions WHERE id = $1")
+        .bind(session_id)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(enabled.unwrap_or(false))
+}
+
+/// Insert accepted locations into the durable history in a single statement
+pub async fn insert_location_history(pool: &PgPool, locations: &[(Uuid, String, Location)]) -> AppResult<()> {
+    let session_ids: Vec<Uuid> = locations.iter().map(|(session_id, _, _)| *session_id).collect();
+    let user_ids: Vec<&str> = locations.iter().map(|(_, user_id, _)| user_id.as_str()).collect();
+    let lats: Vec<f64> = locations.iter().map(|(_, _, location)| location.lat).collect();
+    let lngs: Vec<f64> = locations.iter().map(|(_, _, location)| location.lng).collect();
+    let accuracies: Vec<f64> = locations.iter().map(|(_, _, location)| location.accuracy).collect();
+    let recorded_at: Vec<DateTime<Utc>> = locations.iter().map(|(_, _, location)| location.timestamp).collect();
+
+    sqlx::query(
+        r#"
+        INSERT INTO location_history (session_id, user_id, lat, lng, accuracy, recorded_at)
+        SELECT * FROM UNNEST($1::uuid[], $2::varchar[], $3::float8[], $4::float8[], $5::float8[], $6::timestamptz[])
+        "#,
+    )
+    .bind(&session_ids)
+    .bind(&user_ids)
+    .bind(&lats)
+    .bind(&lngs)
+    .bind(&accuracies)
+    .bind(&recorded_at)
+    .execute(pool)
+    .await?;
+
+    debug!("Wrote {} locations to the durable history", locations.len());
+    Ok(())
+}
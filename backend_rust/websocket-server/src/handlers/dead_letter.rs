@@ -0,0 +1,104 @@
+use chrono::{DateTime, Utc};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// Most recent unhandled messages kept for inspection
+const MAX_SAMPLES: usize = 50;
+
+/// Longest stored excerpt of an unhandled message, in characters
+const MAX_SAMPLE_CHARS: usize = 256;
+
+/// Longest stored message type, in characters
+const MAX_TYPE_CHARS: usize = 64;
+
+/// An unhandled client message, as kept in the dead-letter log
+#[derive(Debug, Clone)]
+pub struct DeadLetter {
+    pub received_at: DateTime<Utc>,
+    pub user_id: String,
+    /// The message's `type`, when it was a JSON object carrying one
+    pub message_type: Option<String>,
+    /// The start of the raw message
+    pub sample: String,
+}
+
+/// Count and recent samples of client messages the server couldn't handle
+///
+/// Shows which message types clients send that this server doesn't know,
+/// e.g. clients rolled out ahead of the server. Only the last few samples
+/// are kept, each truncated.
+#[derive(Debug, Default)]
+pub struct DeadLetterLog {
+    total: AtomicU64,
+    samples: Mutex<VecDeque<DeadLetter>>,
+}
+
+impl DeadLetterLog {
+    /// Record a message that was malformed or of an unhandled type
+    pub fn record(&self, user_id: &str, message: &str) {
+        self.total.fetch_add(1, Ordering::Relaxed);
+
+        let message_type = serde_json::from_str::<serde_json::Value>(message)
+            .ok()
+            .and_then(|value| value.get("type")?.as_str().map(|kind| truncate(kind, MAX_TYPE_CHARS)));
+        let dead_letter = DeadLetter {
+            received_at: Utc::now(),
+            user_id: user_id.to_string(),
+            message_type,
+            sample: truncate(message, MAX_SAMPLE_CHARS),
+        };
+
+        let mut samples = self.samples.lock().unwrap();
+        if samples.len() == MAX_SAMPLES {
+            samples.pop_front();
+        }
+        samples.push_back(dead_letter);
+    }
+
+    /// Unhandled messages received since startup
+    pub fn total(&self) -> u64 {
+        self.total.load(Ordering::Relaxed)
+    }
+
+    /// The most recent unhandled messages, oldest first
+    pub fn recent(&self) -> Vec<DeadLetter> {
+        self.samples.lock().unwrap().iter().cloned().collect()
+    }
+}
+
+fn truncate(value: &str, max_chars: usize) -> String {
+    match value.char_indices().nth(max_chars) {
+        Some((end, _)) => format!("{}…", &value[..end]),
+        None => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_samples_are_capped_and_truncated() {
+        let log = DeadLetterLog::default();
+        let long_type = "x".repeat(MAX_TYPE_CHARS * 2);
+        let long_message = format!(r#"{{"type":"{}","data":"{}"}}"#, long_type, "é".repeat(MAX_SAMPLE_CHARS));
+
+        for _ in 0..MAX_SAMPLES + 5 {
+            log.record("user", &long_message);
+        }
+        log.record("user", "not json");
+
+        assert_eq!(log.total(), MAX_SAMPLES as u64 + 6);
+        let recent = log.recent();
+        assert_eq!(recent.len(), MAX_SAMPLES);
+
+        let sample = &recent[0];
+        assert_eq!(sample.message_type.as_deref().map(|kind| kind.chars().count()), Some(MAX_TYPE_CHARS + 1));
+        assert_eq!(sample.sample.chars().count(), MAX_SAMPLE_CHARS + 1);
+
+        let last = recent.last().unwrap();
+        assert_eq!(last.message_type, None);
+        assert_eq!(last.sample, "not json");
+    }
+}
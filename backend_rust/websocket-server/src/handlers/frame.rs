@@ -0,0 +1,88 @@
+use serde_json::Value;
+use std::sync::OnceLock;
+use tokio_tungstenite::tungstenite::Message;
+
+/// A session broadcast's frame, shared by every recipient and encoded as
+/// MessagePack at most once however many of them negotiated it
+#[derive(Debug)]
+pub struct SharedFrame {
+    message: Message,
+    msgpack: OnceLock<Message>,
+}
+
+impl SharedFrame {
+    pub fn new(message: Message) -> Self {
+        Self {
+            message,
+            msgpack: OnceLock::new(),
+        }
+    }
+
+    /// The frame as sent to a connection, in MessagePack if it negotiated it
+    pub fn encoded(&self, msgpack: bool) -> Message {
+        if msgpack {
+            self.msgpack.get_or_init(|| to_msgpack_frame(self.message.clone())).clone()
+        } else {
+            self.message.clone()
+        }
+    }
+}
+
+/// Re-encode an outgoing JSON text frame as a MessagePack binary frame
+///
+/// Replies to a single connection are queued as JSON whatever it negotiated,
+/// so this runs on them just before writing; session broadcasts arrive
+/// already encoded through [`SharedFrame`]. Anything that isn't a JSON text
+/// frame is passed through unchanged.
+pub fn to_msgpack_frame(message: Message) -> Message {
+    let Message::Text(text) = &message else {
+        return message;
+    };
+    let Ok(value) = serde_json::from_str::<Value>(text) else {
+        return message;
+    };
+
+    match rmp_serde::to_vec_named(&value) {
+        Ok(bytes) => Message::Binary(bytes),
+        Err(_) => message,
+    }
+}
+
+/// Decode a MessagePack binary frame from a client into the equivalent JSON
+pub fn msgpack_to_json(bytes: &[u8]) -> Option<String> {
+    let value: Value = rmp_serde::from_slice(bytes).ok()?;
+    Some(value.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_msgpack_frames_mirror_json() {
+        let json = r#"{"type":"participant_count","data":{"count":3}}"#;
+        let Message::Binary(bytes) = to_msgpack_frame(Message::Text(json.to_string())) else {
+            panic!("Expected a binary frame");
+        };
+        let decoded: Value = serde_json::from_str(&msgpack_to_json(&bytes).unwrap()).unwrap();
+        assert_eq!(decoded, serde_json::from_str::<Value>(json).unwrap());
+
+        // Non-JSON text and control frames aren't touched
+        let plain = Message::Text("relayed".to_string());
+        assert_eq!(to_msgpack_frame(plain.clone()), plain);
+        assert_eq!(to_msgpack_frame(Message::Ping(vec![1])), Message::Ping(vec![1]));
+        assert_eq!(msgpack_to_json(b"\xc1"), None);
+    }
+
+    #[test]
+    fn test_shared_frame_encodes_msgpack_once() {
+        let json = r#"{"type":"participant_count","data":{"count":3}}"#;
+        let frame = SharedFrame::new(Message::Text(json.to_string()));
+
+        assert_eq!(frame.encoded(false), Message::Text(json.to_string()));
+        let first = frame.encoded(true);
+        assert_eq!(first, to_msgpack_frame(Message::Text(json.to_string())));
+        assert!(frame.msgpack.get().is_some());
+        assert_eq!(frame.encoded(true), first);
+    }
+}
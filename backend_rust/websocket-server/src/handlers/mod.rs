@@ -1 +1,5 @@
+pub mod dead_letter;
+pub mod frame;
+pub mod outbox;
+pub mod quality;
 pub mod websocket;
\ No newline at end of file
@@ -0,0 +1,242 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc::error::{SendError, TryRecvError};
+use tokio::sync::Notify;
+use tokio_tungstenite::tungstenite::Message;
+
+/// Create a connection's outgoing queue
+///
+/// Once `capacity` messages are waiting, e.g. because the client is on a
+/// slow link, a location broadcast queued with `send_location` replaces the
+/// one already queued for the same participant instead of adding to the
+/// backlog. Everything else (session events, errors, replies) is always queued.
+pub fn channel(capacity: usize) -> (OutboxSender, OutboxReceiver) {
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State {
+            queue: VecDeque::new(),
+            senders: 1,
+            receiver_alive: true,
+        }),
+        notify: Notify::new(),
+        capacity,
+    });
+
+    (
+        OutboxSender { shared: Arc::clone(&shared) },
+        OutboxReceiver { shared },
+    )
+}
+
+struct Shared {
+    state: Mutex<State>,
+    // Wakes the receiver when a message is queued or the last sender goes away
+    notify: Notify,
+    capacity: usize,
+}
+
+struct State {
+    queue: VecDeque<Queued>,
+    senders: usize,
+    receiver_alive: bool,
+}
+
+struct Queued {
+    message: Message,
+    /// Participant whose location this is, for location broadcasts
+    location_of: Option<String>,
+}
+
+/// Sending half of a connection's outgoing queue
+pub struct OutboxSender {
+    shared: Arc<Shared>,
+}
+
+impl OutboxSender {
+    /// Queue a message, failing only once the connection is gone
+    pub fn send(&self, message: Message) -> Result<(), SendError<Message>> {
+        self.push(message, None)
+    }
+
+    /// Queue a broadcast of `user_id`'s location, superseding their queued
+    /// locations once the connection is backed up
+    pub fn send_location(&self, message: Message, user_id: String) -> Result<(), SendError<Message>> {
+        self.push(message, Some(user_id))
+    }
+
+    fn push(&self, message: Message, location_of: Option<String>) -> Result<(), SendError<Message>> {
+        let mut state = self.shared.state.lock().unwrap();
+        if !state.receiver_alive {
+            return Err(SendError(message));
+        }
+
+        if state.queue.len() >= self.shared.capacity {
+            // Backed up: earlier positions of this participant are stale
+            if let Some(user_id) = &location_of {
+                state
+                    .queue
+                    .retain(|queued| queued.location_of.as_ref() != Some(user_id));
+            }
+        }
+
+        state.queue.push_back(Queued { message, location_of });
+        drop(state);
+        self.shared.notify.notify_one();
+        Ok(())
+    }
+
+    /// Whether both senders queue to the same connection
+    pub fn same_channel(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.shared, &other.shared)
+    }
+}
+
+impl Clone for OutboxSender {
+    fn clone(&self) -> Self {
+        self.shared.state.lock().unwrap().senders += 1;
+        Self {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl Drop for OutboxSender {
+    fn drop(&mut self) {
+        let mut state = self.shared.state.lock().unwrap();
+        state.senders -= 1;
+        if state.senders == 0 {
+            drop(state);
+            self.shared.notify.notify_one();
+        }
+    }
+}
+
+impl std::fmt::Debug for OutboxSender {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OutboxSender")
+            .field("capacity", &self.shared.capacity)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Receiving half of a connection's outgoing queue, drained by its writer
+pub struct OutboxReceiver {
+    shared: Arc<Shared>,
+}
+
+impl OutboxReceiver {
+    /// Wait for the next message, or `None` once every sender is gone and
+    /// the queue is empty
+    pub async fn recv(&mut self) -> Option<Message> {
+        loop {
+            match self.try_recv() {
+                Ok(message) => return Some(message),
+                Err(TryRecvError::Disconnected) => return None,
+                Err(TryRecvError::Empty) => self.shared.notify.notified().await,
+            }
+        }
+    }
+
+    /// Take the next message without waiting
+    pub fn try_recv(&mut self) -> Result<Message, TryRecvError> {
+        let mut state = self.shared.state.lock().unwrap();
+        match state.queue.pop_front() {
+            Some(queued) => Ok(queued.message),
+            None if state.senders == 0 => Err(TryRecvError::Disconnected),
+            None => Err(TryRecvError::Empty),
+        }
+    }
+}
+
+impl Drop for OutboxReceiver {
+    fn drop(&mut self) {
+        let mut state = self.shared.state.lock().unwrap();
+        state.receiver_alive = false;
+        state.queue.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use shared::{LocationBroadcastData, ParticipantCountData, WebSocketMessage};
+
+    /// Queue a broadcast of a participant's location
+    fn send_location(tx: &OutboxSender, user_id: &str, lat: f64) -> Result<(), SendError<Message>> {
+        let message = WebSocketMessage::LocationBroadcast(LocationBroadcastData {
+            user_id: user_id.to_string(),
+            lat,
+            lng: 0.0,
+            accuracy: 5.0,
+            timestamp: Utc::now(),
+        });
+        tx.send_location(Message::Text(serde_json::to_string(&message).unwrap()), user_id.to_string())
+    }
+
+    fn participant_count(count: usize) -> Message {
+        let message = WebSocketMessage::ParticipantCount(ParticipantCountData { count });
+        Message::Text(serde_json::to_string(&message).unwrap())
+    }
+
+    /// Decode everything queued so far
+    fn drain(rx: &mut OutboxReceiver) -> Vec<WebSocketMessage> {
+        std::iter::from_fn(|| rx.try_recv().ok())
+            .map(|message| match message {
+                Message::Text(text) => serde_json::from_str(&text).unwrap(),
+                other => panic!("Expected a text frame, got {:?}", other),
+            })
+            .collect()
+    }
+
+    fn locations(messages: &[WebSocketMessage]) -> Vec<(String, f64)> {
+        messages
+            .iter()
+            .filter_map(|message| match message {
+                WebSocketMessage::LocationBroadcast(data) => Some((data.user_id.clone(), data.lat)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_slow_consumer_keeps_latest_location_per_user() {
+        let (tx, mut rx) = channel(4);
+
+        // The client stops reading while peers keep moving
+        for step in 0..10 {
+            send_location(&tx, "alice", step as f64).unwrap();
+            send_location(&tx, "bob", 100.0 + step as f64).unwrap();
+            if step % 3 == 0 {
+                tx.send(participant_count(step)).unwrap();
+            }
+        }
+
+        let delivered = drain(&mut rx);
+        assert_eq!(
+            locations(&delivered),
+            vec![("alice".to_string(), 9.0), ("bob".to_string(), 109.0)]
+        );
+
+        // Session events are never dropped
+        let counts: Vec<usize> = delivered
+            .iter()
+            .filter_map(|message| match message {
+                WebSocketMessage::ParticipantCount(data) => Some(data.count),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(counts, vec![0, 3, 6, 9]);
+    }
+
+    #[test]
+    fn test_keeping_up_consumer_gets_every_location() {
+        let (tx, mut rx) = channel(4);
+        for step in 0..3 {
+            send_location(&tx, "alice", step as f64).unwrap();
+        }
+        assert_eq!(locations(&drain(&mut rx)).len(), 3);
+
+        drop(rx);
+        assert!(send_location(&tx, "alice", 3.0).is_err());
+    }
+}
@@ -0,0 +1,129 @@
+use chrono::{DateTime, Duration, Utc};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+/// Round-trip times kept for a connection's rolling average
+const MAX_RTT_SAMPLES: usize = 10;
+
+/// Round-trip time to a client, measured by `ping_probe`/`pong_probe`
+///
+/// One probe is outstanding at a time. A probe still unanswered when the
+/// next one is sent, or answered later than the timeout, counts as a
+/// timeout instead of a sample.
+#[derive(Debug, Default)]
+pub struct ConnectionQuality {
+    state: Mutex<QualityState>,
+}
+
+#[derive(Debug, Default)]
+struct QualityState {
+    last_nonce: u64,
+    /// Nonce and send time of the outstanding probe
+    pending: Option<(u64, DateTime<Utc>)>,
+    samples: VecDeque<Duration>,
+    timeouts: u64,
+}
+
+impl ConnectionQuality {
+    /// Start a probe sent at `sent_at`, returning its nonce
+    pub fn start_probe(&self, sent_at: DateTime<Utc>) -> u64 {
+        let mut state = self.state.lock().unwrap();
+        if state.pending.is_some() {
+            state.timeouts += 1;
+        }
+
+        state.last_nonce += 1;
+        state.pending = Some((state.last_nonce, sent_at));
+        state.last_nonce
+    }
+
+    /// Record the reply to a probe, returning its round-trip time
+    ///
+    /// Replies to a probe other than the outstanding one are ignored, and
+    /// replies arriving after `timeout` are counted as timeouts.
+    pub fn record_pong(&self, nonce: u64, received_at: DateTime<Utc>, timeout: Duration) -> Option<Duration> {
+        let mut state = self.state.lock().unwrap();
+        let (pending_nonce, sent_at) = state.pending?;
+        if pending_nonce != nonce {
+            return None;
+        }
+        state.pending = None;
+
+        let rtt = (received_at - sent_at).max(Duration::zero());
+        if rtt > timeout {
+            state.timeouts += 1;
+            return None;
+        }
+
+        if state.samples.len() == MAX_RTT_SAMPLES {
+            state.samples.pop_front();
+        }
+        state.samples.push_back(rtt);
+        Some(rtt)
+    }
+
+    /// Average of the recent round-trip times in milliseconds, once any were measured
+    pub fn average_rtt_ms(&self) -> Option<f64> {
+        let state = self.state.lock().unwrap();
+        if state.samples.is_empty() {
+            return None;
+        }
+
+        let total: i64 = state.samples.iter().map(Duration::num_milliseconds).sum();
+        Some(total as f64 / state.samples.len() as f64)
+    }
+
+    /// Probes that went unanswered or were answered too late
+    pub fn timeouts(&self) -> u64 {
+        self.state.lock().unwrap().timeouts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rolling_average_rtt() {
+        let quality = ConnectionQuality::default();
+        let start = Utc::now();
+        assert_eq!(quality.average_rtt_ms(), None);
+
+        for (i, rtt_ms) in [40, 60].into_iter().enumerate() {
+            let sent_at = start + Duration::seconds(i as i64);
+            let nonce = quality.start_probe(sent_at);
+            let rtt = quality.record_pong(nonce, sent_at + Duration::milliseconds(rtt_ms), Duration::seconds(10));
+            assert_eq!(rtt, Some(Duration::milliseconds(rtt_ms)));
+        }
+        assert_eq!(quality.average_rtt_ms(), Some(50.0));
+
+        // Only the most recent samples count
+        for i in 0..MAX_RTT_SAMPLES {
+            let sent_at = start + Duration::seconds(10 + i as i64);
+            let nonce = quality.start_probe(sent_at);
+            quality.record_pong(nonce, sent_at + Duration::milliseconds(20), Duration::seconds(10));
+        }
+        assert_eq!(quality.average_rtt_ms(), Some(20.0));
+        assert_eq!(quality.timeouts(), 0);
+    }
+
+    #[test]
+    fn test_missing_and_late_pongs_count_as_timeouts() {
+        let quality = ConnectionQuality::default();
+        let start = Utc::now();
+
+        // Unanswered when the next probe goes out
+        let missed = quality.start_probe(start);
+        let nonce = quality.start_probe(start + Duration::seconds(30));
+        assert_eq!(quality.timeouts(), 1);
+
+        // A reply to the superseded probe is ignored
+        assert_eq!(quality.record_pong(missed, start + Duration::seconds(31), Duration::seconds(10)), None);
+        assert_eq!(quality.timeouts(), 1);
+
+        // Answered after the timeout
+        assert_eq!(quality.record_pong(nonce, start + Duration::seconds(45), Duration::seconds(10)), None);
+        assert_eq!(quality.timeouts(), 2);
+        assert_eq!(quality.average_rtt_ms(), None);
+    }
+}
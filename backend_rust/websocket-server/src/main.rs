@@ -1,17 +1,32 @@
 use futures_util::{SinkExt, StreamExt};
-use shared::{AppConfig, AppResult};
+use shared::{bind_tcp, fmt_layer, AppConfig, AppError, AppResult, LocationPrecision, ParticipantRole};
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     net::SocketAddr,
-    sync::Arc,
+    ops::ControlFlow,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
 };
 use tokio::{
+    io::{AsyncRead, AsyncWrite},
     net::{TcpListener, TcpStream},
-    sync::{broadcast, RwLock},
+    sync::{
+        broadcast::{self, error::RecvError},
+        RwLock,
+    },
+    task::JoinHandle,
 };
+use sqlx::PgPool;
+use tokio_rustls::TlsAcceptor;
 use tokio_tungstenite::{
-    accept_hdr_async,
-    tungstenite::{handshake::server::Request, Message},
+    accept_hdr_async_with_config,
+    tungstenite::{
+        handshake::server::Request,
+        protocol::{frame::coding::CloseCode, CloseFrame, WebSocketConfig},
+        Error as WsError, Message,
+    },
     WebSocketStream,
 };
 use tracing::{error, info, warn};
@@ -20,87 +35,409 @@ use uuid::Uuid;
 
 mod auth;
 mod config;
+mod database;
 mod error;
 mod handlers;
 mod redis;
+mod tasks;
+mod tls;
 
-use auth::jwt::verify_jwt_token;
-use handlers::websocket::{handle_client_message, ConnectionInfo};
+use auth::jwt::{verify_jwt_token, verify_session_token};
+use database::postgres::create_pool;
+use handlers::dead_letter::DeadLetterLog;
+use handlers::frame::{msgpack_to_json, to_msgpack_frame, SharedFrame};
+use handlers::outbox::{self, OutboxSender};
+use handlers::websocket::{
+    clear_follow, close_frame, closing_error_message, handle_client_message, notify_participant_left,
+    schedule_participant_count, ConnectionInfo, ProtocolState,
+};
 use redis::client::RedisClient;
+use tasks::cleanup::spawn_cleanup_task;
+use tasks::dead_letter_report::spawn_dead_letter_report_task;
+use tasks::expiry_warning::spawn_expiry_warning_task;
+use tasks::location_writer::{spawn_location_writer_task, LocationWriter};
+use tasks::purge::spawn_purge_task;
+use tasks::pubsub::spawn_pubsub_task;
+use tasks::rtt_probe::spawn_rtt_probe_task;
+use tls::tls_acceptor;
+
+/// Capacity of each session's broadcast channel
+const SESSION_CHANNEL_CAPACITY: usize = 256;
+
+/// How often sessions are checked for upcoming expiry
+const EXPIRY_WARNING_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Longest an accepted location waits to be written to the durable history
+const LOCATION_WRITER_FLUSH_INTERVAL: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// How often unhandled client messages are summarized in the logs
+const DEAD_LETTER_REPORT_INTERVAL: std::time::Duration = std::time::Duration::from_secs(300);
+
+/// Message fanned out to every connection subscribed to a session
+#[derive(Debug, Clone)]
+struct SessionBroadcast {
+    frame: Arc<SharedFrame>,
+    exclude_user: Option<String>,
+    /// Participant whose location this is, for location broadcasts; these
+    /// are withheld from connections that paused receiving locations
+    location_of: Option<String>,
+}
+
+/// Broadcast channel and members of a single session
+struct SessionChannel {
+    sender: broadcast::Sender<SessionBroadcast>,
+    members: HashSet<String>,
+}
+
+/// Connection registered with the manager along with its session subscription
+struct ConnectionEntry {
+    info: ConnectionInfo,
+    forwarder: JoinHandle<()>,
+}
 
 /// WebSocket connection manager
 #[derive(Clone)]
 pub struct ConnectionManager {
-    connections: Arc<RwLock<HashMap<String, ConnectionInfo>>>,
+    connections: Arc<RwLock<HashMap<String, ConnectionEntry>>>,
+    // Per-session broadcast channels, so a broadcast only reaches that
+    // session's subscribers. Always locked after `connections`.
+    sessions: Arc<RwLock<HashMap<Uuid, SessionChannel>>>,
     redis: RedisClient,
+    // Cleared when a Redis operation fails at the connection level
+    redis_available: Arc<AtomicBool>,
+    // Connections holding a slot, from handshake until disconnect
+    connection_count: Arc<AtomicUsize>,
+    // Sessions with a participant count update waiting to be sent
+    pending_counts: Arc<std::sync::Mutex<HashSet<Uuid>>>,
+    // Client messages that were malformed or of an unhandled type
+    dead_letters: Arc<DeadLetterLog>,
+    // Source of session details sent on connect, if configured
+    db: Option<PgPool>,
+    // Durable history of accepted locations, when persistence is enabled
+    location_writer: Option<LocationWriter>,
     config: Arc<AppConfig>,
-    // Broadcast channel for sending messages to all connections
-    broadcast_tx: broadcast::Sender<(Uuid, String)>, // (session_id, message)
+}
+
+/// A reserved place under the connection cap, released when dropped
+pub struct ConnectionSlot {
+    count: Arc<AtomicUsize>,
+}
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.count.fetch_sub(1, Ordering::AcqRel);
+    }
 }
 
 impl ConnectionManager {
     pub fn new(redis: RedisClient, config: Arc<AppConfig>) -> Self {
-        let (broadcast_tx, _) = broadcast::channel(1000);
-        
         Self {
             connections: Arc::new(RwLock::new(HashMap::new())),
+            sessions: Arc::new(RwLock::new(HashMap::new())),
             redis,
+            redis_available: Arc::new(AtomicBool::new(true)),
+            connection_count: Arc::new(AtomicUsize::new(0)),
+            pending_counts: Arc::new(std::sync::Mutex::new(HashSet::new())),
+            dead_letters: Arc::new(DeadLetterLog::default()),
+            db: None,
+            location_writer: None,
             config,
-            broadcast_tx,
         }
     }
 
-    /// Add a new connection
-    pub async fn add_connection(&self, user_id: String, session_id: Uuid, info: ConnectionInfo) {
+    /// Look up session details in this database, so connecting participants
+    /// are sent their session's name and welcome message
+    pub fn with_database(mut self, db: PgPool) -> Self {
+        self.db = Some(db);
+        self
+    }
+
+    /// Also write accepted locations to the durable history through `writer`
+    pub fn with_location_writer(mut self, writer: LocationWriter) -> Self {
+        self.location_writer = Some(writer);
+        self
+    }
+
+    /// Reserve a slot for a new connection, or `None` once the configured
+    /// maximum number of connections is reached
+    pub fn try_reserve_slot(&self) -> Option<ConnectionSlot> {
+        let max = self.config.app.max_total_connections;
+        self.connection_count
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |count| {
+                (count < max).then_some(count + 1)
+            })
+            .ok()?;
+
+        Some(ConnectionSlot {
+            count: Arc::clone(&self.connection_count),
+        })
+    }
+
+    /// Number of connections currently holding a slot
+    pub fn connection_count(&self) -> usize {
+        self.connection_count.load(Ordering::Acquire)
+    }
+
+    /// Add a new connection and subscribe it to its session's channel
+    ///
+    /// A user already connected here has their previous connection told it
+    /// was replaced and closed, or with `single_connection_per_user` the new
+    /// connection is refused with `AppError::AlreadyConnected` instead.
+    pub async fn add_connection(&self, user_id: String, session_id: Uuid, info: ConnectionInfo) -> AppResult<()> {
         let mut connections = self.connections.write().await;
-        connections.insert(user_id.clone(), info);
+        if connections.contains_key(&user_id) && self.config.app.single_connection_per_user {
+            return Err(AppError::AlreadyConnected);
+        }
+        let mut sessions = self.sessions.write().await;
+
+        if let Some(previous) = connections.remove(&user_id) {
+            previous.forwarder.abort();
+            Self::leave_session(&mut sessions, &previous.info.session_id, &user_id);
+
+            // The previous entry is already gone, so nothing here may fail the new connection
+            let replaced = AppError::ConnectionReplaced;
+            if let Ok(message) = closing_error_message(&replaced) {
+                let _ = previous.info.sender.send(message);
+            }
+            let _ = previous.info.sender.send(Message::Close(Some(close_frame(&replaced))));
+        }
+
+        let channel = sessions.entry(session_id).or_insert_with(|| SessionChannel {
+            sender: broadcast::channel(SESSION_CHANNEL_CAPACITY).0,
+            members: HashSet::new(),
+        });
+        channel.members.insert(user_id.clone());
+
+        let forwarder = spawn_session_forwarder(
+            user_id.clone(),
+            channel.sender.subscribe(),
+            info.sender.clone(),
+            Arc::clone(&info.receive_paused),
+            Arc::clone(&info.msgpack_frames),
+        );
+        connections.insert(user_id.clone(), ConnectionEntry { info, forwarder });
+        drop(sessions);
+        drop(connections);
         
         // Update Redis connection mapping
         if let Err(e) = self.redis.set_connection(&user_id, &session_id).await {
             error!("Failed to update Redis connection mapping: {}", e);
         }
+        Ok(())
     }
 
-    /// Remove a connection
+    /// Remove a connection and unsubscribe it from its session's channel
     pub async fn remove_connection(&self, user_id: &str) {
+        self.remove_connection_entry(user_id, None).await;
+    }
+
+    /// Remove the user's connection if it is the one queueing to `sender`,
+    /// leaving alone a connection that has since replaced it
+    ///
+    /// Returns whether the connection was removed.
+    pub async fn remove_own_connection(&self, user_id: &str, sender: &OutboxSender) -> bool {
+        self.remove_connection_entry(user_id, Some(sender)).await
+    }
+
+    async fn remove_connection_entry(&self, user_id: &str, sender: Option<&OutboxSender>) -> bool {
         let mut connections = self.connections.write().await;
-        if let Some(info) = connections.remove(user_id) {
-            // Remove from Redis
-            if let Err(e) = self.redis.remove_connection(user_id).await {
-                error!("Failed to remove Redis connection mapping: {}", e);
-            }
-            
-            // Remove from session participants
-            if let Err(e) = self.redis.remove_from_session_participants(&info.session_id, user_id).await {
-                error!("Failed to remove from session participants: {}", e);
+        let is_own = |entry: &ConnectionEntry| sender.is_none_or(|sender| entry.info.sender.same_channel(sender));
+        if !connections.get(user_id).is_some_and(is_own) {
+            return false;
+        }
+        let Some(entry) = connections.remove(user_id) else {
+            return false;
+        };
+        entry.forwarder.abort();
+        Self::leave_session(&mut *self.sessions.write().await, &entry.info.session_id, user_id);
+        drop(connections);
+
+        // Remove from Redis
+        if let Err(e) = self.redis.remove_connection(user_id).await {
+            error!("Failed to remove Redis connection mapping: {}", e);
+        }
+        
+        // Remove from session participants
+        if let Err(e) = self.redis.remove_from_session_participants(&entry.info.session_id, user_id).await {
+            error!("Failed to remove from session participants: {}", e);
+        }
+        true
+    }
+
+    /// Drop a member from a session channel, discarding the channel once empty
+    fn leave_session(sessions: &mut HashMap<Uuid, SessionChannel>, session_id: &Uuid, user_id: &str) {
+        if let Some(channel) = sessions.get_mut(session_id) {
+            channel.members.remove(user_id);
+            if channel.members.is_empty() {
+                sessions.remove(session_id);
             }
         }
     }
 
     /// Broadcast message to all connections in a session
     pub async fn broadcast_to_session(&self, session_id: Uuid, message: String, exclude_user: Option<&str>) {
+        let sessions = self.sessions.read().await;
+        let Some(channel) = sessions.get(&session_id) else {
+            return;
+        };
+
+        // Sending only fails when the session has no subscribers left
+        let _ = channel.sender.send(SessionBroadcast {
+            location_of: location_broadcast_user(&message),
+            frame: Arc::new(SharedFrame::new(Message::Text(message))),
+            exclude_user: exclude_user.map(str::to_string),
+        });
+    }
+
+    /// Send a message to a participant connected to this instance, returning
+    /// `false` if they aren't connected here in that session
+    pub async fn send_to_participant(&self, session_id: Uuid, user_id: &str, message: String) -> bool {
         let connections = self.connections.read().await;
-        
-        for (user_id, connection_info) in connections.iter() {
-            if connection_info.session_id == session_id {
-                if let Some(exclude) = exclude_user {
-                    if user_id == exclude {
-                        continue;
-                    }
+        let Some(entry) = connections.get(user_id).filter(|entry| entry.info.session_id == session_id) else {
+            return false;
+        };
+
+        if let Err(e) = entry.info.sender.send(Message::Text(message)) {
+            warn!("Failed to send message to user {}: {}", user_id, e);
+        }
+        true
+    }
+
+    /// Close every connection in a session with the close code for `error`
+    pub async fn close_session(&self, session_id: Uuid, error: &AppError) {
+        let sessions = self.sessions.read().await;
+        let Some(channel) = sessions.get(&session_id) else {
+            return;
+        };
+
+        let _ = channel.sender.send(SessionBroadcast {
+            frame: Arc::new(SharedFrame::new(Message::Close(Some(close_frame(error))))),
+            exclude_user: None,
+            location_of: None,
+        });
+    }
+
+    /// Number of connections subscribed to a session's channel
+    pub async fn session_subscriber_count(&self, session_id: &Uuid) -> usize {
+        let sessions = self.sessions.read().await;
+        sessions
+            .get(session_id)
+            .map_or(0, |channel| channel.members.len())
+    }
+
+    /// Whether Redis was reachable as of the last recorded operation
+    pub fn is_redis_available(&self) -> bool {
+        self.redis_available.load(Ordering::Relaxed)
+    }
+
+    /// Record the outcome of a Redis operation, logging availability changes
+    pub fn record_redis_result<T>(&self, result: &AppResult<T>) {
+        match result {
+            Ok(_) => {
+                if !self.redis_available.swap(true, Ordering::Relaxed) {
+                    info!("Redis connection restored");
                 }
-                
-                if let Err(e) = connection_info.sender.send(Message::Text(message.clone())) {
-                    warn!("Failed to send message to user {}: {}", user_id, e);
+            }
+            Err(e) if e.is_redis_connection_error() => {
+                if self.redis_available.swap(false, Ordering::Relaxed) {
+                    error!("Redis connection lost: {}", e);
                 }
             }
+            Err(_) => {}
         }
     }
 
+    /// Mark a session's participant count as needing an update, returning
+    /// `false` if an update is already pending
+    pub fn mark_count_pending(&self, session_id: Uuid) -> bool {
+        self.pending_counts.lock().unwrap().insert(session_id)
+    }
+
+    /// Clear a session's pending participant count update
+    pub fn clear_count_pending(&self, session_id: &Uuid) {
+        self.pending_counts.lock().unwrap().remove(session_id);
+    }
+
+    /// Info for every connection on this instance
+    pub async fn all_connections(&self) -> Vec<ConnectionInfo> {
+        let connections = self.connections.read().await;
+        connections.values().map(|entry| entry.info.clone()).collect()
+    }
+
     /// Get connection info for a user
     pub async fn get_connection(&self, user_id: &str) -> Option<ConnectionInfo> {
         let connections = self.connections.read().await;
-        connections.get(user_id).cloned()
+        connections.get(user_id).map(|entry| entry.info.clone())
+    }
+}
+
+/// User ID of a broadcast carrying a participant's location, judged by its
+/// type tag so messages relayed from other instances are recognised too
+fn location_broadcast_user(message: &str) -> Option<String> {
+    #[derive(serde::Deserialize)]
+    struct Tagged<'a> {
+        #[serde(rename = "type", borrow)]
+        kind: &'a str,
+        data: Option<LocationOf>,
     }
+
+    #[derive(serde::Deserialize)]
+    struct LocationOf {
+        user_id: String,
+    }
+
+    let tagged: Tagged = serde_json::from_str(message).ok()?;
+    if !matches!(tagged.kind, "location_broadcast" | "location_path") {
+        return None;
+    }
+    tagged.data.map(|data| data.user_id)
+}
+
+/// Forward a session's broadcasts to a single connection's outgoing queue
+///
+/// A connection that falls behind the session's channel has missed
+/// broadcasts, possibly session events, so it is closed with 1013 for the
+/// client to reconnect and resync rather than carry on out of step.
+fn spawn_session_forwarder(
+    user_id: String,
+    mut receiver: broadcast::Receiver<SessionBroadcast>,
+    sender: OutboxSender,
+    receive_paused: Arc<AtomicBool>,
+    msgpack_frames: Arc<AtomicBool>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            match receiver.recv().await {
+                Ok(broadcast) => {
+                    if broadcast.exclude_user.as_deref() == Some(user_id.as_str()) {
+                        continue;
+                    }
+                    if broadcast.location_of.is_some() && receive_paused.load(Ordering::Relaxed) {
+                        continue;
+                    }
+                    let message = broadcast.frame.encoded(msgpack_frames.load(Ordering::Relaxed));
+                    let sent = match broadcast.location_of {
+                        Some(user_id) => sender.send_location(message, user_id),
+                        None => sender.send(message),
+                    };
+                    if let Err(e) = sent {
+                        warn!("Failed to send message to user {}: {}", user_id, e);
+                        break;
+                    }
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("User {} missed {} session broadcasts; closing the connection", user_id, skipped);
+                    let lagged = AppError::service_unavailable("session broadcasts");
+                    if let Ok(message) = closing_error_message(&lagged) {
+                        let _ = sender.send(message);
+                    }
+                    let _ = sender.send(Message::Close(Some(close_frame(&lagged))));
+                    break;
+                }
+                Err(RecvError::Closed) => break,
+            }
+        }
+    })
 }
 
 #[tokio::main]
@@ -126,33 +463,87 @@ async fn main() -> AppResult<()> {
     info!("Starting WebSocket server with configuration: {}", config);
 
     // Create Redis client
-    let redis_client = RedisClient::new(&config.redis.url).await?;
+    let redis_client = RedisClient::new(&config.redis.url)
+        .await?
+        .with_key_prefix(&config.redis.key_prefix)
+        .with_max_publish_bytes(config.redis.max_publish_bytes)
+        .with_location_history_limits(
+            config.app.location_history_max_points,
+            config.app.location_history_ttl_seconds,
+        );
+
+    // Create database connection pool
+    let db = create_pool(&config).await?;
 
     // Create connection manager
-    let connection_manager = ConnectionManager::new(redis_client, Arc::clone(&config));
+    let mut connection_manager = ConnectionManager::new(redis_client, Arc::clone(&config))
+        .with_database(db.clone());
 
-    // Start Redis subscriber for broadcasting messages
-    let redis_subscriber = connection_manager.redis.clone();
-    let broadcast_manager = connection_manager.clone();
-    tokio::spawn(async move {
-        if let Err(e) = handle_redis_messages(redis_subscriber, broadcast_manager).await {
-            error!("Redis message handler error: {}", e);
-        }
-    });
+    // Keep a durable location history in Postgres, if the operator opted in
+    if config.app.persist_locations {
+        let (writer, _) = spawn_location_writer_task(db.clone(), LOCATION_WRITER_FLUSH_INTERVAL);
+        connection_manager = connection_manager.with_location_writer(writer);
+    }
+
+    // Start periodic cleanup of expired sessions and locations
+    spawn_cleanup_task(
+        connection_manager.clone(),
+        db.clone(),
+        chrono::Duration::minutes(config.app.session_inactivity_minutes),
+        std::time::Duration::from_secs(config.app.session_cleanup_interval_minutes * 60),
+    );
+
+    // Delete long-ended sessions, if the operator opted in
+    if config.app.session_purge_enabled {
+        spawn_purge_task(
+            db.clone(),
+            chrono::Duration::days(config.app.session_retention_days),
+            std::time::Duration::from_secs(config.app.session_purge_interval_minutes * 60),
+        );
+    }
+
+    // Warn participants shortly before their session expires
+    spawn_expiry_warning_task(
+        connection_manager.clone(),
+        db,
+        chrono::Duration::minutes(config.app.session_expiry_warning_minutes),
+        EXPIRY_WARNING_CHECK_INTERVAL,
+    );
+
+    // Start Redis subscriber for broadcasting messages from other instances
+    spawn_pubsub_task(connection_manager.clone());
+
+    // Summarize messages clients send that this server can't handle
+    spawn_dead_letter_report_task(connection_manager.clone(), DEAD_LETTER_REPORT_INTERVAL);
+
+    // Measure round-trip times to connected clients
+    if config.app.rtt_probe_interval_seconds > 0 {
+        spawn_rtt_probe_task(
+            connection_manager.clone(),
+            std::time::Duration::from_secs(config.app.rtt_probe_interval_seconds),
+        );
+    }
 
     // Create server address
-    let addr = config.ws_address();
-    info!("WebSocket server listening on {}", addr);
+    let addr = config
+        .ws_address()
+        .map_err(|e| AppError::Internal(anyhow::anyhow!(e)))?;
+
+    // Serve wss:// directly when a certificate is configured
+    let tls = tls_acceptor(&config.server)?;
+    let scheme = if tls.is_some() { "wss" } else { "ws" };
+    info!("WebSocket server listening on {} ({})", addr, scheme);
 
     // Start the server
-    let listener = TcpListener::bind(&addr).await?;
+    let listener = TcpListener::from_std(bind_tcp(addr)?)?;
     
     while let Ok((stream, addr)) = listener.accept().await {
         let connection_manager = connection_manager.clone();
         let config = Arc::clone(&config);
+        let tls = tls.clone();
         
         tokio::spawn(async move {
-            if let Err(e) = handle_connection(stream, addr, connection_manager, config).await {
+            if let Err(e) = handle_stream(stream, addr, tls, connection_manager, config).await {
                 error!("Connection error from {}: {}", addr, e);
             }
         });
@@ -161,20 +552,65 @@ async fn main() -> AppResult<()> {
     Ok(())
 }
 
-/// Handle incoming WebSocket connection
-async fn handle_connection(
+/// Complete the TLS handshake when serving wss://, then handle the
+/// WebSocket connection
+///
+/// Plaintext clients of a TLS server fail the handshake and are dropped.
+async fn handle_stream(
     stream: TcpStream,
     addr: SocketAddr,
+    tls: Option<TlsAcceptor>,
     connection_manager: ConnectionManager,
     config: Arc<AppConfig>,
 ) -> AppResult<()> {
+    match tls {
+        Some(acceptor) => {
+            let stream = acceptor
+                .accept(stream)
+                .await
+                .map_err(|e| AppError::websocket(&format!("TLS handshake failed: {}", e)))?;
+            handle_connection(stream, addr, connection_manager, config).await
+        }
+        None => handle_connection(stream, addr, connection_manager, config).await,
+    }
+}
+
+/// Handle incoming WebSocket connection
+async fn handle_connection<S>(
+    stream: S,
+    addr: SocketAddr,
+    connection_manager: ConnectionManager,
+    config: Arc<AppConfig>,
+) -> AppResult<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
     info!("New connection from: {}", addr);
 
+    // Held until the connection closes so it counts against the cap
+    let slot = connection_manager.try_reserve_slot();
+
     let mut claims_holder: Option<shared::JwtClaims> = None;
     let config_clone = Arc::clone(&config);
 
     // Accept WebSocket connection with JWT token verification
-    let ws_stream = accept_hdr_async(stream, |req: &Request, response| {
+    let ws_stream = accept_hdr_async_with_config(stream, |req: &Request, response| {
+        if req.uri().path() != config_clone.app.ws_path {
+            warn!("Rejecting WebSocket connection from {}: unknown path {}", addr, req.uri().path());
+            return Err(http::Response::builder()
+                .status(404)
+                .body(Some("Not found".to_string()))
+                .unwrap());
+        }
+
+        if slot.is_none() {
+            warn!("Rejecting WebSocket connection from {}: connection limit reached", addr);
+            return Err(http::Response::builder()
+                .status(503)
+                .body(Some("Server at connection capacity".to_string()))
+                .unwrap());
+        }
+
         // Extract JWT token from query parameters
         let uri = req.uri();
         let query = uri.query().unwrap_or("");
@@ -190,20 +626,33 @@ async fn handle_connection(
             })
             .collect();
 
-        // Verify JWT token
+        // Verify JWT token, binding it to the requested session when one is given
         if let Some(token) = params.get("token") {
-            match verify_jwt_token(token, &config_clone.jwt.secret) {
+            let jwt = &config_clone.jwt;
+            let verified = jwt
+                .validation()
+                .map_err(|e| shared::AppError::Internal(anyhow::anyhow!(e)))
+                .and_then(|validation| match params.get("session_id") {
+                    Some(session_id) => Uuid::parse_str(session_id)
+                        .map_err(shared::AppError::from)
+                        .and_then(|session_id| {
+                            verify_session_token(token, &jwt.secret, &validation, &session_id)
+                        }),
+                    None => verify_jwt_token(token, &jwt.secret, &validation),
+                });
+
+            match verified {
                 Ok(claims) => {
                     info!("Authenticated WebSocket connection for user: {}", claims.sub);
-                    // Store claims for later use (this is a workaround for the closure limitation)
-                    // In production, consider using a thread-safe approach
+                    claims_holder = Some(claims);
                     Ok(response)
                 }
                 Err(e) => {
                     warn!("WebSocket authentication failed: {}", e);
+                    let status = if e.is_client_error() { e.status_code() } else { 401 };
                     Err(http::Response::builder()
-                        .status(401)
-                        .body(Some("Unauthorized".to_string()))
+                        .status(status)
+                        .body(Some(e.to_string()))
                         .unwrap())
                 }
             }
@@ -214,67 +663,131 @@ async fn handle_connection(
                 .body(Some("Token required".to_string()))
                 .unwrap())
         }
-    }).await.map_err(|e| shared::AppError::websocket(&e.to_string()))?;
+    }, Some(ws_config(&config))).await.map_err(|e| shared::AppError::websocket(&e.to_string()))?;
 
-    // For now, we'll use a placeholder approach for the claims
-    // In production, you'd want to properly extract and validate the token
-    // This is a limitation of the current architecture that should be addressed
-    warn!("Using placeholder JWT claims - this should be fixed in production");
-    let user_id = format!("user_{}", uuid::Uuid::new_v4().to_string()[..8].to_string());
-    let session_id = Uuid::new_v4(); // This should come from the JWT token
+    let claims = claims_holder.ok_or(shared::AppError::InvalidToken)?;
+    let user_id = claims.sub;
+    let session_id = claims.session_id;
 
     info!("WebSocket connection established for user {} in session {}", user_id, session_id);
 
     // Handle the WebSocket connection
-    handle_websocket_connection(ws_stream, user_id, session_id, connection_manager).await
+    handle_websocket_connection(ws_stream, user_id, session_id, claims.precision, claims.role, connection_manager).await
+}
+
+/// Limits on what a client may send, so one oversized message can't force
+/// a large allocation
+fn ws_config(config: &AppConfig) -> WebSocketConfig {
+    WebSocketConfig {
+        max_message_size: Some(config.app.max_ws_message_bytes),
+        max_frame_size: Some(config.app.max_ws_frame_bytes),
+        ..Default::default()
+    }
 }
 
 /// Handle WebSocket messages for a specific connection
-async fn handle_websocket_connection(
-    ws_stream: WebSocketStream<TcpStream>,
+async fn handle_websocket_connection<S>(
+    ws_stream: WebSocketStream<S>,
     user_id: String,
     session_id: Uuid,
+    precision: LocationPrecision,
+    role: ParticipantRole,
     connection_manager: ConnectionManager,
-) -> AppResult<()> {
+) -> AppResult<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
     let (mut ws_sender, mut ws_receiver) = ws_stream.split();
-    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let (tx, mut rx) = outbox::channel(connection_manager.config.app.outgoing_queue_capacity);
+    let own_sender = tx.clone();
 
     // Create connection info
+    let msgpack_frames = Arc::new(AtomicBool::new(false));
     let connection_info = ConnectionInfo {
         user_id: user_id.clone(),
         session_id,
         sender: tx,
+        receive_paused: Default::default(),
+        msgpack_frames: Arc::clone(&msgpack_frames),
+        quality: Default::default(),
+        connected_at: chrono::Utc::now(),
     };
 
     // Add connection to manager
-    connection_manager.add_connection(user_id.clone(), session_id, connection_info).await;
+    if let Err(e) = connection_manager.add_connection(user_id.clone(), session_id, connection_info.clone()).await {
+        warn!("Refusing connection for user {}: {}", user_id, e);
+        ws_sender.send(closing_error_message(&e)?).await.map_err(|e| AppError::websocket(&e.to_string()))?;
+        ws_sender
+            .send(Message::Close(Some(close_frame(&e))))
+            .await
+            .map_err(|e| AppError::websocket(&e.to_string()))?;
+        return Ok(());
+    }
 
     // Add to session participants in Redis
     if let Err(e) = connection_manager.redis.add_to_session_participants(&session_id, &user_id).await {
         error!("Failed to add participant to Redis: {}", e);
     }
+    // A new stay, whose departure is announced even if an earlier one was
+    if let Err(e) = connection_manager.redis.clear_participant_left(&session_id, &user_id).await {
+        error!("Failed to clear departure tombstone for {}: {}", user_id, e);
+    }
+    schedule_participant_count(session_id, &connection_manager);
 
-    // Handle outgoing messages
+    // Handle outgoing messages, re-encoding replies for clients that negotiated
+    // msgpack; session broadcasts are already encoded for them
     let outgoing_task = tokio::spawn(async move {
         while let Some(message) = rx.recv().await {
+            let message = if msgpack_frames.load(Ordering::Relaxed) {
+                to_msgpack_frame(message)
+            } else {
+                message
+            };
+            // Nothing more goes out once the connection is being closed
+            let closing = matches!(message, Message::Close(_));
             if let Err(e) = ws_sender.send(message).await {
                 error!("Failed to send WebSocket message: {}", e);
                 break;
             }
+            if closing {
+                break;
+            }
         }
     });
 
     // Handle incoming messages
-    let incoming_task = {
+    let mut incoming_task = {
         let connection_manager = connection_manager.clone();
         let user_id = user_id.clone();
+        // Replies go on this connection's own channel; once replaced, the
+        // user's registered connection is the new one
+        let connection_info = connection_info.clone();
+        let own_sender = own_sender.clone();
         
         tokio::spawn(async move {
+            let mut protocol_state = ProtocolState {
+                precision,
+                role,
+                ..Default::default()
+            };
             while let Some(msg) = ws_receiver.next().await {
                 match msg {
                     Ok(Message::Text(text)) => {
-                        if let Err(e) = handle_client_message(&text, &user_id, session_id, &connection_manager).await {
-                            error!("Error handling client message: {}", e);
+                        match handle_client_message(&text, &connection_info, &mut protocol_state, &connection_manager).await {
+                            Ok(ControlFlow::Break(())) => break,
+                            Ok(ControlFlow::Continue(())) => {}
+                            Err(e) => error!("Error handling client message: {}", e),
+                        }
+                    }
+                    Ok(Message::Binary(bytes)) => {
+                        // MessagePack is handled as its JSON equivalent; anything
+                        // else is reported like any other malformed message
+                        let text = msgpack_to_json(&bytes)
+                            .unwrap_or_else(|| String::from_utf8_lossy(&bytes).into_owned());
+                        match handle_client_message(&text, &connection_info, &mut protocol_state, &connection_manager).await {
+                            Ok(ControlFlow::Break(())) => break,
+                            Ok(ControlFlow::Continue(())) => {}
+                            Err(e) => error!("Error handling client message: {}", e),
                         }
                     }
                     Ok(Message::Close(_)) => {
@@ -283,9 +796,15 @@ async fn handle_websocket_connection(
                     }
                     Ok(Message::Ping(data)) => {
                         // Echo ping as pong
-                        if let Some(connection_info) = connection_manager.get_connection(&user_id).await {
-                            let _ = connection_info.sender.send(Message::Pong(data));
-                        }
+                        let _ = own_sender.send(Message::Pong(data));
+                    }
+                    Err(WsError::Capacity(e)) => {
+                        warn!("Closing connection for user {}: {}", user_id, e);
+                        let _ = own_sender.send(Message::Close(Some(CloseFrame {
+                            code: CloseCode::Size,
+                            reason: e.to_string().into(),
+                        })));
+                        break;
                     }
                     Err(e) => {
                         error!("WebSocket error for user {}: {}", user_id, e);
@@ -302,40 +821,26 @@ async fn handle_websocket_connection(
         _ = outgoing_task => {
             info!("Outgoing task completed for user: {}", user_id);
         }
-        _ = incoming_task => {
+        _ = &mut incoming_task => {
             info!("Incoming task completed for user: {}", user_id);
         }
     }
+    // A replaced connection's client must not keep acting on the session
+    incoming_task.abort();
 
-    // Clean up connection
-    connection_manager.remove_connection(&user_id).await;
-    info!("WebSocket connection closed for user: {}", user_id);
-
-    Ok(())
-}
-
-/// Handle Redis pub/sub messages for broadcasting
-async fn handle_redis_messages(
-    redis_client: RedisClient,
-    connection_manager: ConnectionManager,
-) -> AppResult<()> {
-    use futures_util::StreamExt;
-    
-    let mut pubsub = redis_client.subscribe_to_sessions().await?;
-    
-    let mut message_stream = pubsub.on_message();
-    while let Some(msg) = message_stream.next().await {
-        let channel = msg.get_channel_name().to_string();
-        let data: String = msg.get_payload().unwrap_or_default();
-        
-        // Extract session ID from channel name (format: "channel:session:{session_id}")
-        if let Some(session_id_str) = channel.strip_prefix("channel:session:") {
-            if let Ok(session_id) = Uuid::parse_str(session_id_str) {
-                connection_manager.broadcast_to_session(session_id, data, None).await;
-            }
+    // Clean up connection, unless it was replaced and the user is still connected
+    if connection_manager.remove_own_connection(&user_id, &own_sender).await {
+        if let Err(e) = clear_follow(&connection_info, &connection_manager).await {
+            error!("Failed to clear follow for user {}: {}", user_id, e);
+        }
+        // Announced once, even if the user also left through the API
+        if let Err(e) = notify_participant_left(session_id, &user_id, &connection_manager).await {
+            error!("Failed to announce departure of user {}: {}", user_id, e);
         }
     }
-    
+    schedule_participant_count(session_id, &connection_manager);
+    info!("WebSocket connection closed for user: {}", user_id);
+
     Ok(())
 }
 
@@ -348,8 +853,524 @@ fn init_logging(config: &AppConfig) -> AppResult<()> {
             tracing_subscriber::EnvFilter::try_from_default_env()
                 .unwrap_or_else(|_| format!("websocket_server={}", log_level).into()),
         )
-        .with(tracing_subscriber::fmt::layer().with_target(false))
+        .with(fmt_layer(config, std::io::stdout))
         .init();
 
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    async fn test_connection_manager() -> ConnectionManager {
+        let redis_url = std::env::var("TEST_REDIS_URL")
+            .unwrap_or_else(|_| "redis://localhost:6379".to_string());
+        let redis = RedisClient::new(&redis_url)
+            .await
+            .expect("Failed to connect to test Redis");
+
+        ConnectionManager::new(redis, Arc::new(AppConfig::default()))
+    }
+
+    async fn connect(
+        connection_manager: &ConnectionManager,
+        session_id: Uuid,
+    ) -> (String, outbox::OutboxReceiver) {
+        let (tx, rx) = outbox::channel(64);
+        let user_id = Uuid::new_v4().to_string();
+        connection_manager
+            .add_connection(
+                user_id.clone(),
+                session_id,
+                ConnectionInfo {
+                    user_id: user_id.clone(),
+                    session_id,
+                    sender: tx,
+                    receive_paused: Default::default(),
+                    msgpack_frames: Default::default(),
+                    quality: Default::default(),
+                    connected_at: chrono::Utc::now(),
+                },
+            )
+            .await
+            .unwrap();
+
+        (user_id, rx)
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_only_reaches_target_session() {
+        let connection_manager = test_connection_manager().await;
+        let session_a = Uuid::new_v4();
+        let session_b = Uuid::new_v4();
+
+        let (sender_id, mut sender_rx) = connect(&connection_manager, session_a).await;
+        let (_, mut peer_rx) = connect(&connection_manager, session_a).await;
+
+        let mut session_b_connections = Vec::new();
+        for _ in 0..200 {
+            session_b_connections.push(connect(&connection_manager, session_b).await);
+        }
+
+        // Session A's channel only carries session A's subscribers
+        assert_eq!(connection_manager.session_subscriber_count(&session_a).await, 2);
+        assert_eq!(connection_manager.session_subscriber_count(&session_b).await, 200);
+
+        for i in 0..100 {
+            connection_manager
+                .broadcast_to_session(session_a, format!("update {}", i), Some(&sender_id))
+                .await;
+        }
+
+        for i in 0..100 {
+            let message = tokio::time::timeout(Duration::from_secs(5), peer_rx.recv())
+                .await
+                .expect("Timed out waiting for broadcast")
+                .expect("Connection channel closed");
+            assert_eq!(message, Message::Text(format!("update {}", i)));
+        }
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(sender_rx.try_recv().is_err());
+        for (_, rx) in &mut session_b_connections {
+            assert!(rx.try_recv().is_err());
+        }
+
+        // Disconnecting unsubscribes, and the last member drops the channel
+        connection_manager.remove_connection(&sender_id).await;
+        assert_eq!(connection_manager.session_subscriber_count(&session_a).await, 1);
+        for (user_id, _) in &session_b_connections {
+            connection_manager.remove_connection(user_id).await;
+        }
+        assert_eq!(connection_manager.session_subscriber_count(&session_b).await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_lagging_forwarder_closes_connection() {
+        let (channel, receiver) = broadcast::channel(2);
+        for i in 0..5 {
+            channel
+                .send(SessionBroadcast {
+                    frame: Arc::new(SharedFrame::new(Message::Text(format!("update {}", i)))),
+                    exclude_user: None,
+                    location_of: None,
+                })
+                .unwrap();
+        }
+
+        let (tx, mut rx) = outbox::channel(64);
+        let forwarder = spawn_session_forwarder("slow".to_string(), receiver, tx, Default::default(), Default::default());
+        tokio::time::timeout(Duration::from_secs(5), forwarder)
+            .await
+            .expect("Lagging forwarder kept running")
+            .unwrap();
+
+        // The client is told to reconnect instead of silently missing events
+        let Ok(Message::Text(text)) = rx.try_recv() else {
+            panic!("Expected an error before the close frame");
+        };
+        let shared::WebSocketMessage::Error(error) = serde_json::from_str(&text).unwrap() else {
+            panic!("Expected an error, got {}", text);
+        };
+        assert_eq!(error.code, "SERVICE_UNAVAILABLE");
+        let Ok(Message::Close(Some(frame))) = rx.try_recv() else {
+            panic!("Expected a close frame");
+        };
+        assert_eq!(u16::from(frame.code), 1013);
+    }
+
+    /// Accept WebSocket connections on an ephemeral port
+    async fn spawn_server(connection_manager: &ConnectionManager, config: &Arc<AppConfig>) -> (SocketAddr, JoinHandle<()>) {
+        spawn_server_with_tls(connection_manager, config, None).await
+    }
+
+    /// Accept WebSocket connections on an ephemeral port, over TLS if given
+    async fn spawn_server_with_tls(
+        connection_manager: &ConnectionManager,
+        config: &Arc<AppConfig>,
+        tls: Option<TlsAcceptor>,
+    ) -> (SocketAddr, JoinHandle<()>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let server_addr = listener.local_addr().unwrap();
+        let connection_manager = connection_manager.clone();
+        let config = Arc::clone(config);
+        let server = tokio::spawn(async move {
+            while let Ok((stream, addr)) = listener.accept().await {
+                let connection_manager = connection_manager.clone();
+                let config = Arc::clone(&config);
+                tokio::spawn(handle_stream(stream, addr, tls.clone(), connection_manager, config));
+            }
+        });
+
+        (server_addr, server)
+    }
+
+    /// Connection URL carrying a token for `user_id` in `session_id`
+    fn ws_url(server_addr: SocketAddr, config: &AppConfig, user_id: &str, session_id: Uuid) -> String {
+        let now = chrono::Utc::now().timestamp();
+        let claims = shared::JwtClaims {
+            sub: user_id.to_string(),
+            session_id,
+            exp: now + 3600,
+            iat: now,
+            precision: Default::default(),
+            role: Default::default(),
+            iss: None,
+            aud: None,
+        };
+        let token = jsonwebtoken::encode(
+            &jsonwebtoken::Header::default(),
+            &claims,
+            &jsonwebtoken::EncodingKey::from_secret(config.jwt.secret.as_ref()),
+        )
+        .unwrap();
+        format!("ws://{}/ws?token={}", server_addr, token)
+    }
+
+    #[tokio::test]
+    async fn test_connection_cap_refuses_excess_handshakes() {
+        let mut config = AppConfig::default();
+        config.app.max_total_connections = 1;
+        let config = Arc::new(config);
+
+        let redis_url = std::env::var("TEST_REDIS_URL")
+            .unwrap_or_else(|_| "redis://localhost:6379".to_string());
+        let redis = RedisClient::new(&redis_url)
+            .await
+            .expect("Failed to connect to test Redis");
+        let connection_manager = ConnectionManager::new(redis, Arc::clone(&config));
+
+        let (server_addr, server) = spawn_server(&connection_manager, &config).await;
+
+        let session_id = Uuid::new_v4();
+        let url_for = |user_id: &str| ws_url(server_addr, &config, user_id, session_id);
+
+        let (first, _) = tokio_tungstenite::connect_async(url_for("first-user"))
+            .await
+            .expect("First connection should be accepted");
+        assert_eq!(connection_manager.connection_count(), 1);
+
+        match tokio_tungstenite::connect_async(url_for("second-user")).await {
+            Err(tokio_tungstenite::tungstenite::Error::Http(response)) => {
+                assert_eq!(response.status(), 503);
+            }
+            other => panic!("Expected the second handshake to be refused, got {:?}", other.map(|_| ())),
+        }
+
+        // Closing the first connection frees its slot
+        drop(first);
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while connection_manager.connection_count() > 0 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("Slot was not released");
+
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_upgrades_only_on_configured_path() {
+        let mut config = AppConfig::default();
+        config.app.ws_path = "/location/ws".to_string();
+        let config = Arc::new(config);
+        let connection_manager = test_connection_manager().await;
+        let (server_addr, server) = spawn_server(&connection_manager, &config).await;
+        let session_id = Uuid::new_v4();
+
+        let url = ws_url(server_addr, &config, "routed-user", session_id).replace("/ws?", "/location/ws?");
+        let (client, _) = tokio_tungstenite::connect_async(url)
+            .await
+            .expect("Configured path should be upgraded");
+        drop(client);
+
+        // Refused before the token is looked at
+        for url in [
+            ws_url(server_addr, &config, "routed-user", session_id),
+            format!("ws://{}/location/ws/other", server_addr),
+        ] {
+            match tokio_tungstenite::connect_async(url).await {
+                Err(tokio_tungstenite::tungstenite::Error::Http(response)) => {
+                    assert_eq!(response.status(), 404);
+                }
+                other => panic!("Expected the handshake to be refused, got {:?}", other.map(|_| ())),
+            }
+        }
+
+        server.abort();
+    }
+
+    /// Next frame received by a test client
+    async fn next_frame<S>(stream: &mut S) -> Message
+    where
+        S: futures_util::Stream<Item = Result<Message, tokio_tungstenite::tungstenite::Error>> + Unpin,
+    {
+        tokio::time::timeout(Duration::from_secs(5), stream.next())
+            .await
+            .expect("Timed out waiting for a frame")
+            .expect("Connection closed")
+            .expect("WebSocket error")
+    }
+
+    /// Wait past any participant counts for the error sent ahead of a close
+    /// frame, returning its code and the close code
+    async fn closing_error<S>(stream: &mut S) -> (String, u16)
+    where
+        S: futures_util::Stream<Item = Result<Message, tokio_tungstenite::tungstenite::Error>> + Unpin,
+    {
+        let code = loop {
+            let Message::Text(text) = next_frame(stream).await else {
+                panic!("Expected a text frame");
+            };
+            if let shared::WebSocketMessage::Error(data) = serde_json::from_str(&text).unwrap() {
+                break data.code;
+            }
+        };
+        let Message::Close(Some(frame)) = next_frame(stream).await else {
+            panic!("Expected a close frame");
+        };
+        (code, u16::from(frame.code))
+    }
+
+    #[tokio::test]
+    async fn test_second_connection_replaces_first() {
+        let config = Arc::new(AppConfig::default());
+        let connection_manager = test_connection_manager().await;
+        let (server_addr, server) = spawn_server(&connection_manager, &config).await;
+        let session_id = Uuid::new_v4();
+        let url = ws_url(server_addr, &config, "two-tabs", session_id);
+
+        let (mut first, _) = tokio_tungstenite::connect_async(&url).await.unwrap();
+        let (mut second, _) = tokio_tungstenite::connect_async(&url).await.unwrap();
+
+        assert_eq!(closing_error(&mut first).await, ("REPLACED".to_string(), 4009));
+        drop(first);
+
+        // The first connection going away leaves the second registered
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while connection_manager.connection_count() > 1 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("Replaced connection was not released");
+        let participants = connection_manager.redis.get_session_participants(&session_id).await.unwrap();
+        assert_eq!(participants, vec!["two-tabs".to_string()]);
+
+        second.send(Message::Text(r#"{"type":"ping"}"#.to_string())).await.unwrap();
+        loop {
+            let Message::Text(text) = next_frame(&mut second).await else {
+                panic!("Expected a text frame");
+            };
+            if text.contains(r#""type":"pong""#) {
+                break;
+            }
+        }
+        assert!(connection_manager.get_connection("two-tabs").await.is_some());
+
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_single_connection_per_user_refuses_second() {
+        let mut config = AppConfig::default();
+        config.app.single_connection_per_user = true;
+        let config = Arc::new(config);
+
+        let redis_url = std::env::var("TEST_REDIS_URL")
+            .unwrap_or_else(|_| "redis://localhost:6379".to_string());
+        let redis = RedisClient::new(&redis_url)
+            .await
+            .expect("Failed to connect to test Redis");
+        let connection_manager = ConnectionManager::new(redis, Arc::clone(&config));
+        let (server_addr, server) = spawn_server(&connection_manager, &config).await;
+        let url = ws_url(server_addr, &config, "one-tab", Uuid::new_v4());
+
+        let (mut first, _) = tokio_tungstenite::connect_async(&url).await.unwrap();
+        let (mut second, _) = tokio_tungstenite::connect_async(&url).await.unwrap();
+
+        assert_eq!(closing_error(&mut second).await, ("ALREADY_CONNECTED".to_string(), 4009));
+
+        // The existing connection carries on
+        first.send(Message::Text(r#"{"type":"ping"}"#.to_string())).await.unwrap();
+        loop {
+            let Message::Text(text) = next_frame(&mut first).await else {
+                panic!("Expected a text frame");
+            };
+            if text.contains(r#""type":"pong""#) {
+                break;
+            }
+        }
+        assert!(connection_manager.get_connection("one-tab").await.is_some());
+
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_msgpack_client_round_trips_location_broadcast() {
+        use shared::{FrameEncoding, HelloData, LocationUpdateData, WebSocketMessage};
+
+        let config = Arc::new(AppConfig::default());
+        let connection_manager = test_connection_manager().await;
+        let (server_addr, server) = spawn_server(&connection_manager, &config).await;
+        let session_id = Uuid::new_v4();
+
+        let hello = |encoding| {
+            WebSocketMessage::Hello(HelloData {
+                client_version: "test-client/1.0".to_string(),
+                protocol_version: 1,
+                encoding,
+            })
+        };
+
+        // The msgpack client says hello in msgpack and is welcomed in kind
+        let (mut msgpack_client, _) = tokio_tungstenite::connect_async(ws_url(server_addr, &config, "msgpack-user", session_id))
+            .await
+            .unwrap();
+        let hello_frame = rmp_serde::to_vec_named(&hello(FrameEncoding::Msgpack)).unwrap();
+        msgpack_client.send(Message::Binary(hello_frame)).await.unwrap();
+        let Message::Binary(bytes) = next_frame(&mut msgpack_client).await else {
+            panic!("Expected a binary welcome");
+        };
+        match rmp_serde::from_slice(&bytes).unwrap() {
+            WebSocketMessage::Welcome(data) => assert_eq!(data.encoding, FrameEncoding::Msgpack),
+            other => panic!("Expected Welcome, got {:?}", other),
+        }
+
+        // JSON stays the default for everyone else
+        let (mut json_client, _) = tokio_tungstenite::connect_async(ws_url(server_addr, &config, "json-user", session_id))
+            .await
+            .unwrap();
+        let hello_text = serde_json::to_string(&hello(FrameEncoding::Json)).unwrap();
+        json_client.send(Message::Text(hello_text)).await.unwrap();
+        assert!(matches!(next_frame(&mut json_client).await, Message::Text(_)));
+
+        let update = WebSocketMessage::LocationUpdate(LocationUpdateData {
+            lat: 51.5,
+            lng: -0.12,
+            accuracy: 5.0,
+            timestamp: chrono::Utc::now(),
+            seq: None,
+        });
+        json_client.send(Message::Text(serde_json::to_string(&update).unwrap())).await.unwrap();
+
+        let broadcast = loop {
+            let Message::Binary(bytes) = next_frame(&mut msgpack_client).await else {
+                panic!("Expected a binary frame");
+            };
+            match rmp_serde::from_slice(&bytes).unwrap() {
+                WebSocketMessage::LocationBroadcast(data) => break data,
+                // Participant counts may arrive first
+                _ => continue,
+            }
+        };
+        assert_eq!(broadcast.user_id, "json-user");
+        assert_eq!((broadcast.lat, broadcast.lng), (51.5, -0.12));
+
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_tls_listener_serves_wss_and_refuses_plaintext() {
+        use tokio_rustls::rustls::{self, Certificate, RootCertStore, ServerName};
+
+        // Self-signed certificate for localhost
+        let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let dir = std::env::temp_dir().join(format!("ws-tls-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let cert_path = dir.join("cert.pem");
+        let key_path = dir.join("key.pem");
+        std::fs::write(&cert_path, cert.serialize_pem().unwrap()).unwrap();
+        std::fs::write(&key_path, cert.serialize_private_key_pem()).unwrap();
+
+        let mut config = AppConfig::default();
+        config.server.ws_tls_cert_path = Some(cert_path.to_string_lossy().into_owned());
+        config.server.ws_tls_key_path = Some(key_path.to_string_lossy().into_owned());
+        let config = Arc::new(config);
+        let tls = tls_acceptor(&config.server).unwrap().expect("TLS should be enabled");
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let connection_manager = test_connection_manager().await;
+        let (server_addr, server) = spawn_server_with_tls(&connection_manager, &config, Some(tls)).await;
+        let session_id = Uuid::new_v4();
+
+        // A client trusting the certificate completes the TLS and WebSocket handshakes
+        let mut roots = RootCertStore::empty();
+        roots.add(&Certificate(cert.serialize_der().unwrap())).unwrap();
+        let client_config = rustls::ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        let tcp = TcpStream::connect(server_addr).await.unwrap();
+        let tls_stream = tokio_rustls::TlsConnector::from(Arc::new(client_config))
+            .connect(ServerName::try_from("localhost").unwrap(), tcp)
+            .await
+            .expect("TLS handshake failed");
+        let url = ws_url(server_addr, &config, "tls-user", session_id).replace("ws://", "wss://");
+        let (mut client, _) = tokio_tungstenite::client_async(url, tls_stream).await.unwrap();
+
+        client.send(Message::Text(r#"{"type":"ping"}"#.to_string())).await.unwrap();
+        // Participant counts may arrive before the pong
+        loop {
+            let Message::Text(text) = next_frame(&mut client).await else {
+                panic!("Expected a text frame");
+            };
+            if text.contains(r#""type":"pong""#) {
+                break;
+            }
+        }
+
+        // Plain ws:// gets no WebSocket handshake
+        let plaintext = tokio_tungstenite::connect_async(ws_url(server_addr, &config, "plain-user", session_id));
+        let result = tokio::time::timeout(Duration::from_secs(5), plaintext)
+            .await
+            .expect("Plaintext handshake hung");
+        assert!(result.is_err());
+
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_oversized_message_closes_connection() {
+        let mut config = AppConfig::default();
+        config.app.max_ws_message_bytes = 1024;
+        config.app.max_ws_frame_bytes = 1024;
+        let config = Arc::new(config);
+        let connection_manager = test_connection_manager().await;
+        let (server_addr, server) = spawn_server(&connection_manager, &config).await;
+
+        let (mut client, _) = tokio_tungstenite::connect_async(ws_url(server_addr, &config, "big-sender", Uuid::new_v4()))
+            .await
+            .unwrap();
+        let oversized = format!(r#"{{"type":"ping","padding":"{}"}}"#, "x".repeat(4096));
+        client.send(Message::Text(oversized)).await.unwrap();
+
+        // Participant counts may arrive before the close
+        let close = loop {
+            if let Message::Close(close) = next_frame(&mut client).await {
+                break close.expect("Close frame without a reason");
+            }
+        };
+        assert_eq!(close.code, CloseCode::Size);
+        assert!(close.reason.contains("1024"), "Unexpected reason {}", close.reason);
+
+        // The server is still serving other clients
+        let (mut other, _) = tokio_tungstenite::connect_async(ws_url(server_addr, &config, "small-sender", Uuid::new_v4()))
+            .await
+            .unwrap();
+        other.send(Message::Text(r#"{"type":"ping"}"#.to_string())).await.unwrap();
+        loop {
+            let Message::Text(text) = next_frame(&mut other).await else {
+                panic!("Expected a text frame");
+            };
+            if text.contains(r#""type":"pong""#) {
+                break;
+            }
+        }
+
+        server.abort();
+    }
+}
@@ -1,8 +1,8 @@
 use redis::{
     aio::{ConnectionManager, PubSub},
-    AsyncCommands, RedisResult,
+    AsyncCommands,
 };
-use shared::{AppResult, Constants, Location, RedisKeys};
+use shared::{check_publish_size, AppResult, Constants, Location, RedisKeys, SessionInfoData, StatusBroadcastData, WaypointBroadcastData};
 use serde_json;
 use tracing::{debug, info};
 use uuid::Uuid;
@@ -10,7 +10,12 @@ use uuid::Uuid;
 /// Redis client for WebSocket server operations
 #[derive(Clone)]
 pub struct RedisClient {
+    client: redis::Client,
     connection: ConnectionManager,
+    keys: RedisKeys,
+    max_publish_bytes: usize,
+    history_max_points: usize,
+    history_ttl_seconds: u64,
 }
 
 impl RedisClient {
@@ -19,10 +24,43 @@ impl RedisClient {
         info!("Connecting to Redis...");
         
         let client = redis::Client::open(redis_url)?;
-        let connection = ConnectionManager::new(client).await?;
+        let connection = ConnectionManager::new(client.clone()).await?;
         
         info!("Successfully connected to Redis");
-        Ok(Self { connection })
+        Ok(Self {
+            client,
+            connection,
+            keys: RedisKeys::default(),
+            max_publish_bytes: usize::MAX,
+            history_max_points: Constants::LOCATION_HISTORY_MAX_POINTS,
+            history_ttl_seconds: Constants::LOCATION_HISTORY_TTL_SECONDS,
+        })
+    }
+
+    /// Namespace every key and channel under `prefix`
+    pub fn with_key_prefix(mut self, prefix: &str) -> Self {
+        self.keys = RedisKeys::new(prefix);
+        self
+    }
+
+    /// Refuse to publish messages larger than `max_bytes`, rather than
+    /// leaving Redis and every subscriber to handle them
+    pub fn with_max_publish_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_publish_bytes = max_bytes;
+        self
+    }
+
+    /// Keep at most `max_points` locations in each participant's history,
+    /// expiring it `ttl_seconds` after their last update
+    pub fn with_location_history_limits(mut self, max_points: usize, ttl_seconds: u64) -> Self {
+        self.history_max_points = max_points;
+        self.history_ttl_seconds = ttl_seconds;
+        self
+    }
+
+    /// Key builders for this client's namespace
+    pub fn keys(&self) -> &RedisKeys {
+        &self.keys
     }
 
     /// Store location data with TTL
@@ -33,16 +71,43 @@ impl RedisClient {
         location: &Location,
     ) -> AppResult<()> {
         let mut conn = self.connection.clone();
-        let key = RedisKeys::location(session_id, user_id);
+        let key = self.keys.location(session_id, user_id);
         let value = serde_json::to_string(location)?;
         
         // Store location with TTL
-        conn.set_ex(&key, &value, Constants::LOCATION_TTL_SECONDS as u64).await?;
+        let _: () = conn.set_ex(&key, &value, Constants::LOCATION_TTL_SECONDS as u64).await?;
         
         debug!("Stored location for user {} in session {}", user_id, session_id);
         Ok(())
     }
 
+    /// Append a location to the participant's history, keeping only the most
+    /// recent points
+    pub async fn append_location_history(
+        &self,
+        session_id: &Uuid,
+        user_id: &str,
+        location: &Location,
+    ) -> AppResult<()> {
+        let mut conn = self.connection.clone();
+        let key = self.keys.location_history(session_id, user_id);
+        let value = serde_json::to_string(location)?;
+        let max_points = self.history_max_points as isize;
+        
+        let _: () = redis::pipe()
+            .rpush(&key, &value)
+            .ignore()
+            .ltrim(&key, -max_points, -1)
+            .ignore()
+            .expire(&key, self.history_ttl_seconds as i64)
+            .ignore()
+            .query_async(&mut conn)
+            .await?;
+        
+        debug!("Appended location history for user {} in session {}", user_id, session_id);
+        Ok(())
+    }
+
     /// Get location data for a user
     pub async fn get_location(
         &self,
@@ -50,7 +115,7 @@ impl RedisClient {
         user_id: &str,
     ) -> AppResult<Option<Location>> {
         let mut conn = self.connection.clone();
-        let key = RedisKeys::location(session_id, user_id);
+        let key = self.keys.location(session_id, user_id);
         
         let value: Option<String> = conn.get(&key).await?;
         
@@ -69,16 +134,16 @@ impl RedisClient {
         session_id: &Uuid,
     ) -> AppResult<Vec<(String, Location)>> {
         let mut conn = self.connection.clone();
-        let pattern = format!("locations:{}:*", session_id);
+        let prefix = self.keys.location(session_id, "");
         
-        let keys: Vec<String> = conn.keys(&pattern).await?;
+        let keys: Vec<String> = conn.keys(format!("{}*", prefix)).await?;
         let mut locations = Vec::new();
         
         for key in keys {
             if let Ok(Some(value)) = conn.get::<_, Option<String>>(&key).await {
                 if let Ok(location) = serde_json::from_str::<Location>(&value) {
                     // Extract user_id from key (format: locations:{session_id}:{user_id})
-                    if let Some(user_id) = key.split(':').nth(2) {
+                    if let Some(user_id) = key.strip_prefix(&prefix) {
                         locations.push((user_id.to_string(), location));
                     }
                 }
@@ -88,6 +153,118 @@ impl RedisClient {
         Ok(locations)
     }
 
+    /// Store a participant's status, expiring after `STATUS_TTL_SECONDS`
+    pub async fn store_status(
+        &self,
+        session_id: &Uuid,
+        status: &StatusBroadcastData,
+    ) -> AppResult<()> {
+        let mut conn = self.connection.clone();
+        let key = self.keys.status(session_id, &status.user_id);
+        let value = serde_json::to_string(status)?;
+        
+        let _: () = conn.set_ex(&key, &value, Constants::STATUS_TTL_SECONDS).await?;
+        
+        debug!("Stored status for user {} in session {}", status.user_id, session_id);
+        Ok(())
+    }
+
+    /// Get all current statuses for a session
+    pub async fn get_session_statuses(
+        &self,
+        session_id: &Uuid,
+    ) -> AppResult<Vec<StatusBroadcastData>> {
+        let mut conn = self.connection.clone();
+        let pattern = self.keys.status(session_id, "*");
+        
+        let keys: Vec<String> = conn.keys(pattern).await?;
+        let mut statuses = Vec::new();
+        
+        for key in keys {
+            if let Ok(Some(value)) = conn.get::<_, Option<String>>(&key).await {
+                if let Ok(status) = serde_json::from_str::<StatusBroadcastData>(&value) {
+                    statuses.push(status);
+                }
+            }
+        }
+        
+        Ok(statuses)
+    }
+
+    /// Store a participant's waypoint, replacing any they set before
+    ///
+    /// The session's waypoints expire together, `WAYPOINT_TTL_SECONDS` after
+    /// the latest one was set.
+    pub async fn store_waypoint(
+        &self,
+        session_id: &Uuid,
+        waypoint: &WaypointBroadcastData,
+    ) -> AppResult<()> {
+        let mut conn = self.connection.clone();
+        let key = self.keys.session_waypoints(session_id);
+        let value = serde_json::to_string(waypoint)?;
+        
+        let _: () = redis::pipe()
+            .hset(&key, &waypoint.user_id, &value)
+            .ignore()
+            .expire(&key, Constants::WAYPOINT_TTL_SECONDS as i64)
+            .ignore()
+            .query_async(&mut conn)
+            .await?;
+        
+        debug!("Stored waypoint for user {} in session {}", waypoint.user_id, session_id);
+        Ok(())
+    }
+
+    /// Remove a participant's waypoint, returning whether they had one
+    pub async fn remove_waypoint(&self, session_id: &Uuid, user_id: &str) -> AppResult<bool> {
+        let mut conn = self.connection.clone();
+        let key = self.keys.session_waypoints(session_id);
+        
+        let removed: usize = conn.hdel(&key, user_id).await?;
+        Ok(removed > 0)
+    }
+
+    /// Get all active waypoints for a session
+    pub async fn get_session_waypoints(
+        &self,
+        session_id: &Uuid,
+    ) -> AppResult<Vec<WaypointBroadcastData>> {
+        let mut conn = self.connection.clone();
+        let key = self.keys.session_waypoints(session_id);
+        
+        let values: Vec<(String, String)> = conn.hgetall(&key).await?;
+        let waypoints = values
+            .into_iter()
+            .filter_map(|(_, value)| serde_json::from_str::<WaypointBroadcastData>(&value).ok())
+            .collect();
+        
+        Ok(waypoints)
+    }
+
+    /// Cache a session's info for clients asking for it, expiring after `ttl_seconds`
+    pub async fn cache_session_info(
+        &self,
+        session_id: &Uuid,
+        info: &SessionInfoData,
+        ttl_seconds: u64,
+    ) -> AppResult<()> {
+        let mut conn = self.connection.clone();
+        let value = serde_json::to_string(info)?;
+        
+        let _: () = conn.set_ex(self.keys.session_info(session_id), value, ttl_seconds).await?;
+        Ok(())
+    }
+
+    /// Get a session's cached info, written by the API server when the
+    /// session is created or updated
+    pub async fn get_cached_session_info(&self, session_id: &Uuid) -> AppResult<Option<SessionInfoData>> {
+        let mut conn = self.connection.clone();
+        
+        let value: Option<String> = conn.get(self.keys.session_info(session_id)).await?;
+        Ok(value.and_then(|value| serde_json::from_str(&value).ok()))
+    }
+
     /// Add user to session participants set
     pub async fn add_to_session_participants(
         &self,
@@ -95,9 +272,9 @@ impl RedisClient {
         user_id: &str,
     ) -> AppResult<()> {
         let mut conn = self.connection.clone();
-        let key = RedisKeys::session_participants(session_id);
+        let key = self.keys.session_participants(session_id);
         
-        conn.sadd(&key, user_id).await?;
+        let _: () = conn.sadd(&key, user_id).await?;
         
         debug!("Added user {} to session {} participants", user_id, session_id);
         Ok(())
@@ -110,9 +287,9 @@ impl RedisClient {
         user_id: &str,
     ) -> AppResult<()> {
         let mut conn = self.connection.clone();
-        let key = RedisKeys::session_participants(session_id);
+        let key = self.keys.session_participants(session_id);
         
-        conn.srem(&key, user_id).await?;
+        let _: () = conn.srem(&key, user_id).await?;
         
         debug!("Removed user {} from session {} participants", user_id, session_id);
         Ok(())
@@ -121,18 +298,129 @@ impl RedisClient {
     /// Get all participants for a session
     pub async fn get_session_participants(&self, session_id: &Uuid) -> AppResult<Vec<String>> {
         let mut conn = self.connection.clone();
-        let key = RedisKeys::session_participants(session_id);
+        let key = self.keys.session_participants(session_id);
         
         let participants: Vec<String> = conn.smembers(&key).await?;
         Ok(participants)
     }
 
+    /// Count the participants of a session
+    pub async fn get_session_participant_count(&self, session_id: &Uuid) -> AppResult<usize> {
+        let mut conn = self.connection.clone();
+        let key = self.keys.session_participants(session_id);
+        
+        let count: usize = conn.scard(&key).await?;
+        Ok(count)
+    }
+
+    /// Check whether a user is a participant of a session
+    pub async fn is_session_participant(&self, session_id: &Uuid, user_id: &str) -> AppResult<bool> {
+        let mut conn = self.connection.clone();
+        let key = self.keys.session_participants(session_id);
+        
+        let is_member: bool = conn.sismember(&key, user_id).await?;
+        Ok(is_member)
+    }
+
+    /// Announce a participant's departure to their session, unless it
+    /// already was; see [`shared::announce_participant_left`]
+    pub async fn announce_participant_left(&self, session_id: &Uuid, user_id: &str) -> AppResult<bool> {
+        let mut conn = self.connection.clone();
+        shared::announce_participant_left(&mut conn, &self.keys, session_id, user_id).await
+    }
+
+    /// Record that a session's expiry warning has been sent, keeping the
+    /// marker for `ttl_seconds`
+    ///
+    /// Returns `true` only for the first caller, across every instance
+    /// sharing this Redis.
+    pub async fn mark_expiry_warned(&self, session_id: &Uuid, ttl_seconds: u64) -> AppResult<bool> {
+        let mut conn = self.connection.clone();
+        let key = self.keys.session_expiry_warned(session_id);
+        
+        let created: Option<String> = redis::cmd("SET")
+            .arg(&key)
+            .arg(1)
+            .arg("NX")
+            .arg("EX")
+            .arg(ttl_seconds.max(1))
+            .query_async(&mut conn)
+            .await?;
+        
+        Ok(created.is_some())
+    }
+
+    /// Clear a participant's departure tombstone so a later leave is announced
+    pub async fn clear_participant_left(&self, session_id: &Uuid, user_id: &str) -> AppResult<()> {
+        let mut conn = self.connection.clone();
+        shared::clear_participant_left(&mut conn, &self.keys, session_id, user_id).await
+    }
+
+    /// Make `follower_id` follow `target_id`, returning the target's
+    /// follower count
+    ///
+    /// A user follows one participant at a time, so callers unfollow
+    /// whoever they followed before.
+    pub async fn follow(&self, session_id: &Uuid, follower_id: &str, target_id: &str) -> AppResult<usize> {
+        let mut conn = self.connection.clone();
+        let following_key = self.keys.following(session_id, follower_id);
+        let followers_key = self.keys.followers(session_id, target_id);
+        
+        let (count,): (usize,) = redis::pipe()
+            .set_ex(&following_key, target_id, Constants::FOLLOW_TTL_SECONDS)
+            .ignore()
+            .sadd(&followers_key, follower_id)
+            .ignore()
+            .expire(&followers_key, Constants::FOLLOW_TTL_SECONDS as i64)
+            .ignore()
+            .scard(&followers_key)
+            .query_async(&mut conn)
+            .await?;
+        
+        debug!("User {} follows {} in session {}", follower_id, target_id, session_id);
+        Ok(count)
+    }
+
+    /// Stop `follower_id` following anyone
+    ///
+    /// Returns the user they followed and that user's remaining follower
+    /// count, or `None` if they weren't following anyone.
+    pub async fn unfollow(&self, session_id: &Uuid, follower_id: &str) -> AppResult<Option<(String, usize)>> {
+        let mut conn = self.connection.clone();
+        let following_key = self.keys.following(session_id, follower_id);
+        
+        let Some(target_id) = conn.get::<_, Option<String>>(&following_key).await? else {
+            return Ok(None);
+        };
+        let followers_key = self.keys.followers(session_id, &target_id);
+        let (count,): (usize,) = redis::pipe()
+            .del(&following_key)
+            .ignore()
+            .srem(&followers_key, follower_id)
+            .ignore()
+            .scard(&followers_key)
+            .query_async(&mut conn)
+            .await?;
+        
+        debug!("User {} unfollowed {} in session {}", follower_id, target_id, session_id);
+        Ok(Some((target_id, count)))
+    }
+
+    /// Users following a participant
+    pub async fn get_followers(&self, session_id: &Uuid, user_id: &str) -> AppResult<Vec<String>> {
+        let mut conn = self.connection.clone();
+        let key = self.keys.followers(session_id, user_id);
+        
+        let followers: Vec<String> = conn.smembers(&key).await?;
+        Ok(followers)
+    }
+
     /// Set connection mapping for a user
     pub async fn set_connection(&self, user_id: &str, session_id: &Uuid) -> AppResult<()> {
         let mut conn = self.connection.clone();
-        let key = RedisKeys::connection(user_id);
+        let key = self.keys.connection(user_id);
         
-        conn.set(&key, session_id.to_string()).await?;
+        let _: () = conn.set(&key, session_id.to_string()).await?;
         
         debug!("Set connection mapping for user {} to session {}", user_id, session_id);
         Ok(())
@@ -141,9 +429,9 @@ impl RedisClient {
     /// Remove connection mapping for a user
     pub async fn remove_connection(&self, user_id: &str) -> AppResult<()> {
         let mut conn = self.connection.clone();
-        let key = RedisKeys::connection(user_id);
+        let key = self.keys.connection(user_id);
         
-        conn.del(&key).await?;
+        let _: () = conn.del(&key).await?;
         
         debug!("Removed connection mapping for user {}", user_id);
         Ok(())
@@ -152,10 +440,10 @@ impl RedisClient {
     /// Update session activity timestamp
     pub async fn update_session_activity(&self, session_id: &Uuid) -> AppResult<()> {
         let mut conn = self.connection.clone();
-        let key = RedisKeys::session_activity(session_id);
+        let key = self.keys.session_activity(session_id);
         let timestamp = chrono::Utc::now().timestamp();
         
-        conn.set(&key, timestamp).await?;
+        let _: () = conn.set(&key, timestamp).await?;
         
         debug!("Updated activity for session {}", session_id);
         Ok(())
@@ -167,24 +455,41 @@ impl RedisClient {
         session_id: &Uuid,
         message: &str,
     ) -> AppResult<()> {
+        check_publish_size(message, self.max_publish_bytes)?;
         let mut conn = self.connection.clone();
-        let channel = RedisKeys::session_channel(session_id);
+        let channel = self.keys.session_channel(session_id);
         
-        conn.publish(&channel, message).await?;
+        let _: () = conn.publish(&channel, message).await?;
         
         debug!("Published message to session {} channel", session_id);
         Ok(())
     }
 
+    /// Publish a message for one participant, delivered by whichever instance holds their connection
+    pub async fn publish_to_participant(
+        &self,
+        session_id: &Uuid,
+        user_id: &str,
+        message: &str,
+    ) -> AppResult<()> {
+        check_publish_size(message, self.max_publish_bytes)?;
+        let mut conn = self.connection.clone();
+        let channel = self.keys.direct_channel(session_id, user_id);
+        
+        let _: () = conn.publish(&channel, message).await?;
+        
+        debug!("Published direct message to user {} in session {}", user_id, session_id);
+        Ok(())
+    }
+
     /// Subscribe to session channels for pub/sub  
     pub async fn subscribe_to_sessions(&self) -> AppResult<PubSub> {
         // Create a new connection for pub/sub since ConnectionManager doesn't support it
-        let client = redis::Client::open("redis://localhost:6379")?; // TODO: Get this from config
-        let conn = client.get_async_connection().await?;
+        let conn = self.client.get_async_connection().await?;
         let mut pubsub = conn.into_pubsub();
         
         // Subscribe to all session channels using pattern
-        pubsub.psubscribe("channel:session:*").await?;
+        pubsub.psubscribe(self.keys.session_channel_pattern()).await?;
         
         info!("Subscribed to session channels");
         Ok(pubsub)
@@ -193,7 +498,7 @@ impl RedisClient {
     /// Clean up expired location data
     pub async fn cleanup_expired_locations(&self) -> AppResult<usize> {
         let mut conn = self.connection.clone();
-        let pattern = "locations:*";
+        let pattern = self.keys.pattern("locations:*");
         
         let keys: Vec<String> = conn.keys(&pattern).await?;
         let mut cleaned_count = 0;
@@ -213,6 +518,49 @@ impl RedisClient {
         Ok(cleaned_count)
     }
 
+    /// Trim every location history to the configured length, and expire
+    /// histories whose TTL lapsed (or outlasts the configured one, after it
+    /// was lowered) within the configured TTL
+    ///
+    /// Returns the number of histories trimmed or given a TTL.
+    pub async fn trim_location_histories(&self) -> AppResult<usize> {
+        let mut conn = self.connection.clone();
+        let pattern = self.keys.pattern("location_history:*");
+        let max_points = self.history_max_points as isize;
+        let ttl_seconds = self.history_ttl_seconds as i64;
+        
+        let keys: Vec<String> = conn.keys(&pattern).await?;
+        let mut trimmed_count = 0;
+        
+        for key in keys {
+            let (len, ttl): (usize, i64) = redis::pipe()
+                .llen(&key)
+                .ttl(&key)
+                .query_async(&mut conn)
+                .await?;
+            
+            // A TTL of -1 means the key has none; -2 that it is already gone
+            let ttl_lapsed = ttl == -1 || ttl > ttl_seconds;
+            if len <= self.history_max_points && !ttl_lapsed {
+                continue;
+            }
+            
+            let mut pipe = redis::pipe();
+            pipe.ltrim(&key, -max_points, -1).ignore();
+            if ttl_lapsed {
+                pipe.expire(&key, ttl_seconds).ignore();
+            }
+            let _: () = pipe.query_async(&mut conn).await?;
+            trimmed_count += 1;
+        }
+        
+        if trimmed_count > 0 {
+            debug!("Trimmed {} location histories", trimmed_count);
+        }
+        
+        Ok(trimmed_count)
+    }
+
     /// Get Redis connection health status
     pub async fn health_check(&self) -> AppResult<()> {
         let mut conn = self.connection.clone();
@@ -225,15 +573,15 @@ impl RedisClient {
         let mut conn = self.connection.clone();
         
         // Count active locations
-        let location_keys: Vec<String> = conn.keys("locations:*").await?;
+        let location_keys: Vec<String> = conn.keys(self.keys.pattern("locations:*")).await?;
         let active_locations = location_keys.len();
         
         // Count active sessions
-        let session_keys: Vec<String> = conn.keys("session_participants:*").await?;
+        let session_keys: Vec<String> = conn.keys(self.keys.pattern("session_participants:*")).await?;
         let active_sessions = session_keys.len();
         
         // Count active connections
-        let connection_keys: Vec<String> = conn.keys("connections:*").await?;
+        let connection_keys: Vec<String> = conn.keys(self.keys.pattern("connections:*")).await?;
         let active_connections = connection_keys.len();
         
         Ok(RedisStats {
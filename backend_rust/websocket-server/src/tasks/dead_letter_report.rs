@@ -0,0 +1,52 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+use tokio::{task::JoinHandle, time::MissedTickBehavior};
+use tracing::{info, warn};
+
+use crate::ConnectionManager;
+
+/// Spawn the periodic report of client messages the server couldn't handle
+///
+/// Logs a summary each interval in which clients sent malformed messages or
+/// message types this server doesn't handle, tallied by type over the recent
+/// samples, along with the latest sample.
+pub fn spawn_dead_letter_report_task(
+    connection_manager: ConnectionManager,
+    interval: Duration,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut reported = 0;
+
+        info!("Dead-letter report task started (interval: {:?})", interval);
+
+        loop {
+            ticker.tick().await;
+
+            let total = connection_manager.dead_letters.total();
+            if total == reported {
+                continue;
+            }
+
+            let recent = connection_manager.dead_letters.recent();
+            let mut types: BTreeMap<&str, usize> = BTreeMap::new();
+            for dead_letter in &recent {
+                *types.entry(dead_letter.message_type.as_deref().unwrap_or("<malformed>")).or_default() += 1;
+            }
+
+            warn!(
+                "Clients sent {} unhandled messages since the last report ({} total); recent types: {:?}",
+                total - reported, total, types
+            );
+            if let Some(latest) = recent.last() {
+                warn!(
+                    "Latest unhandled message from user {} at {}: {}",
+                    latest.user_id, latest.received_at, latest.sample
+                );
+            }
+
+            reported = total;
+        }
+    })
+}
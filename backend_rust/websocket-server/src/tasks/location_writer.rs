@@ -0,0 +1,69 @@
+use shared::Location;
+use sqlx::PgPool;
+use std::time::Duration;
+use tokio::{
+    sync::mpsc::{self, error::TrySendError},
+    task::JoinHandle,
+    time::Instant,
+};
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::database::postgres::insert_location_history;
+
+/// Most locations written in a single insert
+const MAX_BATCH_SIZE: usize = 500;
+
+/// Locations waiting to be written before new ones are dropped, so a slow
+/// database can't hold up location updates
+const QUEUE_CAPACITY: usize = 10_000;
+
+/// Queue of accepted locations for the durable history in Postgres
+#[derive(Clone)]
+pub struct LocationWriter {
+    sender: mpsc::Sender<(Uuid, String, Location)>,
+}
+
+impl LocationWriter {
+    /// Queue a participant's location to be written with the next batch
+    pub fn record(&self, session_id: Uuid, user_id: &str, location: &Location) {
+        match self.sender.try_send((session_id, user_id.to_string(), location.clone())) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => {
+                warn!("Location history queue full; dropped a location of user {}", user_id);
+            }
+            Err(TrySendError::Closed(_)) => error!("Location history writer has stopped"),
+        }
+    }
+}
+
+/// Spawn the writer of accepted locations into the `location_history` table
+///
+/// Locations are written in batches, each gathered for at most
+/// `flush_interval` after its first location arrives, so updates don't wait
+/// on a database round trip. A failed batch is logged and dropped; Redis
+/// still holds the recent history.
+pub fn spawn_location_writer_task(pool: PgPool, flush_interval: Duration) -> (LocationWriter, JoinHandle<()>) {
+    let (sender, mut receiver) = mpsc::channel(QUEUE_CAPACITY);
+
+    let handle = tokio::spawn(async move {
+        info!("Location history writer started (flush interval: {:?})", flush_interval);
+
+        while let Some(first) = receiver.recv().await {
+            let mut batch = vec![first];
+            let deadline = Instant::now() + flush_interval;
+            while batch.len() < MAX_BATCH_SIZE {
+                match tokio::time::timeout_at(deadline, receiver.recv()).await {
+                    Ok(Some(location)) => batch.push(location),
+                    Ok(None) | Err(_) => break,
+                }
+            }
+
+            if let Err(e) = insert_location_history(&pool, &batch).await {
+                error!("Failed to write {} locations to the durable history: {}", batch.len(), e);
+            }
+        }
+    });
+
+    (LocationWriter { sender }, handle)
+}
@@ -0,0 +1,7 @@
+pub mod cleanup;
+pub mod dead_letter_report;
+pub mod expiry_warning;
+pub mod location_writer;
+pub mod purge;
+pub mod pubsub;
+pub mod rtt_probe;
\ No newline at end of file
@@ -0,0 +1,232 @@
+use futures_util::StreamExt;
+use rand::Rng;
+use shared::AppResult;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+use crate::ConnectionManager;
+
+/// Delay before the first reconnect attempt
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Upper bound on the delay between reconnect attempts
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Spawn the Redis pub/sub subscriber that relays other instances' messages
+///
+/// The subscription is re-established with exponential backoff whenever it
+/// fails or the stream ends, so a dropped Redis connection doesn't silently
+/// stop cross-instance broadcasting.
+pub fn spawn_pubsub_task(connection_manager: ConnectionManager) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut backoff = INITIAL_BACKOFF;
+
+        loop {
+            match relay_session_messages(&connection_manager).await {
+                Ok(()) => {
+                    warn!("Redis pub/sub stream ended");
+                    backoff = INITIAL_BACKOFF;
+                }
+                Err(e) => error!("Redis pub/sub subscription failed: {}", e),
+            }
+
+            let delay = with_jitter(backoff);
+            info!("Reconnecting to Redis pub/sub in {:?}", delay);
+            tokio::time::sleep(delay).await;
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+    })
+}
+
+/// Subscribe to session channels and relay messages until the stream ends
+async fn relay_session_messages(connection_manager: &ConnectionManager) -> AppResult<()> {
+    let mut pubsub = connection_manager.redis.subscribe_to_sessions().await?;
+
+    let mut message_stream = pubsub.on_message();
+    while let Some(msg) = message_stream.next().await {
+        let channel = msg.get_channel_name().to_string();
+        let data: String = msg.get_payload().unwrap_or_default();
+
+        // Direct messages go only to their recipient, if connected here
+        let keys = connection_manager.redis.keys();
+        if let Some((session_id, user_id)) = keys.direct_target_from_channel(&channel) {
+            connection_manager.send_to_participant(session_id, &user_id, data).await;
+            continue;
+        }
+
+        // Extract session ID from channel name (format: "{prefix}channel:session:{session_id}")
+        if let Some(session_id) = keys.session_id_from_channel(&channel) {
+            connection_manager.broadcast_to_session(session_id, data, None).await;
+        }
+    }
+
+    Ok(())
+}
+
+/// Add up to 50% random jitter so instances don't reconnect in lockstep
+fn with_jitter(delay: Duration) -> Duration {
+    let jitter = rand::thread_rng().gen_range(0.0..0.5);
+    delay.mul_f64(1.0 + jitter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::websocket::ConnectionInfo;
+    use crate::redis::client::RedisClient;
+    use shared::AppConfig;
+    use std::sync::{Arc, Mutex};
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio::task::AbortHandle;
+    use tokio_tungstenite::tungstenite::Message;
+    use uuid::Uuid;
+
+    fn redis_url() -> String {
+        std::env::var("TEST_REDIS_URL").unwrap_or_else(|_| "redis://localhost:6379".to_string())
+    }
+
+    /// Proxy Redis traffic, returning the proxy URL and the handles of its
+    /// open connections so a test can drop them
+    async fn spawn_proxy() -> (String, Arc<Mutex<Vec<AbortHandle>>>) {
+        let upstream = url::Url::parse(&redis_url()).unwrap();
+        let upstream_addr = format!(
+            "{}:{}",
+            upstream.host_str().unwrap_or("localhost"),
+            upstream.port().unwrap_or(6379)
+        );
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy_addr = listener.local_addr().unwrap();
+        let connections = Arc::new(Mutex::new(Vec::new()));
+
+        let open = connections.clone();
+        tokio::spawn(async move {
+            while let Ok((mut client, _)) = listener.accept().await {
+                let mut server = TcpStream::connect(&upstream_addr).await.unwrap();
+                let handle = tokio::spawn(async move {
+                    let _ = tokio::io::copy_bidirectional(&mut client, &mut server).await;
+                });
+                open.lock().unwrap().push(handle.abort_handle());
+            }
+        });
+
+        (format!("redis://{}", proxy_addr), connections)
+    }
+
+    /// Publish to a session until the message reaches the local connection
+    async fn wait_for_relay(
+        publisher: &RedisClient,
+        session_id: &Uuid,
+        rx: &mut crate::handlers::outbox::OutboxReceiver,
+    ) -> Message {
+        tokio::time::timeout(Duration::from_secs(10), async {
+            loop {
+                publisher.publish_to_session(session_id, "relayed").await.unwrap();
+                if let Ok(Some(message)) = tokio::time::timeout(Duration::from_millis(100), rx.recv()).await {
+                    return message;
+                }
+            }
+        })
+        .await
+        .expect("Timed out waiting for relayed message")
+    }
+
+    #[tokio::test]
+    async fn test_pubsub_resubscribes_after_disconnect() {
+        let (proxy_url, proxy_connections) = spawn_proxy().await;
+        let redis = RedisClient::new(&proxy_url)
+            .await
+            .expect("Failed to connect to test Redis through proxy");
+        let publisher = RedisClient::new(&redis_url())
+            .await
+            .expect("Failed to connect to test Redis");
+        let connection_manager = ConnectionManager::new(redis, Arc::new(AppConfig::default()));
+
+        let session_id = Uuid::new_v4();
+        let user_id = Uuid::new_v4().to_string();
+        let (tx, mut rx) = crate::handlers::outbox::channel(64);
+        connection_manager
+            .add_connection(
+                user_id.clone(),
+                session_id,
+                ConnectionInfo {
+                    user_id: user_id.clone(),
+                    session_id,
+                    sender: tx,
+                    receive_paused: Default::default(),
+                    msgpack_frames: Default::default(),
+                    quality: Default::default(),
+                    connected_at: chrono::Utc::now(),
+                },
+            )
+            .await
+            .unwrap();
+
+        let handle = spawn_pubsub_task(connection_manager.clone());
+        assert_eq!(wait_for_relay(&publisher, &session_id, &mut rx).await, Message::Text("relayed".to_string()));
+
+        // Simulate Redis dropping every connection, including the subscriber's
+        for connection in proxy_connections.lock().unwrap().drain(..) {
+            connection.abort();
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        while rx.try_recv().is_ok() {}
+
+        assert_eq!(wait_for_relay(&publisher, &session_id, &mut rx).await, Message::Text("relayed".to_string()));
+        assert!(!proxy_connections.lock().unwrap().is_empty(), "Expected a new subscriber connection");
+        handle.abort();
+    }
+
+    #[tokio::test]
+    async fn test_pubsub_relays_prefixed_channels() {
+        let redis = RedisClient::new(&redis_url())
+            .await
+            .expect("Failed to connect to test Redis")
+            .with_key_prefix("tenant-a");
+        let other_tenant = RedisClient::new(&redis_url())
+            .await
+            .expect("Failed to connect to test Redis")
+            .with_key_prefix("tenant-b");
+        let publisher = redis.clone();
+        let connection_manager = ConnectionManager::new(redis, Arc::new(AppConfig::default()));
+
+        let session_id = Uuid::new_v4();
+        let user_id = Uuid::new_v4().to_string();
+        let (tx, mut rx) = crate::handlers::outbox::channel(64);
+        connection_manager
+            .add_connection(
+                user_id.clone(),
+                session_id,
+                ConnectionInfo {
+                    user_id: user_id.clone(),
+                    session_id,
+                    sender: tx,
+                    receive_paused: Default::default(),
+                    msgpack_frames: Default::default(),
+                    quality: Default::default(),
+                    connected_at: chrono::Utc::now(),
+                },
+            )
+            .await
+            .unwrap();
+
+        let handle = spawn_pubsub_task(connection_manager.clone());
+        assert_eq!(wait_for_relay(&publisher, &session_id, &mut rx).await, Message::Text("relayed".to_string()));
+
+        // Another deployment's channel for the same session isn't relayed
+        other_tenant.publish_to_session(&session_id, "other tenant").await.unwrap();
+        publisher.publish_to_session(&session_id, "after").await.unwrap();
+        let next = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                match rx.recv().await.expect("Connection channel closed") {
+                    Message::Text(text) if text == "relayed" => continue,
+                    message => return message,
+                }
+            }
+        })
+        .await
+        .expect("Timed out waiting for relayed message");
+        assert_eq!(next, Message::Text("after".to_string()));
+        handle.abort();
+    }
+}
@@ -0,0 +1,66 @@
+use chrono::Utc;
+use shared::{ProbeData, WebSocketMessage};
+use std::time::Duration;
+use tokio::{task::JoinHandle, time::MissedTickBehavior};
+use tokio_tungstenite::tungstenite::Message;
+use tracing::{debug, error, info};
+
+use crate::ConnectionManager;
+
+/// Spawn the periodic round-trip time probe of every connection
+///
+/// Each tick sends every connection a `ping_probe`, whose `pong_probe` reply
+/// updates the connection's rolling average, then logs the average across
+/// connections and the probes timed out since the last tick.
+pub fn spawn_rtt_probe_task(
+    connection_manager: ConnectionManager,
+    interval: Duration,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut reported_timeouts = 0;
+
+        info!("RTT probe task started (interval: {:?})", interval);
+
+        loop {
+            ticker.tick().await;
+
+            let connections = connection_manager.all_connections().await;
+            for connection_info in &connections {
+                let sent_at = Utc::now();
+                let nonce = connection_info.quality.start_probe(sent_at);
+                let probe = WebSocketMessage::PingProbe(ProbeData { nonce, sent_at });
+                let probe_json = match serde_json::to_string(&probe) {
+                    Ok(json) => json,
+                    Err(e) => {
+                        error!("Failed to serialize RTT probe: {}", e);
+                        continue;
+                    }
+                };
+                if let Err(e) = connection_info.sender.send(Message::Text(probe_json)) {
+                    debug!("Failed to send RTT probe to user {}: {}", connection_info.user_id, e);
+                }
+            }
+
+            let rtts: Vec<f64> = connections
+                .iter()
+                .filter_map(|connection_info| connection_info.quality.average_rtt_ms())
+                .collect();
+            let timeouts: u64 = connections
+                .iter()
+                .map(|connection_info| connection_info.quality.timeouts())
+                .sum();
+            if !rtts.is_empty() {
+                info!(
+                    "Average RTT {:.1}ms across {} of {} connections; {} probe timeouts since the last report",
+                    rtts.iter().sum::<f64>() / rtts.len() as f64,
+                    rtts.len(),
+                    connections.len(),
+                    timeouts.saturating_sub(reported_timeouts)
+                );
+            }
+            reported_timeouts = timeouts;
+        }
+    })
+}
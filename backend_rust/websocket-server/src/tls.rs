@@ -0,0 +1,54 @@
+use anyhow::{anyhow, Context};
+use shared::{AppResult, ServerConfig};
+use std::{fs::File, io::BufReader, sync::Arc};
+use tokio_rustls::{
+    rustls::{self, Certificate, PrivateKey},
+    TlsAcceptor,
+};
+
+/// Build the TLS acceptor for `wss://`, or `None` to serve plain `ws://`
+///
+/// Certificate and key paths are validated as a pair by
+/// `AppConfig::validate`, so only both or neither are set here.
+pub fn tls_acceptor(config: &ServerConfig) -> AppResult<Option<TlsAcceptor>> {
+    let (Some(cert_path), Some(key_path)) = config.ws_tls_paths() else {
+        return Ok(None);
+    };
+
+    let certs = load_certs(cert_path)?;
+    let key = load_private_key(key_path)?;
+    let tls_config = rustls::ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .context("Invalid WebSocket TLS certificate or key")?;
+
+    Ok(Some(TlsAcceptor::from(Arc::new(tls_config))))
+}
+
+/// Read a PEM certificate chain, leaf first
+fn load_certs(path: &str) -> AppResult<Vec<Certificate>> {
+    let mut reader = BufReader::new(File::open(path).with_context(|| format!("Failed to open {}", path))?);
+    let certs = rustls_pemfile::certs(&mut reader).with_context(|| format!("Failed to read {}", path))?;
+    if certs.is_empty() {
+        return Err(anyhow!("No certificates found in {}", path).into());
+    }
+
+    Ok(certs.into_iter().map(Certificate).collect())
+}
+
+/// Read the first PKCS#8, PKCS#1 or SEC1 private key in a PEM file
+fn load_private_key(path: &str) -> AppResult<PrivateKey> {
+    let mut reader = BufReader::new(File::open(path).with_context(|| format!("Failed to open {}", path))?);
+    loop {
+        match rustls_pemfile::read_one(&mut reader).with_context(|| format!("Failed to read {}", path))? {
+            Some(
+                rustls_pemfile::Item::PKCS8Key(key)
+                | rustls_pemfile::Item::RSAKey(key)
+                | rustls_pemfile::Item::ECKey(key),
+            ) => return Ok(PrivateKey(key)),
+            Some(_) => continue,
+            None => return Err(anyhow!("No private key found in {}", path).into()),
+        }
+    }
+}
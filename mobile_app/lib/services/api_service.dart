@@ -96,7 +96,7 @@ class ApiService {
     try {
       final response = await _dio.get('/sessions/$sessionId/participants');
       final data = response.data as Map<String, dynamic>;
-      final participantsList = data['participants'] as List<dynamic>;
+      final participantsList = data['items'] as List<dynamic>;
       
       return participantsList
           .map((p) => ParticipantExtension.fromApiMap(p as Map<String, dynamic>))